@@ -1,2 +1,4 @@
 #cargo test --release --package pqppvss --lib -- prot_pvss_pedcom_ibe::tests::test_share_basic --exact --show-output
 cargo test --release --package pqppvss --lib -- prot_pvss_hash_ibe::tests::test_share_basic --exact --show-output
+cargo bench --package pqppvss --bench pvss --no-default-features --features mock-ibe,parallel
+cargo bench --package pqppvss --bench pvss
@@ -0,0 +1,394 @@
+//! Criterion benchmarks of both PVSS protocols over a grid of access
+//! structures `(t, n)`: dealing, the check of one receiver, the checks of a
+//! verifier holding all receiver keys, and reconstruction. Every operation is
+//! also run with `NullIbe`, which leaves out the IBE, so that the time of the
+//! commitments and the time of the IBE can be told apart, and the IBE
+//! operations are timed on their own. The batch verification of the n
+//! dealings of a DKG by one receiver is compared with checking them one by one
+//! at `BATCH`. With the `parallel` feature and the mock IBE, dealing and
+//! checking all receivers are compared with their parallel versions at
+//! `SCALE`.
+//!
+//! The grid defaults to `GRID` and is read from `PQPPVSS_BENCH_GRID` as e.g.
+//! `"10:32,21:64"`. Besides the reports of criterion, the mean times and the
+//! sizes of the sharings are written as JSON to `PQPPVSS_BENCH_JSON`
+//! (`target/pvss-bench.json` by default), for the tables of the paper.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, BenchmarkId, Criterion};
+use pqppvss::prot_pvss_hash_ibe::{self, HashPvss};
+use pqppvss::prot_pvss_pedcom_ibe::{self, PedersenPvss};
+use pqppvss::serialization::SizeReport;
+use pqppvss::{bitcodec, PVSSError, Pvss};
+use rand::thread_rng;
+use rust_bindings::{IbeError, IbeScheme, N0};
+use serde_json::{json, Value};
+
+#[cfg(feature = "mock-ibe")]
+type Ibe = rust_bindings::mock::MockIbe;
+#[cfg(not(feature = "mock-ibe"))]
+type Ibe = rust_bindings::LatticeIbe;
+
+const GRID: &[(u64, u64)] = &[(5, 16), (10, 32), (21, 64), (42, 128), (85, 256)];
+/// access structures of the batch verification, of n dealings each
+const BATCH: &[(u64, u64)] = &[(15, 32), (31, 64)];
+/// numbers of receivers of the parallel benchmarks, with t = n/2 - 1
+#[cfg(all(feature = "parallel", feature = "mock-ibe"))]
+const SCALE: &[u64] = &[128, 256, 1024];
+const SAMPLES: usize = 10;
+const SESSION: [u8; 32] = [0u8; 32];
+const DEALER: u64 = 0;
+const SECRET: [u8; 32] = [7u8; 32];
+
+/// An IBE that does not encrypt: the protocols run with it spend their time
+/// on everything but the IBE.
+struct NullIbe;
+
+impl IbeScheme for NullIbe {
+    type MasterKeypair = ();
+    type MasterPublicKey = ();
+    type PublicKey = ();
+    type SecretKeyID = ();
+    type Ciphertext = Box<[i64; N0]>;
+
+    fn keygen() {}
+    fn master_pk(_: &()) -> &() {
+        &()
+    }
+    fn extract(_: &(), _: &[i64; N0]) {}
+    fn encrypt(msg: &[i64; N0], _: &(), _: &[i64; N0]) -> Box<[i64; N0]> {
+        Box::new(*msg)
+    }
+    fn decrypt(ct: &Box<[i64; N0]>, _: &()) -> [i64; N0] {
+        **ct
+    }
+    fn verify_key(_: &(), _: &[i64; N0], _: &()) -> bool {
+        true
+    }
+    fn public_key_to_bytes(_: &()) -> Vec<u8> {
+        Vec::new()
+    }
+    fn public_key_from_bytes(_: &[u8]) -> Result<(), IbeError> {
+        Ok(())
+    }
+    fn secret_key_to_bytes(_: &()) -> Vec<u8> {
+        Vec::new()
+    }
+    fn secret_key_from_bytes(_: &[u8]) -> Result<(), IbeError> {
+        Ok(())
+    }
+    fn ciphertext_to_bytes(ct: &Box<[i64; N0]>) -> Vec<u8> {
+        ct.iter().map(|&c| c as u8).collect()
+    }
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Box<[i64; N0]>, IbeError> {
+        let mut ct = Box::new([0i64; N0]);
+        for (c, &b) in ct.iter_mut().zip(bytes) {
+            *c = b as i64;
+        }
+        Ok(ct)
+    }
+}
+
+/// What a protocol offers beyond [`Pvss`] that the benchmarks measure.
+trait Protocol: Pvss<Error = PVSSError> {
+    const NAME: &'static str;
+
+    fn size(sharing: &Self::Sharing) -> SizeReport;
+
+    /// the checks of a verifier that holds the keys of all receivers
+    fn verify_all(
+        params: &Self::Params,
+        sharing: &Self::Sharing,
+        pub_keys: &[&<Self::Ibe as IbeScheme>::MasterPublicKey],
+        access: (u64, u64),
+        sks: &[<Self::Ibe as IbeScheme>::SecretKeyID],
+    ) -> Result<(), PVSSError>;
+}
+
+impl<I: IbeScheme> Protocol for PedersenPvss<I> {
+    const NAME: &'static str = "pedersen";
+
+    fn size(sharing: &Self::Sharing) -> SizeReport {
+        sharing.size_report()
+    }
+
+    fn verify_all(
+        params: &Self::Params,
+        sharing: &Self::Sharing,
+        _: &[&I::MasterPublicKey],
+        access: (u64, u64),
+        sks: &[I::SecretKeyID],
+    ) -> Result<(), PVSSError> {
+        prot_pvss_pedcom_ibe::verify_all_indices(params, sharing, access, sks)?.into_iter().try_for_each(|share| share.map(|_| ()))
+    }
+}
+
+impl<I: IbeScheme> Protocol for HashPvss<I> {
+    const NAME: &'static str = "hash";
+
+    fn size(sharing: &Self::Sharing) -> SizeReport {
+        sharing.size_report()
+    }
+
+    fn verify_all(
+        _: &(),
+        sharing: &Self::Sharing,
+        pub_keys: &[&I::MasterPublicKey],
+        access: (u64, u64),
+        sks: &[I::SecretKeyID],
+    ) -> Result<(), PVSSError> {
+        sks.iter().enumerate().try_for_each(|(i, sk)| prot_pvss_hash_ibe::verify(sharing, pub_keys, access, i, sk))
+    }
+}
+
+/// the time and iteration count of every sample, per protocol, access
+/// structure and benchmark
+type Samples = BTreeMap<(String, u64, u64, String), Vec<(Duration, u64)>>;
+
+/// The times of the last `SAMPLES` calls of every benchmark, which are the
+/// samples of criterion after its warm-up.
+#[derive(Default)]
+struct Recorder {
+    samples: RefCell<Samples>,
+}
+
+impl Recorder {
+    fn bench<F: FnMut()>(&self, c: &mut Criterion, protocol: &str, access: (u64, u64), name: &str, mut f: F) {
+        let key = (protocol.to_string(), access.0, access.1, name.to_string());
+        let mut group = c.benchmark_group(format!("pvss/{}", protocol));
+        group.sample_size(SAMPLES);
+        group.bench_function(BenchmarkId::new(name, format!("t: {}, n: {}", access.0, access.1)), |b| {
+            b.iter_custom(|iters| {
+                let start = Instant::now();
+                for _ in 0..iters {
+                    f();
+                }
+                let elapsed = start.elapsed();
+                self.samples.borrow_mut().entry(key.clone()).or_default().push((elapsed, iters));
+                elapsed
+            })
+        });
+        group.finish();
+    }
+
+    /// mean time per call in nanoseconds
+    fn mean(&self, protocol: &str, access: (u64, u64), name: &str) -> Option<f64> {
+        let samples = self.samples.borrow();
+        let samples = samples.get(&(protocol.to_string(), access.0, access.1, name.to_string()))?;
+        let last = &samples[samples.len().saturating_sub(SAMPLES)..];
+        let (total, iters) = last.iter().fold((Duration::ZERO, 0), |(d, i), &(dd, ii)| (d + dd, i + ii));
+        Some(total.as_nanos() as f64 / iters as f64)
+    }
+}
+
+/// Benchmarks protocol `P` and its run `Q` without IBE at `access`, and
+/// returns the row of the JSON report.
+fn bench_protocol<P, Q>(c: &mut Criterion, recorder: &Recorder, keys: &[<Ibe as IbeScheme>::MasterKeypair], access: (u64, u64)) -> Value
+where
+    P: Protocol<Ibe = Ibe>,
+    Q: Protocol<Ibe = NullIbe, Params = P::Params>,
+    P::DecryptedShare: Clone,
+{
+    let (t, n) = access;
+    let params = P::setup();
+    let keys = &keys[..n as usize];
+    let pub_keys: Vec<_> = keys.iter().map(Ibe::master_pk).collect();
+    let null_keys = vec![&(); n as usize];
+    let name = P::NAME;
+
+    let sharing = P::deal(&params, &pub_keys, &SECRET, access, &SESSION, DEALER, &mut thread_rng()).unwrap();
+    let null_sharing = Q::deal(&params, &null_keys, &SECRET, access, &SESSION, DEALER, &mut thread_rng()).unwrap();
+    let sks: Vec<_> = keys.iter().enumerate().map(|(i, kp)| P::extract_key(kp, &sharing, i)).collect();
+    let null_sks = vec![(); n as usize];
+    let shares: Vec<_> = (0..=t as usize)
+        .map(|i| P::decrypt_share(&params, &sharing, &pub_keys, access, i, &sks[i]).unwrap())
+        .collect();
+
+    recorder.bench(c, name, access, "deal", || {
+        black_box(P::deal(&params, &pub_keys, &SECRET, access, &SESSION, DEALER, &mut thread_rng()).unwrap());
+    });
+    recorder.bench(c, name, access, "deal (without IBE)", || {
+        black_box(Q::deal(&params, &null_keys, &SECRET, access, &SESSION, DEALER, &mut thread_rng()).unwrap());
+    });
+    let ids: Vec<_> = (0..n as usize).map(|i| bitcodec::convert_id_to_ibe_format(&P::receiver_id(&sharing, i))).collect();
+    let plaintext = [1i64; N0];
+    recorder.bench(c, name, access, "deal (IBE: n encryptions)", || {
+        for (pub_key, id) in pub_keys.iter().zip(&ids) {
+            black_box(Ibe::encrypt(&plaintext, pub_key, id));
+        }
+    });
+
+    recorder.bench(c, name, access, "verify index", || {
+        P::verify_share(&params, &sharing, &pub_keys, access, 0, &sks[0]).unwrap();
+    });
+    recorder.bench(c, name, access, "verify index (without IBE)", || {
+        Q::verify_share(&params, &null_sharing, &null_keys, access, 0, &()).unwrap();
+    });
+    let ciphertext = Ibe::encrypt(&plaintext, pub_keys[0], &ids[0]);
+    recorder.bench(c, name, access, "verify index (IBE: 1 decryption)", || {
+        black_box(Ibe::decrypt(&ciphertext, &sks[0]));
+    });
+
+    recorder.bench(c, name, access, "verify all", || {
+        P::verify_all(&params, &sharing, &pub_keys, access, &sks).unwrap();
+    });
+    recorder.bench(c, name, access, "verify all (without IBE)", || {
+        Q::verify_all(&params, &null_sharing, &null_keys, access, &null_sks).unwrap();
+    });
+
+    recorder.bench(c, name, access, "reconstruct", || {
+        assert_eq!(P::reconstruct(&params, &sharing, &shares).unwrap(), SECRET);
+    });
+
+    let size = P::size(&sharing);
+    let mean = |op: &str| recorder.mean(name, access, op);
+    json!({
+        "protocol": name,
+        "t": t,
+        "n": n,
+        "ns": {
+            "deal": mean("deal"),
+            "deal_commitments": mean("deal (without IBE)"),
+            "deal_ibe": mean("deal (IBE: n encryptions)"),
+            "verify_index": mean("verify index"),
+            "verify_index_commitments": mean("verify index (without IBE)"),
+            "verify_index_ibe": mean("verify index (IBE: 1 decryption)"),
+            "verify_all": mean("verify all"),
+            "verify_all_commitments": mean("verify all (without IBE)"),
+            "reconstruct": mean("reconstruct"),
+        },
+        "bytes": {
+            "header": size.header,
+            "commitments": size.commitments,
+            "ciphertexts": size.ciphertexts,
+            "total": size.total,
+        },
+    })
+}
+
+/// Benchmarks the checks of receiver 0 on n Pedersen dealings at `access`,
+/// batched and one by one, and returns the row of the JSON report.
+fn bench_batch_verify(c: &mut Criterion, recorder: &Recorder, keys: &[<Ibe as IbeScheme>::MasterKeypair], access: (u64, u64)) -> Value {
+    let (t, n) = access;
+    let params = PedersenPvss::<Ibe>::setup();
+    let pub_keys: Vec<_> = keys[..n as usize].iter().map(Ibe::master_pk).collect();
+    let (sharings, sks): (Vec<_>, Vec<_>) = (0..n)
+        .map(|dealer| {
+            let sharing = prot_pvss_pedcom_ibe::share::<Ibe, _, _>(&params, &pub_keys, &SECRET, access, &SESSION, dealer, &mut thread_rng()).unwrap();
+            let sk = PedersenPvss::<Ibe>::extract_key(&keys[0], &sharing, 0);
+            (sharing, sk)
+        })
+        .unzip();
+
+    let name = "pedersen";
+    recorder.bench(c, name, access, "batch verify (n dealings)", || {
+        let results = prot_pvss_pedcom_ibe::batch_verify(&params, &sharings, access, 0, &sks);
+        assert!(results.iter().all(Result::is_ok));
+    });
+    recorder.bench(c, name, access, "verify n dealings one by one", || {
+        for (sharing, sk) in sharings.iter().zip(&sks) {
+            black_box(prot_pvss_pedcom_ibe::decrypt_share(&params, sharing, access, 0, sk).unwrap());
+        }
+    });
+
+    let mean = |op: &str| recorder.mean(name, access, op);
+    json!({
+        "t": t,
+        "n": n,
+        "ns": {
+            "batch_verify": mean("batch verify (n dealings)"),
+            "verify_one_by_one": mean("verify n dealings one by one"),
+        },
+    })
+}
+
+/// Benchmarks dealing and the checks of all receivers of one Pedersen
+/// dealing with `n` receivers, sequentially and in parallel, and returns the
+/// row of the JSON report. The lattice IBE cannot run in parallel, so this
+/// needs the mock IBE.
+#[cfg(all(feature = "parallel", feature = "mock-ibe"))]
+fn bench_parallel(c: &mut Criterion, recorder: &Recorder, keys: &[<Ibe as IbeScheme>::MasterKeypair], n: u64) -> Value {
+    let access = (n / 2 - 1, n);
+    let params = PedersenPvss::<Ibe>::setup();
+    let keys = &keys[..n as usize];
+    let pub_keys: Vec<_> = keys.iter().map(Ibe::master_pk).collect();
+    let sharing = prot_pvss_pedcom_ibe::share::<Ibe, _, _>(&params, &pub_keys, &SECRET, access, &SESSION, DEALER, &mut thread_rng()).unwrap();
+    let sks: Vec<_> = keys.iter().enumerate().map(|(i, kp)| PedersenPvss::<Ibe>::extract_key(kp, &sharing, i)).collect();
+
+    let name = "pedersen/scale";
+    recorder.bench(c, name, access, "deal", || {
+        black_box(prot_pvss_pedcom_ibe::share::<Ibe, _, _>(&params, &pub_keys, &SECRET, access, &SESSION, DEALER, &mut thread_rng()).unwrap());
+    });
+    recorder.bench(c, name, access, "deal (parallel)", || {
+        black_box(prot_pvss_pedcom_ibe::share_parallel::<Ibe, _, _>(&params, &pub_keys, &SECRET, access, &SESSION, DEALER, &mut thread_rng()).unwrap());
+    });
+    recorder.bench(c, name, access, "verify public", || {
+        prot_pvss_pedcom_ibe::verify_public(&sharing, access).unwrap();
+    });
+    recorder.bench(c, name, access, "verify all", || {
+        let results = prot_pvss_pedcom_ibe::verify_all_indices(&params, &sharing, access, &sks).unwrap();
+        assert!(results.iter().all(Result::is_ok));
+    });
+    recorder.bench(c, name, access, "verify all (parallel)", || {
+        let results = prot_pvss_pedcom_ibe::verify_all_indices_parallel(&params, &sharing, access, &sks).unwrap();
+        assert!(results.iter().all(Result::is_ok));
+    });
+
+    let mean = |op: &str| recorder.mean(name, access, op);
+    json!({
+        "t": access.0,
+        "n": n,
+        "ns": {
+            "deal": mean("deal"),
+            "deal_parallel": mean("deal (parallel)"),
+            "verify_public": mean("verify public"),
+            "verify_all": mean("verify all"),
+            "verify_all_parallel": mean("verify all (parallel)"),
+        },
+    })
+}
+
+fn grid() -> Vec<(u64, u64)> {
+    let Ok(grid) = std::env::var("PQPPVSS_BENCH_GRID") else {
+        return GRID.to_vec();
+    };
+    grid.split(',')
+        .map(|access| {
+            let (t, n) = access.trim().split_once(':').expect("PQPPVSS_BENCH_GRID is a list of t:n");
+            let (t, n) = (t.parse().expect("t is a number"), n.parse().expect("n is a number"));
+            assert!(t < n, "t must be below n in {}", access);
+            (t, n)
+        })
+        .collect()
+}
+
+fn main() {
+    let mut c = Criterion::default().configure_from_args();
+    let grid = grid();
+    let recorder = Recorder::default();
+    let max_n = grid.iter().chain(BATCH).map(|&(_, n)| n).max().unwrap_or(0);
+    #[cfg(all(feature = "parallel", feature = "mock-ibe"))]
+    let max_n = SCALE.iter().copied().fold(max_n, u64::max);
+    let keys: Vec<_> = (0..max_n).map(|_| Ibe::keygen()).collect();
+
+    let mut rows = Vec::new();
+    for &access in &grid {
+        rows.push(bench_protocol::<PedersenPvss<Ibe>, PedersenPvss<NullIbe>>(&mut c, &recorder, &keys, access));
+        rows.push(bench_protocol::<HashPvss<Ibe>, HashPvss<NullIbe>>(&mut c, &recorder, &keys, access));
+    }
+    let batch: Vec<_> = BATCH.iter().map(|&access| bench_batch_verify(&mut c, &recorder, &keys, access)).collect();
+    #[cfg(all(feature = "parallel", feature = "mock-ibe"))]
+    let scale: Vec<_> = SCALE.iter().map(|&n| bench_parallel(&mut c, &recorder, &keys, n)).collect();
+    #[cfg(not(all(feature = "parallel", feature = "mock-ibe")))]
+    let scale: Vec<Value> = Vec::new();
+    c.final_summary();
+
+    let path = std::env::var("PQPPVSS_BENCH_JSON").unwrap_or_else(|_| "target/pvss-bench.json".to_string());
+    let ibe = if cfg!(feature = "mock-ibe") { "mock" } else { "lattice" };
+    let report = json!({ "ibe": ibe, "parallel": cfg!(feature = "parallel"), "results": rows, "batch": batch, "parallel_scale": scale });
+    std::fs::write(&path, serde_json::to_string_pretty(&report).unwrap()).expect("cannot write the JSON report");
+    println!("wrote {}", path);
+}
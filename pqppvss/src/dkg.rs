@@ -0,0 +1,341 @@
+//! Distributed key generation from Pedersen-IBE PVSS dealings.
+//!
+//! 1. Every party `k` deals a random secret with [`deal`], as dealer `k` of the
+//!    session, to the master public keys of all n parties.
+//! 2. Every party `i` checks its share of every dealing with [`receive`], and
+//!    publishes a complaint about each dealing its share does not verify in.
+//! 3. Everybody computes the same [`qualified_set`] from the dealings and the
+//!    complaints: the dealings that pass the public checks and that no valid
+//!    complaint was raised against.
+//! 4. Every party sums its shares of the qualified dealings into its
+//!    [`KeyShare`] x_i, and publishes the verification key g^x_i with a proof
+//!    that it matches the commitments.
+//! 5. From t+1 valid verification keys, everybody interpolates the threshold
+//!    public key g^s, with s the sum of the qualified secrets, and the
+//!    verification keys of all parties with [`public_output`].
+//!
+//! Pedersen commitments hide g^s, which is why the parties publish their
+//! verification keys in step 4 instead of the dealers.
+
+use ark_ff::{PrimeField, UniformRand};
+use rand::Rng;
+use rust_bindings::IbeScheme;
+use sha2::{Digest, Sha256};
+
+use crate::ibe::SessionId;
+use crate::pedcom::*;
+use crate::prot_pvss_pedcom_ibe::{batch_verify, share_polynomials, verify_complaint, verify_public, Complaint, DecryptedShare, Sharing};
+use crate::utils;
+
+/// domain separator of the verification key proofs
+const PROOF_TAG: &[u8] = b"pqppvss/dkg/verification-key/v1";
+
+/// Deals a fresh random secret, as dealer `dealer_id` of the session.
+pub fn deal<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    pub_keys: &[&I::MasterPublicKey],
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R
+) -> Result<Sharing<I, G>, PVSSError> {
+    check_access(access)?;
+    check_len(access.1, pub_keys.len())?;
+    let secret_poly = utils::sample_poly(Some(G::Scalar::rand(rng)), access.0, rng);
+    let random_poly = utils::sample_poly(None, access.0, rng);
+    Ok(share_polynomials(pedcom_params, pub_keys, &secret_poly, &random_poly, access, session_id, dealer_id))
+}
+
+/// A complaint of a receiver about the dealing of `dealer`.
+pub struct DkgComplaint<I: IbeScheme> {
+    pub dealer: usize,
+    pub complaint: Complaint<I>,
+}
+
+/// the share of a party in each dealing, if it verified
+pub type ReceivedShares<G> = Vec<Option<DecryptedShare<<G as PedComGroup>::Scalar>>>;
+
+/// Checks the share of party `index` in every dealing, `sks[k]` being its key
+/// for dealing `k`. Returns the shares of the dealings that verify, and the
+/// complaints to publish about the dealings that gave a bad share. Dealings
+/// that fail the public checks of [`qualified_set`] get no complaint: nobody
+/// accepts them anyway, and a complaint would disclose the key of the share.
+pub fn receive<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    dealings: &[Sharing<I, G>],
+    access: (u64, u64),
+    index: usize,
+    sks: Vec<I::SecretKeyID>
+) -> (ReceivedShares<G>, Vec<DkgComplaint<I>>) {
+    let results = batch_verify(pedcom_params, dealings, access, index, &sks);
+    let mut shares = Vec::new();
+    let mut complaints = Vec::new();
+    for (dealer, (result, sk_id)) in results.into_iter().zip(sks).enumerate() {
+        match result {
+            Ok(share) => shares.push(Some(share)),
+            Err(err) => {
+                let dealing = &dealings[dealer];
+                let public_fault = dealing.dealer_id != dealer as u64 || verify_public(dealing, access).is_err();
+                if err.is_dealer_fault() && !public_fault {
+                    complaints.push(DkgComplaint { dealer, complaint: Complaint { index, sk_id } });
+                }
+                shares.push(None);
+            }
+        }
+    }
+    (shares, complaints)
+}
+
+/// The dealers whose dealings everybody accepts: dealing `k` must be dealt by
+/// dealer `k` in this session for `access`, pass [`verify_public`], and no
+/// complaint about it may hold. `pub_keys` are the master public keys of the
+/// parties, in order.
+pub fn qualified_set<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    dealings: &[Sharing<I, G>],
+    access: (u64, u64),
+    session_id: &SessionId,
+    complaints: &[DkgComplaint<I>],
+    pub_keys: &[&I::MasterPublicKey]
+) -> Vec<usize> {
+    let mut qualified: Vec<bool> = dealings.iter().enumerate()
+        .map(|(k, dealing)| {
+            dealing.session_id == *session_id
+                && dealing.dealer_id == k as u64
+                && verify_public(dealing, access).is_ok()
+        })
+        .collect();
+    for DkgComplaint { dealer, complaint } in complaints {
+        let Some(&receiver_mpk) = pub_keys.get(complaint.index) else { continue };
+        if qualified.get(*dealer) == Some(&true)
+            && verify_complaint(pedcom_params, &dealings[*dealer], complaint, receiver_mpk).is_ok() {
+            qualified[*dealer] = false;
+        }
+    }
+    (0..dealings.len()).filter(|&k| qualified[k]).collect()
+}
+
+/// Party `index`'s share of the threshold secret: x_i = sum_k s_ki, opening
+/// the commitment prod_k C_ki with r_i = sum_k r_ki.
+pub struct KeyShare<G: PedComGroup> {
+    pub index: usize,
+    pub x: G::Scalar,
+    pub r: G::Scalar,
+}
+
+/// Sums the shares of party `index` of the qualified dealings, as returned by
+/// [`receive`].
+pub fn key_share<G: PedComGroup>(
+    qualified: &[usize],
+    index: usize,
+    shares: &[Option<DecryptedShare<G::Scalar>>]
+) -> Result<KeyShare<G>, PVSSError> {
+    let mut x = G::Scalar::from(0u64);
+    let mut r = G::Scalar::from(0u64);
+    for &dealer in qualified {
+        let share = shares.get(dealer).and_then(Option::as_ref).ok_or(PVSSError::MissingShare { dealer })?;
+        x += share.s;
+        r += share.r;
+    }
+    Ok(KeyShare { index, x, r })
+}
+
+/// What party `index` publishes: y_i = g^x_i, and a Schnorr proof of
+/// knowledge of r_i with prod_k C_ki - y_i = h^r_i.
+pub struct VerificationKey<G: PedComGroup> {
+    pub index: usize,
+    pub y: G::Element,
+    pub proof: (G::Element, G::Scalar),
+}
+
+/// the sum of the commitments of the qualified dealings to the shares of party `index`
+fn summed_commitment<I: IbeScheme, G: PedComGroup>(
+    dealings: &[Sharing<I, G>],
+    qualified: &[usize],
+    index: usize
+) -> G::Element {
+    qualified.iter().fold(G::zero(), |acc, &k| G::add(&acc, &dealings[k].commitments[index]))
+}
+
+fn proof_challenge<G: PedComGroup>(session_id: &SessionId, index: usize, c: &G::Element, y: &G::Element, a: &G::Element) -> G::Scalar {
+    let mut hasher = Sha256::new();
+    hasher.update(PROOF_TAG);
+    hasher.update(session_id);
+    hasher.update((index as u64).to_le_bytes());
+    for element in [c, y, a] {
+        hasher.update(utils::serialize(element));
+    }
+    let digest: [u8; 32] = hasher.finalize().into();
+    G::Scalar::from_le_bytes_mod_order(&digest)
+}
+
+impl<G: PedComGroup> KeyShare<G> {
+    pub fn verification_key<I: IbeScheme, R: Rng>(
+        &self,
+        pedcom_params: &PedComParams<G>,
+        dealings: &[Sharing<I, G>],
+        qualified: &[usize],
+        session_id: &SessionId,
+        rng: &mut R
+    ) -> VerificationKey<G> {
+        let y = G::mul(&pedcom_params.g, &self.x);
+        let c = summed_commitment(dealings, qualified, self.index);
+        let k = G::Scalar::rand(rng);
+        let a = G::mul(&pedcom_params.h, &k);
+        let e = proof_challenge::<G>(session_id, self.index, &c, &y, &a);
+        VerificationKey { index: self.index, y, proof: (a, k + e * self.r) }
+    }
+}
+
+/// Checks the proof of a verification key against the qualified dealings.
+pub fn verify_verification_key<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    dealings: &[Sharing<I, G>],
+    qualified: &[usize],
+    session_id: &SessionId,
+    vk: &VerificationKey<G>
+) -> Result<(), PVSSError> {
+    let index = vk.index;
+    if let Some(&k) = qualified.iter().find(|&&k| index >= dealings[k].commitments.len()) {
+        return Err(PVSSError::IndexOutOfRange { index, n: dealings[k].commitments.len() as u64 });
+    }
+    let c = summed_commitment(dealings, qualified, index);
+    let (a, z) = &vk.proof;
+    let e = proof_challenge::<G>(session_id, index, &c, &vk.y, a);
+    // h^z = a + e (C - y)
+    let c_minus_y = G::add(&c, &G::mul(&vk.y, &-G::Scalar::from(1u64)));
+    if G::mul(&pedcom_params.h, z) != G::add(a, &G::mul(&c_minus_y, &e)) {
+        return Err(PVSSError::InvalidVerificationKey { index });
+    }
+    Ok(())
+}
+
+/// The public result of a DKG.
+pub struct DkgOutput<G: PedComGroup> {
+    /// g^s
+    pub public_key: G::Element,
+    /// g^x_i of every party i
+    pub verification_keys: Vec<G::Element>,
+}
+
+/// Interpolates the threshold public key and all verification keys from the
+/// published ones. Invalid verification keys are skipped; at least t+1 valid
+/// ones are needed, and all of them must lie on one polynomial of degree t.
+pub fn public_output<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    dealings: &[Sharing<I, G>],
+    qualified: &[usize],
+    session_id: &SessionId,
+    access: (u64, u64),
+    vks: &[VerificationKey<G>]
+) -> Result<DkgOutput<G>, PVSSError> {
+    let (t, n) = access;
+    let mut valid: Vec<&VerificationKey<G>> = Vec::new();
+    for vk in vks {
+        if valid.iter().all(|v| v.index != vk.index)
+            && verify_verification_key(pedcom_params, dealings, qualified, session_id, vk).is_ok() {
+            valid.push(vk);
+        }
+    }
+    if (valid.len() as u64) < t + 1 {
+        return Err(PVSSError::NotEnoughShares { needed: t + 1, got: valid.len() });
+    }
+    // Lagrange interpolation in the exponent through the first t+1 keys
+    let basis = &valid[..(t + 1) as usize];
+    let xs: Vec<G::Scalar> = basis.iter().map(|vk| G::Scalar::from(vk.index as u64 + 1)).collect();
+    let ys: Vec<G::Element> = basis.iter().map(|vk| vk.y).collect();
+    let interpolate = |x: G::Scalar| interpolate_commitment::<G>(&xs, &ys, x);
+    let verification_keys: Vec<G::Element> = (0..n).map(|i| interpolate(G::Scalar::from(i + 1))).collect();
+    if valid.iter().any(|vk| verification_keys.get(vk.index) != Some(&vk.y)) {
+        return Err(PVSSError::DegreeCheckFailed);
+    }
+    Ok(DkgOutput { public_key: interpolate(G::Scalar::from(0u64)), verification_keys })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ibe::{self, ibe_encrypt, ibe_extract_id_secret_key, TestIbe};
+    use ark_serialize::CanonicalSerialize;
+    use rand::thread_rng;
+
+    const SESSION: SessionId = [9u8; 32];
+
+    type F = ark_bls12_381::Fr;
+
+    #[test]
+    fn test_dkg_with_malicious_dealers() {
+        let params = setup::<Bls12G1>();
+        let (t, n) = (2u64, 7u64);
+        let parties: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = parties.iter().map(TestIbe::master_pk).collect();
+
+        let mut dealings: Vec<Sharing<TestIbe>> = (0..n)
+            .map(|k| deal(&params, &pub_keys, (t, n), &SESSION, k, &mut thread_rng()).unwrap())
+            .collect();
+        // dealer 1 uses a polynomial of too high degree
+        dealings[1] = deal(&params, &pub_keys, (t + 1, n), &SESSION, 1, &mut thread_rng()).unwrap();
+        dealings[1].access = (t, n);
+        // dealer 4 sends party 2 a share that does not open its commitment
+        let mut msg = [0u8; 96];
+        F::from(5u64).serialize_compressed(&mut msg[0..32]).unwrap();
+        dealings[4].ciphertexts[2] = ibe_encrypt::<TestIbe>(&msg, pub_keys[2], &dealings[4].receiver_id(2));
+        // dealer 5 replays the dealing of dealer 0
+        dealings[5] = deal(&params, &pub_keys, (t, n), &SESSION, 0, &mut thread_rng()).unwrap();
+
+        let mut shares = Vec::new();
+        let mut complaints = Vec::new();
+        for (i, party) in parties.iter().enumerate() {
+            let sks = dealings.iter()
+                .map(|dealing| ibe_extract_id_secret_key::<TestIbe>(&dealing.receiver_id(i), party))
+                .collect();
+            let (received, complained) = receive(&params, &dealings, (t, n), i, sks);
+            shares.push(received);
+            complaints.extend(complained);
+        }
+        assert!(matches!(deal::<TestIbe, Bls12G1, _>(&params, &pub_keys[1..], (t, n), &SESSION, 0, &mut thread_rng()),
+            Err(PVSSError::LengthMismatch { expected: 7, actual: 6 })));
+        assert!(matches!(deal::<TestIbe, Bls12G1, _>(&params, &pub_keys, (n, n), &SESSION, 0, &mut thread_rng()),
+            Err(PVSSError::InvalidThreshold { t: 7, n: 7 })));
+        // party 2 complains about dealer 4; dealers 1 and 5 fail publicly, so
+        // nobody discloses a key to complain about them
+        assert!(complaints.iter().any(|c| c.dealer == 4 && c.complaint.index == 2));
+        assert!(complaints.iter().all(|c| c.dealer != 1 && c.dealer != 5));
+        // a complaint about an honest dealing does not count
+        let false_complaint = Complaint { index: 3, sk_id: ibe_extract_id_secret_key::<TestIbe>(&dealings[0].receiver_id(3), &parties[3]) };
+        complaints.push(DkgComplaint { dealer: 0, complaint: false_complaint });
+
+        let qualified = qualified_set(&params, &dealings, (t, n), &SESSION, &complaints, &pub_keys);
+        assert_eq!(qualified, vec![0, 2, 3, 6]);
+
+        let key_shares: Vec<KeyShare<Bls12G1>> = (0..n as usize)
+            .map(|i| key_share(&qualified, i, &shares[i]).unwrap())
+            .collect();
+        // party 2 has no share of dealing 4, which therefore must not be in the set
+        assert!(matches!(key_share::<Bls12G1>(&[4], 2, &shares[2]), Err(PVSSError::MissingShare { dealer: 4 })));
+
+        let mut vks: Vec<_> = key_shares.iter()
+            .map(|ks| ks.verification_key(&params, &dealings, &qualified, &SESSION, &mut thread_rng()))
+            .collect();
+        // party 6 lies about its verification key
+        vks[6].y = params.g;
+        assert!(matches!(verify_verification_key(&params, &dealings, &qualified, &SESSION, &vks[6]),
+            Err(PVSSError::InvalidVerificationKey { index: 6 })));
+
+        let output = public_output(&params, &dealings, &qualified, &SESSION, (t, n), &vks).unwrap();
+        // any t+1 key shares reconstruct the secret behind the public key
+        for subset in [[0, 1, 2], [6, 3, 5], [4, 0, 6]] {
+            let points: Vec<(F, F)> = subset.iter().map(|&i| (F::from(i as u64 + 1), key_shares[i].x)).collect();
+            let s = utils::lagrange_interpolate_at_zero(&points);
+            assert_eq!(output.public_key, Bls12G1::mul(&params.g, &s));
+        }
+        for (i, ks) in key_shares.iter().enumerate() {
+            assert_eq!(output.verification_keys[i], Bls12G1::mul(&params.g, &ks.x));
+        }
+        // the same result from any t+1 honest verification keys
+        let other = public_output(&params, &dealings, &qualified, &SESSION, (t, n), &vks[3..]).unwrap();
+        assert_eq!(other.public_key, output.public_key);
+        assert!(matches!(public_output(&params, &dealings, &qualified, &SESSION, (t, n), &vks[4..]),
+            Err(PVSSError::NotEnoughShares { needed: 3, got: 2 })));
+    }
+}
@@ -1,7 +1,16 @@
-use rust_bindings::{
-    lattice_ibe_ffi::MasterPublicKey,
-    decrypt, encrypt, IbeCiphertext, IbeMasterKeypair, IbeSecretKeyID
-};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use rust_bindings::IbeScheme;
+
+use crate::utils;
+
+/// IBE backend of the unit tests: the in-memory mock with the `mock-ibe`
+/// feature, the lattice IBE otherwise.
+#[cfg(all(test, feature = "mock-ibe"))]
+pub type TestIbe = rust_bindings::mock::MockIbe;
+#[cfg(all(test, not(feature = "mock-ibe")))]
+pub type TestIbe = rust_bindings::LatticeIbe;
 
 /// maps a 32-byte block <--> N0-length bit vector
 pub mod bitcodec {
@@ -11,7 +20,7 @@ pub mod bitcodec {
      |  1. bytes -> message coefficients (encrypt direction)     |
      *----------------------------------------------------------*/
     pub fn scalar_bytes_to_encryption_input(bytes: &[u8; 96]) -> [i64; N0] {
-        assert!(N0 >= 1024, "N0 must be ≥ 1024 to embed 1024 bits");
+        const { assert!(N0 >= 1024, "N0 must be ≥ 1024 to embed 1024 bits") };
 
         let mut msg = [0_i64; N0];
 
@@ -30,11 +39,11 @@ pub mod bitcodec {
      |  2. message coefficients -> bytes (decrypt direction)     |
      *----------------------------------------------------------*/
     pub fn decryption_output_to_scalar_bytes(msg: &[i64; N0]) -> [u8; 96] {
-        assert!(N0 >= 1024, "N0 must be ≥ 1024");
+        const { assert!(N0 >= 1024, "N0 must be ≥ 1024") };
         let mut bytes = [0_u8; 96];
 
-        for bit_idx in 0..768 {
-            let coef = msg[bit_idx] & 1;                  // be tolerant
+        for (bit_idx, &m) in msg.iter().enumerate().take(768) {
+            let coef = m & 1;                             // be tolerant
             let byte   = bit_idx / 8;
             let offset = bit_idx % 8;
             bytes[byte] |= (coef as u8) << offset;
@@ -58,12 +67,12 @@ pub mod bitcodec {
 
         #[test]
         fn bitcodec_roundtrip() {
-            assert!(N0 >= 1024, "N0 must be at least 1024 for the codec");
+            const { assert!(N0 >= 1024, "N0 must be at least 1024 for the codec") };
 
             // ❶ deterministic pseudo-random pattern
             let mut bytes = [0u8; 96];
-            for i in 0..96 {
-                bytes[i] = (i as u8).wrapping_mul(37).wrapping_add(11);
+            for (i, b) in bytes.iter_mut().enumerate() {
+                *b = (i as u8).wrapping_mul(37).wrapping_add(11);
             }
 
             let msg_vec  = bitcodec::scalar_bytes_to_encryption_input(&bytes);
@@ -88,45 +97,166 @@ pub mod bitcodec {
     }
 }
 
-pub fn ibe_keygen() -> IbeMasterKeypair {
-    IbeMasterKeypair::generate()
+pub fn ibe_keygen<I: IbeScheme>() -> I::MasterKeypair {
+    I::keygen()
 }
 
-pub fn ibe_extract_id_secret_key(id: &[u8; 96], mkp: &IbeMasterKeypair) -> IbeSecretKeyID {
+pub fn ibe_extract_id_secret_key<I: IbeScheme>(id: &[u8; 96], mkp: &I::MasterKeypair) -> I::SecretKeyID {
     let id_ibe = bitcodec::convert_id_to_ibe_format(id);
-    mkp.extract_sk_id(&id_ibe)
+    I::extract(mkp, &id_ibe)
+}
+
+/// whether `sk_id` is the key of `id` under `master_public_key`, checkable by anybody
+pub fn ibe_verify_id_secret_key<I: IbeScheme>(id: &[u8; 96], master_public_key: &I::MasterPublicKey, sk_id: &I::SecretKeyID) -> bool {
+    let id_ibe = bitcodec::convert_id_to_ibe_format(id);
+    I::verify_key(master_public_key, &id_ibe, sk_id)
+}
+
+/// identifies one sharing among all sharings of all dealers
+pub type SessionId = [u8; 32];
+
+/// Identity the share of receiver `index` is encrypted to: a hash of the
+/// protocol `label`, the session, the dealer and the index. Every share of
+/// every sharing thus has its own identity, and revealing its key (e.g. in a
+/// complaint) opens nothing else.
+pub fn receiver_id(label: &[u8], session_id: &SessionId, dealer_id: u64, index: usize) -> [u8; 96] {
+    let mut id = [0u8; 96];
+    for (block, chunk) in id.chunks_mut(32).enumerate() {
+        chunk.copy_from_slice(&utils::digest_sha256(&[
+            b"pqppvss/receiver-id",
+            &(label.len() as u64).to_le_bytes(),
+            label,
+            session_id,
+            &dealer_id.to_le_bytes(),
+            &(index as u64).to_le_bytes(),
+            &[block as u8],
+        ]));
+    }
+    id
 }
 
-pub fn ibe_encrypt(msg: &[u8; 96], master_public_key: &MasterPublicKey, id: &[u8; 96]) -> IbeCiphertext {
+/// IBE backends whose keys and ciphertexts may cross threads, so that shares
+/// are encrypted and decrypted in parallel. The lattice backend is not one:
+/// NTL keeps its modulus in global state.
+#[cfg(feature = "parallel")]
+pub trait ParallelIbe: IbeScheme<MasterPublicKey: Sync, SecretKeyID: Sync, Ciphertext: Send + Sync> {}
+
+#[cfg(feature = "parallel")]
+impl<I: IbeScheme<MasterPublicKey: Sync, SecretKeyID: Sync, Ciphertext: Send + Sync>> ParallelIbe for I {}
+
+pub fn ibe_encrypt<I: IbeScheme>(msg: &[u8; 96], master_public_key: &I::MasterPublicKey, id: &[u8; 96]) -> I::Ciphertext {
     let id_ibe = bitcodec::convert_id_to_ibe_format(id);
     let input_data_ser = bitcodec::scalar_bytes_to_encryption_input(msg);
-    encrypt(&input_data_ser, master_public_key, &id_ibe)
+    I::encrypt(&input_data_ser, master_public_key, &id_ibe)
+}
+
+pub fn ibe_decrypt<I: IbeScheme>(cipher: &I::Ciphertext, sk_id: &I::SecretKeyID) -> [u8; 96] {
+    bitcodec::decryption_output_to_scalar_bytes(&I::decrypt(cipher, sk_id))
+}
+
+/// How the dealer encrypts the plaintext of every receiver: with
+/// [`encrypt_shares`], or with [`encrypt_shares_parallel`].
+pub type EncryptShares<I> = fn(
+    &[u8],
+    &SessionId,
+    u64,
+    &[&<I as IbeScheme>::MasterPublicKey],
+    &[[u8; 96]]
+) -> Vec<<I as IbeScheme>::Ciphertext>;
+
+/// Encrypts `plaintexts[i]` under `pub_keys[i]` to the [`receiver_id`] of
+/// receiver i.
+pub fn encrypt_shares<I: IbeScheme>(
+    label: &[u8],
+    session_id: &SessionId,
+    dealer_id: u64,
+    pub_keys: &[&I::MasterPublicKey],
+    plaintexts: &[[u8; 96]]
+) -> Vec<I::Ciphertext> {
+    plaintexts.iter().zip(pub_keys).enumerate()
+        .map(|(i, (msg, pub_key))| ibe_encrypt::<I>(msg, pub_key, &receiver_id(label, session_id, dealer_id, i)))
+        .collect()
+}
+
+/// [`encrypt_shares`], in parallel.
+#[cfg(feature = "parallel")]
+pub fn encrypt_shares_parallel<I: ParallelIbe>(
+    label: &[u8],
+    session_id: &SessionId,
+    dealer_id: u64,
+    pub_keys: &[&I::MasterPublicKey],
+    plaintexts: &[[u8; 96]]
+) -> Vec<I::Ciphertext> {
+    plaintexts.par_iter().zip(pub_keys).enumerate()
+        .map(|(i, (msg, pub_key))| ibe_encrypt::<I>(msg, pub_key, &receiver_id(label, session_id, dealer_id, i)))
+        .collect()
+}
+
+/// 32-byte scalars per IBE plaintext
+pub const SCALARS_PER_PLAINTEXT: usize = 3;
+
+/// number of plaintexts [`pack_scalars`] needs for `count` scalars
+pub fn plaintexts_for(count: usize) -> usize {
+    count.div_ceil(SCALARS_PER_PLAINTEXT)
 }
 
-pub fn ibe_decrypt(cipher: &IbeCiphertext, sk_id: &IbeSecretKeyID) -> [u8; 96] {
-    bitcodec::decryption_output_to_scalar_bytes(&decrypt(cipher, sk_id))
+/// Packs scalars of at most 32 bytes into as few plaintexts as possible,
+/// padding the last one with zeros.
+pub fn pack_scalars<S: CanonicalSerialize>(scalars: &[S]) -> Vec<[u8; 96]> {
+    scalars.chunks(SCALARS_PER_PLAINTEXT)
+        .map(|chunk| {
+            let mut msg = [0u8; 96];
+            for (slot, scalar) in chunk.iter().enumerate() {
+                scalar.serialize_compressed(&mut msg[32 * slot..32 * (slot + 1)]).unwrap();
+            }
+            msg
+        })
+        .collect()
+}
+
+/// The first `count` scalars packed by [`pack_scalars`], or the position of
+/// the first one that does not decode.
+pub fn unpack_scalars<S: CanonicalDeserialize>(plaintexts: &[[u8; 96]], count: usize) -> Result<Vec<S>, usize> {
+    (0..count)
+        .map(|k| {
+            let (msg, slot) = (&plaintexts[k / SCALARS_PER_PLAINTEXT], k % SCALARS_PER_PLAINTEXT);
+            S::deserialize_compressed(&msg[32 * slot..32 * (slot + 1)]).map_err(|_| k)
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod test {
-    use rust_bindings::IbeMasterKeypair;
     use super::*;
     use rand::Rng;
 
     #[test]
     fn test_encrypt_decrypt_100() {
-        let master_keypair = IbeMasterKeypair::generate();
+        let master_keypair = ibe_keygen::<TestIbe>();
         let mut rng = rand::thread_rng();
 
         for i in 0..100 {
             let id = [i as u8; 96];
-            let sk_id = ibe_extract_id_secret_key(&id, &master_keypair);
+            let sk_id = ibe_extract_id_secret_key::<TestIbe>(&id, &master_keypair);
             let mut msg: [u8; 96] = [0; 96];
             rng.fill(&mut msg[..64]);
-            let result = ibe_encrypt(&msg, master_keypair.master_pk(), &id);
-            let decrypted = ibe_decrypt(&result, &sk_id);
+            let result = ibe_encrypt::<TestIbe>(&msg, TestIbe::master_pk(&master_keypair), &id);
+            let decrypted = ibe_decrypt::<TestIbe>(&result, &sk_id);
             assert_eq!(msg, decrypted);
         }
     }
 
-}
\ No newline at end of file
+
+    #[test]
+    fn test_receiver_ids_are_distinct() {
+        let session = [1u8; 32];
+        let mut ids: Vec<[u8; 96]> = (0..1 << 16).map(|i| receiver_id(b"test", &session, 0, i)).collect();
+        ids.push(receiver_id(b"test", &[2u8; 32], 0, 0));
+        ids.push(receiver_id(b"test", &session, 1, 0));
+        ids.push(receiver_id(b"other", &session, 0, 0));
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+    }
+}
@@ -1,6 +1,23 @@
 mod ibe;
 mod pedcom;
 mod utils;
+mod multipoint;
 
 pub mod prot_pvss_pedcom_ibe;
-pub mod prot_pvss_hash_ibe;
\ No newline at end of file
+pub mod dkg;
+pub mod reshare;
+pub mod weighted;
+pub mod long_secret;
+pub mod sss;
+pub mod prot_pvss_hash_ibe;
+pub mod pvss;
+#[cfg(feature = "ristretto")]
+pub mod ristretto;
+pub mod serialization;
+pub mod registry;
+
+pub use pedcom::{setup, Bls12G1, PVSSError, PedComCommitment, PedComCurve, PedComGroup, PedComParams};
+pub use ibe::{bitcodec, ibe_extract_id_secret_key, ibe_keygen, ibe_verify_id_secret_key, SessionId};
+pub use pvss::Pvss;
+#[cfg(feature = "parallel")]
+pub use ibe::ParallelIbe;
@@ -0,0 +1,347 @@
+//! Secrets longer than one field element.
+//!
+//! - [`share_bytes`] shares a fresh key with any [`Pvss`], and publishes the
+//!   payload encrypted under it with ChaCha20-Poly1305. Any t+1 receivers
+//!   reconstruct the key, and so the payload; the payload itself is not
+//!   verifiable before that.
+//! - [`share_vector`] shares several field elements with Pedersen
+//!   commitments, one polynomial pair per element, and packs the shares of a
+//!   receiver into as few IBE ciphertexts as possible. Every element is
+//!   verified as in `prot_pvss_pedcom_ibe`.
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::Rng;
+use rust_bindings::IbeScheme;
+
+use crate::ibe::*;
+use crate::pedcom::*;
+use crate::prot_pvss_pedcom_ibe::DecryptedShare;
+use crate::pvss::Pvss;
+use crate::multipoint::EvaluationPoints;
+use crate::utils;
+
+/// protocol label of the receiver identities and the payload encryption
+pub const LABEL: &[u8] = b"pqppvss/long_secret";
+
+/// A byte payload, encrypted under a key shared with `P`.
+pub struct PayloadSharing<P: Pvss> {
+    pub session_id: SessionId,
+    pub dealer_id: u64,
+    /// the sharing of the payload key
+    pub key_sharing: P::Sharing,
+    /// the payload under ChaCha20-Poly1305
+    pub ciphertext: Vec<u8>,
+}
+
+/// the AEAD key of a shared secret
+fn payload_key(secret: &[u8; 32]) -> ChaCha20Poly1305 {
+    let key = utils::digest_sha256(&[b"pqppvss/payload-key", secret]);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+/// binds the payload to its sharing
+fn payload_aad(session_id: &SessionId, dealer_id: u64) -> Vec<u8> {
+    [LABEL, session_id, &dealer_id.to_le_bytes()].concat()
+}
+
+/// Shares `payload` among the owners of `pub_keys` with threshold t: any t+1
+/// shares of the key sharing decrypt it. As every sharing has a fresh key,
+/// the nonce is fixed.
+pub fn share_bytes<P: Pvss, R: Rng>(
+    params: &P::Params,
+    pub_keys: &[&<P::Ibe as IbeScheme>::MasterPublicKey],
+    payload: &[u8],
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R
+) -> Result<PayloadSharing<P>, P::Error> {
+    // the top byte stays zero, so the key is below the modulus of every group
+    let mut secret = [0u8; 32];
+    rng.fill(&mut secret[1..]);
+    let key_sharing = P::deal(params, pub_keys, &secret, access, session_id, dealer_id, rng)?;
+    let aad = payload_aad(session_id, dealer_id);
+    let ciphertext = payload_key(&secret)
+        .encrypt(Nonce::from_slice(&[0u8; 12]), Payload { msg: payload, aad: &aad })
+        .expect("ChaCha20-Poly1305 encrypts payloads of any practical length");
+    Ok(PayloadSharing { session_id: *session_id, dealer_id, key_sharing, ciphertext })
+}
+
+/// Recovers the payload from at least t+1 published shares of the key.
+pub fn reconstruct_bytes<P: Pvss<Error = PVSSError>>(
+    params: &P::Params,
+    sharing: &PayloadSharing<P>,
+    shares: &[P::DecryptedShare]
+) -> Result<Vec<u8>, PVSSError> {
+    let secret = P::reconstruct(params, &sharing.key_sharing, shares)?;
+    let aad = payload_aad(&sharing.session_id, sharing.dealer_id);
+    payload_key(&secret)
+        .decrypt(Nonce::from_slice(&[0u8; 12]), Payload { msg: &sharing.ciphertext, aad: &aad })
+        .map_err(|_| PVSSError::PayloadDecryptionFailed)
+}
+
+pub struct VectorSharing<I: IbeScheme, G: PedComGroup = Bls12G1> {
+    pub session_id: SessionId,
+    pub dealer_id: u64,
+    pub access: (u64, u64), // (t, n)
+    /// `commitments[e][i]` commits to the share of receiver i of element e
+    pub commitments: Vec<Vec<PedComCommitment<G>>>,
+    /// the ciphertexts of receiver 0, then of receiver 1, ...
+    pub ciphertexts: Vec<I::Ciphertext>,
+}
+
+/// The shares of one receiver, one per element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorShare<S> {
+    pub index: usize,
+    pub elements: Vec<DecryptedShare<S>>,
+}
+
+/// Shares every element of `secrets` among the owners of `pub_keys` with
+/// threshold t. There must be at least one element.
+pub fn share_vector<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    pub_keys: &[&I::MasterPublicKey],
+    secrets: &[G::Scalar],
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R
+) -> Result<VectorSharing<I, G>, PVSSError> {
+    let (t, n) = access;
+    check_access(access)?;
+    check_len(n, pub_keys.len())?;
+    if secrets.is_empty() {
+        return Err(PVSSError::LengthMismatch { expected: 1, actual: 0 });
+    }
+
+    let points = EvaluationPoints::indices(access.1);
+    let evaluations: Vec<_> = secrets.iter()
+        .map(|secret| (utils::sample_poly(Some(*secret), t, rng), utils::sample_poly(None, t, rng)))
+        .map(|(secret_poly, random_poly)| (points.evaluate(&secret_poly), points.evaluate(&random_poly)))
+        .collect();
+    let per_receiver = plaintexts_for(2 * secrets.len());
+
+    let mut commitments = vec![Vec::new(); secrets.len()];
+    let mut ciphertexts = Vec::new();
+    for (i, pub_key) in pub_keys.iter().enumerate() {
+        let mut scalars = Vec::new();
+        for ((secret_ys, random_ys), column) in evaluations.iter().zip(commitments.iter_mut()) {
+            let (s, r) = (secret_ys[i], random_ys[i]);
+            column.push(commit(pedcom_params, s, r));
+            scalars.extend([s, r]);
+        }
+        for (c, msg) in pack_scalars(&scalars).iter().enumerate() {
+            let id = receiver_id(LABEL, session_id, dealer_id, i * per_receiver + c);
+            ciphertexts.push(ibe_encrypt::<I>(msg, pub_key, &id));
+        }
+    }
+
+    Ok(VectorSharing { session_id: *session_id, dealer_id, access, commitments, ciphertexts })
+}
+
+impl<I: IbeScheme, G: PedComGroup> VectorSharing<I, G> {
+    fn per_receiver(&self) -> usize {
+        plaintexts_for(2 * self.commitments.len())
+    }
+
+    /// identities receiver `index` needs the keys of to decrypt its shares,
+    /// one per ciphertext
+    pub fn receiver_ids(&self, index: usize) -> Vec<[u8; 96]> {
+        let per_receiver = self.per_receiver();
+        (index * per_receiver..(index + 1) * per_receiver)
+            .map(|c| receiver_id(LABEL, &self.session_id, self.dealer_id, c))
+            .collect()
+    }
+}
+
+/// The checks anybody can run on a vector sharing: the access structure, the
+/// lengths, and the degree of the commitments of every element.
+pub fn verify_vector_public<I: IbeScheme, G: PedComGroup>(
+    sharing: &VectorSharing<I, G>,
+    access: (u64, u64)
+) -> Result<(), PVSSError> {
+    if sharing.access != access {
+        return Err(PVSSError::AccessStructureMismatch { expected: access, actual: sharing.access });
+    }
+    if sharing.commitments.is_empty() {
+        return Err(PVSSError::LengthMismatch { expected: 1, actual: 0 });
+    }
+    check_len(access.1 * sharing.per_receiver() as u64, sharing.ciphertexts.len())?;
+    for column in &sharing.commitments {
+        check_len(access.1, column.len())?;
+        if !degree_check_transcript::<G>(column, access.0, &sharing.session_id) {
+            return Err(PVSSError::DegreeCheckFailed);
+        }
+    }
+    Ok(())
+}
+
+/// Decrypts the shares of receiver `index` and verifies them against the
+/// sharing; `sks[k]` is the key of its k-th identity in
+/// [`VectorSharing::receiver_ids`].
+pub fn decrypt_vector_share<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &VectorSharing<I, G>,
+    access: (u64, u64),
+    index: usize,
+    sks: &[I::SecretKeyID]
+) -> Result<VectorShare<G::Scalar>, PVSSError> {
+    verify_vector_public(sharing, access)?;
+    if index as u64 >= access.1 {
+        return Err(PVSSError::IndexOutOfRange { index, n: access.1 });
+    }
+    let per_receiver = sharing.per_receiver();
+    check_len(per_receiver as u64, sks.len())?;
+    let plaintexts: Vec<[u8; 96]> = sharing.ciphertexts[index * per_receiver..(index + 1) * per_receiver].iter()
+        .zip(sks)
+        .map(|(ciphertext, sk)| ibe_decrypt::<I>(ciphertext, sk))
+        .collect();
+    let scalars: Vec<G::Scalar> = unpack_scalars(&plaintexts, 2 * sharing.commitments.len())
+        .map_err(|_| PVSSError::MalformedShare { index })?;
+    let elements: Vec<_> = scalars.chunks(2)
+        .map(|pair| DecryptedShare { index, s: pair[0], r: pair[1] })
+        .collect();
+    check_vector_share(pedcom_params, sharing, index, &elements)?;
+    Ok(VectorShare { index, elements })
+}
+
+fn check_vector_share<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &VectorSharing<I, G>,
+    index: usize,
+    elements: &[DecryptedShare<G::Scalar>]
+) -> Result<(), PVSSError> {
+    check_len(sharing.commitments.len() as u64, elements.len())?;
+    for (column, element) in sharing.commitments.iter().zip(elements) {
+        if element.index != index || column.get(index) != Some(&commit(pedcom_params, element.s, element.r)) {
+            return Err(PVSSError::CommitmentMismatch { index });
+        }
+    }
+    Ok(())
+}
+
+/// Recovers the shared elements from the published shares. Shares that do not
+/// open their commitments, or repeat an index, are skipped, and the elements
+/// are interpolated from the first t+1 that remain.
+pub fn reconstruct_vector<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &VectorSharing<I, G>,
+    shares: &[VectorShare<G::Scalar>]
+) -> Result<Vec<G::Scalar>, PVSSError> {
+    verify_vector_public(sharing, sharing.access)?;
+    let t = sharing.access.0;
+    let mut seen = std::collections::HashSet::new();
+    let used: Vec<&VectorShare<G::Scalar>> = shares.iter()
+        .filter(|share| seen.insert(share.index)
+            && check_vector_share(pedcom_params, sharing, share.index, &share.elements).is_ok())
+        .take((t + 1) as usize)
+        .collect();
+    if (used.len() as u64) < t + 1 {
+        return Err(PVSSError::NotEnoughShares { needed: t + 1, got: used.len() });
+    }
+    Ok((0..sharing.commitments.len())
+        .map(|e| {
+            let points: Vec<(G::Scalar, G::Scalar)> = used.iter()
+                .map(|share| (G::Scalar::from(share.index as u64 + 1), share.elements[e].s))
+                .collect();
+            utils::lagrange_interpolate_at_zero(&points)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ibe::{self, TestIbe};
+    use crate::prot_pvss_hash_ibe::HashPvss;
+    use crate::prot_pvss_pedcom_ibe::PedersenPvss;
+    use ark_ff::UniformRand;
+    use rand::thread_rng;
+
+    type F = ark_bls12_381::Fr;
+    type Keys = Vec<<TestIbe as IbeScheme>::MasterKeypair>;
+
+    const SESSION: SessionId = [6u8; 32];
+    const DEALER: u64 = 3;
+
+    fn payload_roundtrip<P: Pvss<Ibe = TestIbe, Error = PVSSError>>() {
+        let params = P::setup();
+        let (t, n) = (2, 5);
+        let keys: Keys = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let payload: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        let mut sharing = share_bytes::<P, _>(&params, &pub_keys, &payload, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        assert_eq!(sharing.ciphertext.len(), payload.len() + 16);
+
+        P::verify_public(&params, &sharing.key_sharing, &pub_keys, (t, n)).unwrap();
+        let shares: Vec<_> = (0..n as usize)
+            .map(|i| {
+                let sk = ibe::ibe_extract_id_secret_key::<TestIbe>(&P::receiver_id(&sharing.key_sharing, i), &keys[i]);
+                P::decrypt_share(&params, &sharing.key_sharing, &pub_keys, (t, n), i, &sk).unwrap()
+            })
+            .collect();
+        assert_eq!(reconstruct_bytes(&params, &sharing, &shares[2..]).unwrap(), payload);
+        assert!(matches!(reconstruct_bytes(&params, &sharing, &shares[..2]), Err(PVSSError::NotEnoughShares { .. })));
+
+        // the payload is bound to the dealer
+        sharing.dealer_id += 1;
+        assert!(matches!(reconstruct_bytes(&params, &sharing, &shares[2..]), Err(PVSSError::PayloadDecryptionFailed)));
+        sharing.dealer_id -= 1;
+        sharing.ciphertext[0] ^= 1;
+        assert!(matches!(reconstruct_bytes(&params, &sharing, &shares[2..]), Err(PVSSError::PayloadDecryptionFailed)));
+    }
+
+    #[test]
+    fn test_share_bytes() {
+        payload_roundtrip::<PedersenPvss<TestIbe>>();
+        payload_roundtrip::<HashPvss<TestIbe>>();
+    }
+
+    #[test]
+    fn test_share_vector() {
+        let params = setup::<Bls12G1>();
+        let (t, n) = (2, 5);
+        let keys: Keys = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let secrets: Vec<F> = (0..4).map(|_| F::rand(&mut thread_rng())).collect();
+        let sharing = share_vector::<TestIbe, _, _>(&params, &pub_keys, &secrets, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        // 8 scalars in 3 ciphertexts per receiver
+        assert_eq!(sharing.ciphertexts.len(), 15);
+        verify_vector_public(&sharing, (t, n)).unwrap();
+
+        let shares: Vec<_> = (0..n as usize)
+            .map(|i| {
+                let sks: Vec<_> = sharing.receiver_ids(i).iter()
+                    .map(|id| ibe::ibe_extract_id_secret_key::<TestIbe>(id, &keys[i]))
+                    .collect();
+                decrypt_vector_share(&params, &sharing, (t, n), i, &sks).unwrap()
+            })
+            .collect();
+        assert_eq!(reconstruct_vector(&params, &sharing, &shares[..3]).unwrap(), secrets);
+        assert_eq!(reconstruct_vector(&params, &sharing, &shares[2..]).unwrap(), secrets);
+        assert!(matches!(reconstruct_vector(&params, &sharing, &shares[..2]), Err(PVSSError::NotEnoughShares { needed: 3, got: 2 })));
+
+        // forged and repeated shares are skipped
+        let mut forged = shares[1].clone();
+        forged.elements[3].s += F::from(1u64);
+        assert!(matches!(reconstruct_vector(&params, &sharing, &[shares[0].clone(), forged.clone(), shares[2].clone()]),
+            Err(PVSSError::NotEnoughShares { needed: 3, got: 2 })));
+        let mixed = [forged, shares[0].clone(), shares[0].clone(), shares[3].clone(), shares[4].clone()];
+        assert_eq!(reconstruct_vector(&params, &sharing, &mixed).unwrap(), secrets);
+
+        // one element shared with a polynomial of higher degree
+        let mut cheating = share_vector::<TestIbe, _, _>(&params, &pub_keys, &secrets, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        cheating.commitments[2][4] = sharing.commitments[2][4];
+        assert!(matches!(verify_vector_public(&cheating, (t, n)), Err(PVSSError::DegreeCheckFailed)));
+
+        // bad arguments are errors, not panics
+        assert!(matches!(share_vector::<TestIbe, _, _>(&params, &pub_keys, &secrets, (n, n), &SESSION, DEALER, &mut thread_rng()),
+            Err(PVSSError::InvalidThreshold { t: 5, n: 5 })));
+        assert!(matches!(share_vector::<TestIbe, _, _>(&params, &pub_keys[1..], &secrets, (t, n), &SESSION, DEALER, &mut thread_rng()),
+            Err(PVSSError::LengthMismatch { expected: 5, actual: 4 })));
+        assert!(matches!(share_vector::<TestIbe, _, _>(&params, &pub_keys, &[], (t, n), &SESSION, DEALER, &mut thread_rng()),
+            Err(PVSSError::LengthMismatch { expected: 1, actual: 0 })));
+    }
+}
@@ -0,0 +1,207 @@
+//! Evaluation of a polynomial at many points, such as at x = 1..=n for the
+//! shares of a sharing. Horner's method at every point costs O(n t) for a
+//! polynomial of degree t; the subproduct tree costs O(n log^2 n), with its
+//! polynomial products done by FFTs over radix-2 domains of the scalar
+//! field. Fields without large enough domains (secp256k1, ed25519) fall back
+//! to Horner's method, as do polynomials of small degree.
+
+use std::sync::{Arc, OnceLock};
+use ark_ff::{FftField, Zero};
+use ark_poly::{DenseUVPolynomial, EvaluationDomain, GeneralEvaluationDomain, Polynomial, univariate::DensePolynomial};
+use ark_std::{cfg_chunks, cfg_iter};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::utils;
+
+/// points per leaf of the tree; the remainders at the leaves are evaluated
+/// with Horner's method
+const LEAF: usize = 32;
+
+/// polynomials of lower degree are evaluated with Horner's method, which is
+/// about as fast at degree 512 for 1024 points, and 3.5 times slower at
+/// degree 2048 for 4096 points
+const MIN_DEGREE: usize = 512;
+
+/// Points to evaluate polynomials at, with their subproduct tree.
+pub struct EvaluationPoints<F: FftField> {
+    points: Vec<F>,
+    /// the levels of the tree, from the leaves up: the products of the
+    /// X - x over blocks of `LEAF` points, then the products of pairs of the
+    /// nodes below. Built on the first polynomial of degree `MIN_DEGREE`.
+    tree: OnceLock<Vec<Vec<Node<F>>>>,
+}
+
+/// a node of the subproduct tree
+struct Node<F: FftField> {
+    m: DensePolynomial<F>,
+    /// the inverse of the reversed m, modulo X^k for the k the division of
+    /// the remainder at the parent needs
+    m_rev_inverse: Vec<F>,
+}
+
+impl<F: FftField> EvaluationPoints<F> {
+    pub fn new(points: Vec<F>) -> Self {
+        EvaluationPoints { points, tree: OnceLock::new() }
+    }
+
+    /// Whether the tree pays off for the number of points, and the field has
+    /// the FFT domains it needs.
+    fn fast(&self) -> bool {
+        self.points.len() > MIN_DEGREE && GeneralEvaluationDomain::<F>::new(2 * self.points.len()).is_some()
+    }
+
+    fn build_tree(points: &[F]) -> Vec<Vec<Node<F>>> {
+        let leaves: Vec<DensePolynomial<F>> = cfg_chunks!(points, LEAF)
+            .map(|xs| xs.iter().fold(DensePolynomial::from_coefficients_vec(vec![F::one()]), |acc, x| {
+                acc.naive_mul(&DensePolynomial::from_coefficients_vec(vec![-*x, F::one()]))
+            }))
+            .collect();
+        let mut products = vec![leaves];
+        while products.last().unwrap().len() > 1 {
+            let level = cfg_chunks!(products.last().unwrap(), 2)
+                .map(|pair| match pair {
+                    [a, b] => mul(a, b),
+                    [a] => a.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+            products.push(level);
+        }
+
+        // the remainder at the parent has fewer coefficients than the parent,
+        // so its quotient by m has at most len(parent) - len(m) + 1; the root
+        // divides polynomials of any degree and has its inverse computed then
+        let mut tree: Vec<Vec<Node<F>>> = Vec::new();
+        for (level, parents) in products.iter().zip(products.iter().skip(1).map(Some).chain([None])) {
+            let nodes = cfg_iter!(level).enumerate()
+                .map(|(j, m)| {
+                    let k = parents.map_or(0, |parents| parents[j / 2].coeffs.len() - m.coeffs.len() + 1);
+                    Node { m: m.clone(), m_rev_inverse: inverse_series(&reversed(m), k) }
+                })
+                .collect();
+            tree.push(nodes);
+        }
+        tree
+    }
+
+    /// The points 1..=n of the shares of a sharing, computed once per field
+    /// and n.
+    pub fn indices(n: u64) -> Arc<Self> {
+        utils::cached(n, || EvaluationPoints::new((1..=n).map(F::from).collect()))
+    }
+
+    /// The evaluations of `poly` at the points.
+    pub fn evaluate(&self, poly: &DensePolynomial<F>) -> Vec<F> {
+        if poly.degree() < MIN_DEGREE || !self.fast() {
+            return cfg_iter!(self.points).map(|x| poly.evaluate(x)).collect();
+        }
+
+        // going down the tree, the remainders of poly modulo the nodes
+        let tree = self.tree.get_or_init(|| Self::build_tree(&self.points));
+        let mut remainders = vec![poly.clone()];
+        for level in tree.iter().rev() {
+            remainders = cfg_iter!(level).enumerate()
+                .map(|(j, node)| rem(&remainders[j / 2], node))
+                .collect();
+        }
+        let blocks: Vec<Vec<F>> = cfg_iter!(remainders).zip(cfg_chunks!(self.points, LEAF))
+            .map(|(r, xs)| xs.iter().map(|x| r.evaluate(x)).collect())
+            .collect();
+        blocks.concat()
+    }
+}
+
+/// the product, by FFT where the field has a domain of its size and the
+/// factors are not too short for it to pay off
+fn mul<F: FftField>(a: &DensePolynomial<F>, b: &DensePolynomial<F>) -> DensePolynomial<F> {
+    if a.is_zero() || b.is_zero() {
+        return DensePolynomial::zero();
+    }
+    if a.coeffs.len().min(b.coeffs.len()) < LEAF {
+        return a.naive_mul(b);
+    }
+    match GeneralEvaluationDomain::<F>::new(a.coeffs.len() + b.coeffs.len() - 1) {
+        Some(_) => a * b,
+        None => a.naive_mul(b),
+    }
+}
+
+/// the coefficients of a times b, modulo X^k
+fn mul_mod<F: FftField>(a: &[F], b: &[F], k: usize) -> Vec<F> {
+    let a = DensePolynomial::from_coefficients_slice(&a[..a.len().min(k)]);
+    let b = DensePolynomial::from_coefficients_slice(&b[..b.len().min(k)]);
+    let mut product = mul(&a, &b).coeffs;
+    product.resize(k, F::zero());
+    product
+}
+
+fn reversed<F: FftField>(m: &DensePolynomial<F>) -> Vec<F> {
+    m.coeffs.iter().rev().copied().collect()
+}
+
+/// g with f g = 1 modulo X^k, by Newton iteration: g <- g (2 - f g)
+fn inverse_series<F: FftField>(f: &[F], k: usize) -> Vec<F> {
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut g = vec![f[0].inverse().expect("the constant coefficient is not zero")];
+    while g.len() < k {
+        let precision = (2 * g.len()).min(k);
+        let mut e: Vec<F> = mul_mod(f, &g, precision).iter().map(|c| -*c).collect();
+        e[0] += F::from(2u64);
+        g = mul_mod(&g, &e, precision);
+    }
+    g
+}
+
+/// a modulo the monic m of the node, with the quotient from the reversed
+/// polynomials: rev(q) = rev(a) / rev(m) modulo X^(deg a - deg m + 1)
+fn rem<F: FftField>(a: &DensePolynomial<F>, node: &Node<F>) -> DensePolynomial<F> {
+    let m = &node.m;
+    if a.coeffs.len() < m.coeffs.len() {
+        return a.clone();
+    }
+    let k = a.coeffs.len() - m.coeffs.len() + 1;
+    let computed;
+    let m_rev_inverse = if node.m_rev_inverse.len() >= k {
+        &node.m_rev_inverse
+    } else {
+        computed = inverse_series(&reversed(m), k);
+        &computed
+    };
+    let a_rev: Vec<F> = a.coeffs.iter().rev().take(k).copied().collect();
+    let mut q = mul_mod(&a_rev, m_rev_inverse, k);
+    q.reverse();
+    let qm = mul(&DensePolynomial::from_coefficients_vec(q), m);
+    let mut r = a - &qm;
+    r.coeffs.truncate(m.coeffs.len() - 1);
+    DensePolynomial::from_coefficients_vec(r.coeffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_ff::UniformRand;
+    use super::*;
+
+    fn check<F: FftField>(points: Vec<F>, degree: usize) {
+        let mut rng = rand::thread_rng();
+        let poly = DensePolynomial::<F>::rand(degree, &mut rng);
+        let expected: Vec<F> = points.iter().map(|x| poly.evaluate(x)).collect();
+        assert_eq!(EvaluationPoints::new(points).evaluate(&poly), expected);
+    }
+
+    #[test]
+    fn test_multipoint_evaluation() {
+        type F = ark_bls12_381::Fr;
+        let mut rng = rand::thread_rng();
+        for (n, degree) in [(10, 4), (600, 512), (777, 1000), (1000, 600), (2048, 1023)] {
+            check::<F>((1..=n as u64).map(F::from).collect(), degree);
+        }
+        check::<F>((0..700).map(|_| F::rand(&mut rng)).collect(), 699);
+        assert!(EvaluationPoints::<F>::new((1..=600u64).map(F::from).collect()).evaluate(&DensePolynomial::zero()).iter().all(F::is_zero));
+        assert_eq!(EvaluationPoints::<F>::indices(300).points.len(), 300);
+        // a field without radix-2 domains above 64
+        check::<ark_secp256k1::Fr>((1..=600u64).map(ark_secp256k1::Fr::from).collect(), 550);
+    }
+}
@@ -1,17 +1,29 @@
 use std::fmt;
-use std::ops::{Add ,Mul};
-use ark_ff::field_hashers::DefaultFieldHasher;
+use std::ops::Mul;
+use ark_ff::{field_hashers::DefaultFieldHasher, PrimeField};
 use ark_ec::{
     short_weierstrass::{Affine, Projective},
-    AffineRepr, CurveGroup,
+    AffineRepr, CurveConfig, CurveGroup,
     hashing::{
         curve_maps::wb::WBMap, map_to_curve_hasher::MapToCurveBasedHasher, HashToCurve, HashToCurveError
     }
 };
-use ark_poly::{Polynomial, univariate::DensePolynomial};
-use ark_ff::UniformRand;
+use ark_poly::univariate::DensePolynomial;
+use ark_std::cfg_iter;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use ark_bls12_381::{g1::Config as G1Config};
-use sha2::Sha256;
+use ark_secp256k1::Config as Secp256k1Config;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use rust_bindings::IbeError;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::ibe::SessionId;
+use crate::multipoint::EvaluationPoints;
+use crate::utils;
 
 /// Error enum to wrap underlying failures in HinTS operations, 
 /// or wrap errors coming from dependencies (namely, arkworks).
@@ -19,12 +31,114 @@ use sha2::Sha256;
 pub enum PVSSError {
     /// Error coming from `ark_ec` upon hashing to curve
     HashingError(HashToCurveError),
+    /// Error coming from `ark_serialize` upon decoding a sharing
+    SerializationError(SerializationError),
+    /// Error coming from the IBE upon decoding a ciphertext
+    IbeError(IbeError),
+    /// the encoding has a format version this library does not read
+    UnsupportedVersion(u8),
+    /// the encoding is a sharing of another protocol
+    ProtocolMismatch { expected: u8, actual: u8 },
+    /// the commitments do not lie on a polynomial of the claimed degree
+    DegreeCheckFailed,
+    /// the decrypted share does not open the commitment at `index`
+    CommitmentMismatch { index: usize },
+    /// the decrypted share does not match the hashes at `index`
+    HashMismatch { index: usize },
+    /// the decrypted plaintext at `index` does not encode field elements
+    MalformedShare { index: usize },
+    /// the share `index` does not exist in a sharing among `n` parties
+    IndexOutOfRange { index: usize, n: u64 },
+    /// a component of the sharing, or the key list, has the wrong length
+    LengthMismatch { expected: u64, actual: u64 },
+    /// the sharing was dealt for a different access structure `(t, n)`
+    AccessStructureMismatch { expected: (u64, u64), actual: (u64, u64) },
+    /// the threshold `t` is not below the number of shares `n`
+    InvalidThreshold { t: u64, n: u64 },
+    /// reconstruction needs `needed` valid shares, but only `got` were given
+    NotEnoughShares { needed: u64, got: usize },
+    /// two published shares claim the same `index`
+    DuplicateIndex { index: usize },
+    /// the complaint about share `index` does not prove the dealer wrong
+    InvalidComplaint { index: usize },
+    /// a party has no share of the qualified dealing of `dealer`
+    MissingShare { dealer: usize },
+    /// the verification key of party `index` does not match the commitments
+    InvalidVerificationKey { index: usize },
+    /// the dealing of `dealer` does not reshare the value it was committed to
+    ResharingMismatch { dealer: usize },
+    /// the reshared secret differs from the one committed to
+    SecretMismatch,
+    /// the sharing gives `receiver` a different weight than expected
+    WeightMismatch { receiver: usize },
+    /// the payload does not decrypt under the reconstructed key
+    PayloadDecryptionFailed,
+    /// the 32-byte secret, read big-endian, is not below the modulus of the scalar field
+    SecretOutOfRange,
+    /// `participant` has no key in the registry
+    UnknownParticipant { participant: u64 },
+    /// `participant` is registered, or listed as a receiver, more than once
+    DuplicateParticipant { participant: u64 },
+    /// `participant` registers the key `registered_by` already registered
+    DuplicateKey { participant: u64, registered_by: u64 },
+    /// the proof of possession of `participant` is not a key of its identity
+    InvalidProofOfPossession { participant: u64 },
 }
 
 impl fmt::Display for PVSSError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             PVSSError::HashingError(ref err) => err.fmt(f),
+            PVSSError::SerializationError(ref err) => err.fmt(f),
+            PVSSError::IbeError(ref err) => err.fmt(f),
+            PVSSError::UnsupportedVersion(version) =>
+                write!(f, "unsupported encoding version {}", version),
+            PVSSError::ProtocolMismatch { expected, actual } =>
+                write!(f, "encoding has protocol tag {}, expected {}", actual, expected),
+            PVSSError::DegreeCheckFailed =>
+                write!(f, "commitments fail the degree check"),
+            PVSSError::CommitmentMismatch { index } =>
+                write!(f, "share {} does not match its commitment", index),
+            PVSSError::HashMismatch { index } =>
+                write!(f, "share {} does not match its hashes", index),
+            PVSSError::MalformedShare { index } =>
+                write!(f, "share {} does not decode to field elements", index),
+            PVSSError::IndexOutOfRange { index, n } =>
+                write!(f, "share index {} out of range for {} shares", index, n),
+            PVSSError::LengthMismatch { expected, actual } =>
+                write!(f, "expected {} elements, got {}", expected, actual),
+            PVSSError::AccessStructureMismatch { expected, actual } =>
+                write!(f, "sharing is for access structure {:?}, expected {:?}", actual, expected),
+            PVSSError::InvalidThreshold { t, n } =>
+                write!(f, "threshold {} is not below the number of shares {}", t, n),
+            PVSSError::NotEnoughShares { needed, got } =>
+                write!(f, "need {} shares to reconstruct, got {}", needed, got),
+            PVSSError::DuplicateIndex { index } =>
+                write!(f, "share {} was given more than once", index),
+            PVSSError::InvalidComplaint { index } =>
+                write!(f, "complaint about share {} is not justified", index),
+            PVSSError::MissingShare { dealer } =>
+                write!(f, "no share of the dealing of {}", dealer),
+            PVSSError::InvalidVerificationKey { index } =>
+                write!(f, "verification key of party {} does not match the commitments", index),
+            PVSSError::ResharingMismatch { dealer } =>
+                write!(f, "dealing of {} does not reshare its committed value", dealer),
+            PVSSError::SecretMismatch =>
+                write!(f, "reshared secret does not match its commitment"),
+            PVSSError::WeightMismatch { receiver } =>
+                write!(f, "sharing gives receiver {} a different weight", receiver),
+            PVSSError::PayloadDecryptionFailed =>
+                write!(f, "payload does not decrypt under the reconstructed key"),
+            PVSSError::SecretOutOfRange =>
+                write!(f, "secret is not below the modulus of the scalar field"),
+            PVSSError::UnknownParticipant { participant } =>
+                write!(f, "participant {} is not registered", participant),
+            PVSSError::DuplicateParticipant { participant } =>
+                write!(f, "participant {} appears more than once", participant),
+            PVSSError::DuplicateKey { participant, registered_by } =>
+                write!(f, "participant {} registers the key of participant {}", participant, registered_by),
+            PVSSError::InvalidProofOfPossession { participant } =>
+                write!(f, "proof of possession of participant {} does not verify", participant),
         }
     }
 }
@@ -37,66 +151,272 @@ impl From<HashToCurveError> for PVSSError {
     }
 }
 
-type F = ark_bls12_381::Fr;
-pub type PedComParams = (Affine<G1Config>, Affine<G1Config>);
-pub type PedComCommitment = Affine<G1Config>;
-pub type PedComMessage = ark_bls12_381::Fr;
-pub type PedComRandomness = ark_bls12_381::Fr;
+impl From<SerializationError> for PVSSError {
+    fn from(err: SerializationError) -> PVSSError {
+        PVSSError::SerializationError(err)
+    }
+}
+
+impl From<IbeError> for PVSSError {
+    fn from(err: IbeError) -> PVSSError {
+        PVSSError::IbeError(err)
+    }
+}
+
+/// checks that a component of a sharing among `expected` parties has one entry per party
+pub fn check_len(expected: u64, actual: usize) -> Result<(), PVSSError> {
+    if actual as u64 != expected {
+        return Err(PVSSError::LengthMismatch { expected, actual: actual as u64 });
+    }
+    Ok(())
+}
+
+/// checks that the threshold t of `access` is below the number of shares n;
+/// otherwise the sharing has no redundancy to check
+pub fn check_access(access: (u64, u64)) -> Result<(), PVSSError> {
+    let (t, n) = access;
+    if t >= n {
+        return Err(PVSSError::InvalidThreshold { t, n });
+    }
+    Ok(())
+}
+
+impl PVSSError {
+    /// whether the error proves the dealer misbehaved with the share at hand,
+    /// as opposed to a malformed request
+    pub fn is_dealer_fault(&self) -> bool {
+        matches!(self,
+            PVSSError::DegreeCheckFailed
+            | PVSSError::CommitmentMismatch { .. }
+            | PVSSError::HashMismatch { .. }
+            | PVSSError::MalformedShare { .. })
+    }
+}
+
+/// A prime-order group to commit in, with scalars that encode in 32 bytes.
+/// Every [`PedComCurve`] is one; [`crate::ristretto::Ristretto`] adapts
+/// curve25519-dalek.
+pub trait PedComGroup: 'static {
+    type Scalar: PrimeField;
+    type Element: Copy + Eq + fmt::Debug + Send + Sync + CanonicalSerialize + CanonicalDeserialize;
+
+    fn generator() -> Self::Element;
+    /// a generator nobody knows the discrete logarithm of
+    fn hash_to_generator(msg: &[u8]) -> Result<Self::Element, PVSSError>;
+    fn zero() -> Self::Element;
+    fn add(a: &Self::Element, b: &Self::Element) -> Self::Element;
+    fn mul(a: &Self::Element, s: &Self::Scalar) -> Self::Element;
+    /// the multi-scalar multiplication `sum_i scalars_i * bases_i`
+    fn msm(bases: &[Self::Element], scalars: &[Self::Scalar]) -> Self::Element;
+}
+
+/// An arkworks curve with its own derivation of independent generators.
+pub trait PedComCurve: CurveGroup {
+    fn hash_to_generator(msg: &[u8]) -> Result<Self::Affine, PVSSError>;
+}
+
+impl<C: PedComCurve> PedComGroup for C {
+    type Scalar = C::ScalarField;
+    type Element = C::Affine;
+
+    fn generator() -> C::Affine {
+        C::Affine::generator()
+    }
+
+    fn hash_to_generator(msg: &[u8]) -> Result<C::Affine, PVSSError> {
+        <C as PedComCurve>::hash_to_generator(msg)
+    }
+
+    fn zero() -> C::Affine {
+        C::Affine::zero()
+    }
+
+    fn add(a: &C::Affine, b: &C::Affine) -> C::Affine {
+        (*a + *b).into_affine()
+    }
+
+    fn mul(a: &C::Affine, s: &C::ScalarField) -> C::Affine {
+        a.mul(*s).into_affine()
+    }
+
+    fn msm(bases: &[C::Affine], scalars: &[C::ScalarField]) -> C::Affine {
+        assert_eq!(bases.len(), scalars.len());
+        C::msm_unchecked(bases, scalars).into_affine()
+    }
+}
+
+/// BLS12-381 G1, hashed to with the SSWU map of the BLS signature suite.
+impl PedComCurve for Projective<G1Config> {
+    fn hash_to_generator(msg: &[u8]) -> Result<Affine<G1Config>, PVSSError> {
+        const DST_G1: &str = "BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_";
+        let g1_mapper = MapToCurveBasedHasher::<
+            Projective<G1Config>,
+            DefaultFieldHasher<Sha256, 128>,
+            WBMap<G1Config>,
+        >::new(DST_G1.as_bytes())?;
+        g1_mapper.hash(msg).map_err(PVSSError::HashingError)
+    }
+}
+
+/// secp256k1, hashed to by try-and-increment: arkworks has no SSWU map for it.
+/// The curve has cofactor 1, so every point found is a generator.
+impl PedComCurve for Projective<Secp256k1Config> {
+    fn hash_to_generator(msg: &[u8]) -> Result<Affine<Secp256k1Config>, PVSSError> {
+        const DST: &[u8] = b"pqppvss/secp256k1/try-and-increment";
+        // about half of all x have a point, 256 failures in a row do not happen
+        for counter in 0..=u8::MAX {
+            let digest: [u8; 32] = Sha256::new()
+                .chain_update(DST)
+                .chain_update(msg)
+                .chain_update([counter])
+                .finalize()
+                .into();
+            let x = <Secp256k1Config as CurveConfig>::BaseField::from_be_bytes_mod_order(&digest);
+            if let Some(point) = Affine::<Secp256k1Config>::get_point_from_x_unchecked(x, digest[0] & 1 == 1) {
+                return Ok(point);
+            }
+        }
+        Err(PVSSError::HashingError(HashToCurveError::MapToCurveError("no point found".to_string())))
+    }
+}
 
-fn hash_to_curve(
-    msg: impl AsRef<[u8]>
-) -> Result<Affine<G1Config>, PVSSError> {
-    const DST_G1: &str = "BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_";
-    let g1_mapper = MapToCurveBasedHasher::<
-        Projective<G1Config>,
-        DefaultFieldHasher<Sha256, 128>,
-        WBMap<G1Config>,
-    >::new(DST_G1.as_bytes())?;
-    g1_mapper.hash(msg.as_ref()).map_err(|e| PVSSError::HashingError(e))
+pub type Bls12G1 = Projective<G1Config>;
+/// the generators `g` and `h` of the commitments `g^m h^r`
+pub struct PedComParams<G: PedComGroup = Bls12G1> {
+    pub g: G::Element,
+    pub h: G::Element,
 }
+pub type PedComCommitment<G = Bls12G1> = <G as PedComGroup>::Element;
 
-fn compute_scrape_coefficient(n: u64, i: u64) -> F {
+/// lambda_i by its definition, in O(n) for each i
+#[cfg(test)]
+fn compute_scrape_coefficient<F: PrimeField>(n: u64, i: u64) -> F {
     // lambda_i = product( 1 / (i - j) ) for all j in 1..n and j != i
-    let lambda_i = (1..=n)
+    (1..=n)
         .filter(|&j| j != i)
         .fold(F::from(1u64), |acc, j| {
             acc * (F::from(1u64) / (F::from(i) - F::from(j)))
-        });
-    lambda_i
+        })
+}
+
+/// lambda_i of every i in 1..=n, computed once per field and n. The product
+/// of the i - j is (i-1)! (n-i)! (-1)^(n-i), so all of them take O(n)
+/// multiplications and a single inversion, of (n-1)!.
+pub fn scrape_coefficients<F: PrimeField>(n: u64) -> Arc<Vec<F>> {
+    utils::cached(n, || {
+        if n == 0 {
+            return Vec::new();
+        }
+        // factorials[k] = k!, for k < n
+        let mut factorials = vec![F::one(); n as usize];
+        for k in 1..n as usize {
+            factorials[k] = factorials[k - 1] * F::from(k as u64);
+        }
+        // inverse_factorials[k] = 1 / k!
+        let mut inverse_factorials = vec![F::one(); n as usize];
+        inverse_factorials[n as usize - 1] = factorials[n as usize - 1].inverse().unwrap();
+        for k in (1..n as usize).rev() {
+            inverse_factorials[k - 1] = inverse_factorials[k] * F::from(k as u64);
+        }
+        (1..=n as usize).map(|i| {
+            let lambda_i = inverse_factorials[i - 1] * inverse_factorials[n as usize - i];
+            if (n as usize - i).is_multiple_of(2) { lambda_i } else { -lambda_i }
+        }).collect()
+    })
+}
+
+/// The weights w_i = z(i) * lambda_i of a SCRAPE degree check with a random
+/// z: the commitments v_i lie on a polynomial of degree `degree` iff, but for
+/// a negligible probability over z, sum_i w_i v_i = 0. All zero when every
+/// set of commitments passes.
+pub fn degree_check_weights<F: PrimeField, R: Rng>(coefficients: &[F], degree: u64, rng: &mut R) -> Vec<F> {
+    let n = coefficients.len() as u64;
+    if n < degree + 2 {
+        return vec![F::zero(); n as usize];
+    }
+    let z = DensePolynomial { coeffs: (0..=(n - degree - 2)).map(|_| F::rand(rng)).collect() };
+    let z_evaluations = EvaluationPoints::indices(n).evaluate(&z);
+    cfg_iter!(coefficients).zip(z_evaluations).map(|(lambda, z_i)| z_i * lambda).collect()
+}
+
+/// The Lagrange coefficients at `x` of the polynomial of degree < xs.len()
+/// through the points at `xs`, which must be distinct.
+pub fn lagrange_coefficients<F: PrimeField>(xs: &[F], x: F) -> Vec<F> {
+    (0..xs.len()).map(|j| {
+        (0..xs.len())
+            .filter(|&m| m != j)
+            .fold(F::one(), |l, m| l * (x - xs[m]) / (xs[j] - xs[m]))
+    }).collect()
 }
 
-pub fn setup() -> PedComParams {
-    let g = Affine::<G1Config>::generator();
-    let h = hash_to_curve(b"Pedersen Commitment Generator").unwrap();
-    (g, h)
+/// Interpolates in the exponent: the commitment at `x` to the polynomial
+/// committed to at the points `xs`.
+pub fn interpolate_commitment<G: PedComGroup>(xs: &[G::Scalar], commitments: &[G::Element], x: G::Scalar) -> G::Element {
+    G::msm(commitments, &lagrange_coefficients(xs, x))
 }
 
-pub fn commit(
-    params: &PedComParams,
-    m: PedComMessage,
-    r: PedComRandomness
-) -> PedComCommitment {
-    let (g, h) = params;
-    (g.mul(m) + h.mul(r)).into_affine()
+/// the commitment generators of group `G`: its generator, and a second one
+/// hashed to the group, whose discrete logarithm nobody knows
+pub fn setup<G: PedComGroup>() -> PedComParams<G> {
+    let g = G::generator();
+    let h = G::hash_to_generator(b"Pedersen Commitment Generator").unwrap();
+    PedComParams { g, h }
 }
 
-pub fn degree_check(commitments: &[PedComCommitment], degree: u64) -> bool {
+pub fn commit<G: PedComGroup>(
+    params: &PedComParams<G>,
+    m: G::Scalar,
+    r: G::Scalar
+) -> PedComCommitment<G> {
+    G::add(&G::mul(&params.g, &m), &G::mul(&params.h, &r))
+}
+
+/// Whether the commitments lie on a polynomial of degree `degree`: the SCRAPE
+/// check with the weights of [`degree_check_weights`], as a single
+/// multi-scalar multiplication. The dual codeword z is sampled afresh from
+/// the thread RNG, so a cheating set of commitments passes with probability
+/// 1/|F| and the outcome cannot be replayed; [`degree_check_transcript`] is
+/// the reproducible check.
+pub fn degree_check<G: PedComGroup>(commitments: &[PedComCommitment<G>], degree: u64) -> bool {
+    degree_check_with_rng::<G, _>(commitments, degree, &mut rand::thread_rng())
+}
+
+/// [`degree_check`] with z sampled from `rng`: the interactive check, where
+/// the verifier picks z after the dealer has committed.
+pub fn degree_check_with_rng<G: PedComGroup, R: Rng>(commitments: &[PedComCommitment<G>], degree: u64, rng: &mut R) -> bool {
     let n = commitments.len() as u64;
-    let d = degree as u64;
-    if n < d + 2 {
+    if n < degree + 2 {
         return true;
     }
 
-    let mut rng = rand::thread_rng();
-    let z = DensePolynomial { coeffs: (0..=(n - d - 2)).map(|_| F::rand(&mut rng)).collect() };
-    let sum: PedComCommitment = (1..=n).fold(PedComCommitment::zero(), |acc, i| {
-        let scrape_coeff_i = compute_scrape_coefficient(n, i);
-        let v_i = commitments[i as usize - 1];
-        let z_i = z.evaluate(&F::from(i));
-        acc.add(v_i.mul(z_i * scrape_coeff_i)).into()
-    });
+    let weights = degree_check_weights(&scrape_coefficients::<G::Scalar>(n), degree, rng);
+    G::msm(commitments, &weights) == G::zero()
+}
+
+/// [`degree_check`] with z derived from the transcript, by Fiat-Shamir: the
+/// same commitments, degree and session always get the same z, so anybody
+/// can replay the check of a verifier. A dealer that wants to pass with a
+/// polynomial of higher degree has to find commitments whose own z cancels
+/// them, which takes about |F| hashes.
+pub fn degree_check_transcript<G: PedComGroup>(commitments: &[PedComCommitment<G>], degree: u64, session_id: &SessionId) -> bool {
+    degree_check_with_rng::<G, _>(commitments, degree, &mut transcript_rng::<G>(commitments, degree, session_id))
+}
 
-    return sum == PedComCommitment::zero();
+/// The RNG [`degree_check_transcript`] samples z from: ChaCha20 keyed with
+/// the hash of the session, the degree and the commitments.
+pub fn transcript_rng<G: PedComGroup>(commitments: &[PedComCommitment<G>], degree: u64, session_id: &SessionId) -> ChaCha20Rng {
+    let mut hasher = Sha256::new()
+        .chain_update(b"pqppvss/scrape-degree-check")
+        .chain_update(session_id)
+        .chain_update(degree.to_be_bytes())
+        .chain_update((commitments.len() as u64).to_be_bytes());
+    let mut bytes = Vec::new();
+    for commitment in commitments {
+        bytes.clear();
+        commitment.serialize_compressed(&mut bytes).unwrap();
+        hasher.update(&bytes);
+    }
+    ChaCha20Rng::from_seed(hasher.finalize().into())
 }
 
 #[cfg(test)]
@@ -115,11 +435,87 @@ mod tests {
         let z = DensePolynomial { coeffs: (0..=(n - d - 2)).map(|_| F::rand(&mut rng)).collect() };
         // compute sum of p(i) * z(i) * compute_scrape_coefficient(n, i) for i in 1..=n
         let sum: F = (1..=n).map(|i| {
-            let scrape_coeff = compute_scrape_coefficient(n, i);
+            let scrape_coeff: F = compute_scrape_coefficient(n, i);
             p.evaluate(&F::from(i)) * z.evaluate(&F::from(i)) * scrape_coeff
         }).sum();
 
         assert_eq!(sum, F::from(0), "The sum should be zero for the scrape test");
     }
 
+    #[test]
+    fn test_scrape_coefficients() {
+        for n in [0, 1, 2, 7, 40] {
+            let expected: Vec<F> = (1..=n).map(|i| compute_scrape_coefficient(n, i)).collect();
+            assert_eq!(*scrape_coefficients::<F>(n), expected);
+            assert_eq!(*scrape_coefficients::<ark_secp256k1::Fr>(n), (1..=n).map(|i| compute_scrape_coefficient(n, i)).collect::<Vec<_>>());
+        }
+    }
+
+    /// commitments to a polynomial of degree t pass the degree check with
+    /// degree t, and fail it with degree t-1
+    fn check_group<G: PedComGroup>() {
+        let params = setup::<G>();
+        assert_ne!(params.g, params.h);
+        assert_ne!(params.h, G::zero());
+        assert_eq!(G::hash_to_generator(b"Pedersen Commitment Generator").unwrap(), params.h);
+        assert_ne!(G::hash_to_generator(b"another generator").unwrap(), params.h);
+
+        let (t, n) = (4u64, 12u64);
+        let mut rng = rand::thread_rng();
+        let p = DensePolynomial { coeffs: (0..=t).map(|_| G::Scalar::rand(&mut rng)).collect() };
+        let q = DensePolynomial { coeffs: (0..=t).map(|_| G::Scalar::rand(&mut rng)).collect() };
+        let commitments: Vec<_> = (1..=n)
+            .map(|i| commit::<G>(&params, p.evaluate(&G::Scalar::from(i)), q.evaluate(&G::Scalar::from(i))))
+            .collect();
+        assert!(degree_check::<G>(&commitments, t));
+        assert!(!degree_check::<G>(&commitments, t - 1));
+
+        // commitments are additively homomorphic
+        let (a, b) = (G::Scalar::rand(&mut rng), G::Scalar::rand(&mut rng));
+        assert_eq!(
+            G::add(&commit::<G>(&params, a, b), &commit::<G>(&params, b, a)),
+            commit::<G>(&params, a + b, a + b)
+        );
+    }
+
+    /// commitments to a polynomial of degree t+1, from a fixed seed, fail
+    /// the degree check with degree t in both modes, every time
+    #[test]
+    fn test_deterministic_degree_check() {
+        let params = setup::<Bls12G1>();
+        let (t, n) = (5u64, 16u64);
+        let mut rng = ChaCha20Rng::from_seed([7u8; 32]);
+        let p = DensePolynomial { coeffs: (0..=t + 1).map(|_| F::rand(&mut rng)).collect() };
+        let commitments: Vec<_> = (1..=n)
+            .map(|i| commit::<Bls12G1>(&params, p.evaluate(&F::from(i)), F::from(i)))
+            .collect();
+        for session_id in [[0u8; 32], [1u8; 32]] {
+            assert!(degree_check_transcript::<Bls12G1>(&commitments, t + 1, &session_id));
+            assert!(!degree_check_transcript::<Bls12G1>(&commitments, t, &session_id));
+            let z = |session_id| degree_check_weights::<F, _>(&scrape_coefficients(n), t, &mut transcript_rng::<Bls12G1>(&commitments, t, session_id));
+            assert_eq!(z(&session_id), z(&session_id));
+        }
+        assert!(!degree_check_with_rng::<Bls12G1, _>(&commitments, t, &mut ChaCha20Rng::from_seed([8u8; 32])));
+        assert!(degree_check_with_rng::<Bls12G1, _>(&commitments, t + 1, &mut ChaCha20Rng::from_seed([8u8; 32])));
+    }
+
+    #[test]
+    fn test_bls12_381() {
+        check_group::<Bls12G1>();
+        let h = setup::<Bls12G1>().h;
+        assert!(h.is_on_curve() && h.is_in_correct_subgroup_assuming_on_curve());
+    }
+
+    #[test]
+    fn test_secp256k1() {
+        check_group::<Projective<Secp256k1Config>>();
+        assert!(setup::<Projective<Secp256k1Config>>().h.is_on_curve());
+    }
+
+    #[cfg(feature = "ristretto")]
+    #[test]
+    fn test_ristretto() {
+        check_group::<crate::ristretto::Ristretto>();
+    }
+
 }
\ No newline at end of file
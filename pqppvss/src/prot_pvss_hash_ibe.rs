@@ -1,103 +1,454 @@
 use ark_poly::univariate::DensePolynomial;
-use rust_bindings::{lattice_ibe_ffi::MasterPublicKey, IbeCiphertext, IbeSecretKeyID};
+use ark_ff::PrimeField;
+use rust_bindings::IbeScheme;
 use ark_poly::Polynomial;
 use rand::Rng;
+use std::marker::PhantomData;
+use ark_std::cfg_into_iter;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use ark_serialize::CanonicalSerialize;
 
 use crate::ibe::*;
+use crate::pedcom::{check_access, check_len, PVSSError};
+use crate::pvss::Pvss;
+use crate::serialization::*;
+use crate::multipoint::EvaluationPoints;
 use crate::utils;
 
 pub type F = ark_bls12_381::Fr;
 pub type Hash = [u8; 32];
 
-pub struct Sharing {
+/// domain separator of the Fiat-Shamir transcript
+const CHALLENGE_TAG: &[u8] = b"pqppvss/prot_pvss_hash_ibe/challenge/v1";
+
+/// protocol label of the receiver identities
+pub const LABEL: &[u8] = b"pqppvss/prot_pvss_hash_ibe";
+
+pub struct Sharing<I: IbeScheme> {
+    pub session_id: SessionId,
+    pub dealer_id: u64,
+    pub access: (u64, u64), // (t, n), bound into the challenge
     pub committed_secret: Vec<F>, // c = W(x)
     pub csh: Vec<Hash>, // H(s_i, r_i)
     pub cpt: Vec<Hash>, // H(b_i, q_i)
-    pub ciphertexts: Vec<IbeCiphertext>, // E_i
+    pub ciphertexts: Vec<I::Ciphertext>, // E_i
 }
 
-pub fn share<R: Rng>(
-    pub_keys: &[&MasterPublicKey],
+pub fn share<I: IbeScheme, R: Rng>(
+    pub_keys: &[&I::MasterPublicKey],
     secret: &[u8; 32], 
     access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
     rng: &mut R
-) -> Sharing {
-    // parse the desired access structure.
-    // n is the number of shares, while
-    // t <= n is the reconstruction threshold.
-    let (t, n) = access;
+) -> Result<Sharing<I>, PVSSError> {
+    share_with(pub_keys, secret, access, session_id, dealer_id, rng, encrypt_shares::<I>)
+}
+
+/// [`share`], with the shares also encrypted in parallel.
+#[cfg(feature = "parallel")]
+pub fn share_parallel<I: ParallelIbe, R: Rng>(
+    pub_keys: &[&I::MasterPublicKey],
+    secret: &[u8; 32],
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R
+) -> Result<Sharing<I>, PVSSError> {
+    share_with(pub_keys, secret, access, session_id, dealer_id, rng, encrypt_shares_parallel::<I>)
+}
+
+fn share_with<I: IbeScheme, R: Rng>(
+    pub_keys: &[&I::MasterPublicKey],
+    secret: &[u8; 32],
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R,
+    encrypt: EncryptShares<I>
+) -> Result<Sharing<I>, PVSSError> {
+    check_access(access)?;
+    check_len(access.1, pub_keys.len())?;
+    let polys = Polynomials::sample(secret, access.0, rng)?;
+    let (csh, cpt, plaintexts) = polys.evaluate_shares(pub_keys.len(), access);
+    let ciphertexts = encrypt(LABEL, session_id, dealer_id, pub_keys, &plaintexts);
+    Ok(polys.respond(pub_keys, access, session_id, dealer_id, (csh, cpt, ciphertexts)))
+}
+
+/// the polynomials of a dealing: the secret s, its pad r, and b and q, which
+/// the proof masks s with
+struct Polynomials {
+    s: DensePolynomial<F>,
+    r: DensePolynomial<F>,
+    b: DensePolynomial<F>,
+    q: DensePolynomial<F>,
+}
+
+impl Polynomials {
+    fn sample<R: Rng>(secret: &[u8; 32], t: u64, rng: &mut R) -> Result<Self, PVSSError> {
+        Ok(Polynomials {
+            s: utils::sample_poly(Some(utils::secret_to_field::<F>(secret)?), t, rng),
+            r: utils::sample_poly(None, t, rng),
+            b: utils::sample_poly(None, t, rng),
+            q: utils::sample_poly(None, t, rng),
+        })
+    }
 
-    assert!(n == pub_keys.len() as u64,
-        "Number of public keys must match the number of shares requested.");
+    /// the share hashes, the pad hashes, and the plaintexts of the ciphertexts
+    fn evaluate_shares(&self, keys: usize, access: (u64, u64)) -> (Vec<Hash>, Vec<Hash>, Vec<[u8; 96]>) {
+        // parse the desired access structure.
+        // n is the number of shares, while
+        // t <= n is the reconstruction threshold.
+        let n = access.1;
 
-    let s_poly = utils::sample_poly(Some(secret), t, rng);
-    let r_poly = utils::sample_poly(None, t, rng);
-    let b_poly = utils::sample_poly(None, t, rng);
-    let q_poly = utils::sample_poly(None, t, rng);
+        assert!(n == keys as u64,
+            "Number of public keys must match the number of shares requested.");
 
-    let mut csh = Vec::new();
-    let mut cpt = Vec::new();
-    let mut ciphertexts = Vec::new();
+        // For each share, we need to evaluate the polynomials at x = i + 1 (1-indexed).
+        let points = EvaluationPoints::indices(n);
+        let [s, r, b, q] = [&self.s, &self.r, &self.b, &self.q].map(|poly| points.evaluate(poly));
+        let (csh, (cpt, plaintexts)) = cfg_into_iter!(0..n as usize)
+            .map(|i| {
+                let s_i = utils::serialize(&s[i]);
+                let r_i = utils::serialize(&r[i]);
+                let b_i = utils::serialize(&b[i]);
+                let q_i = utils::serialize(&q[i]);
 
-    for i in 0..n {
-        // For each share, we need to evaluate the secret polynomial and the random polynomial
-        // at x = i + 1 (1-indexed).
-        let x = F::from((i + 1) as u64);
-        let s_i = utils::serialize(&s_poly.evaluate(&x));
-        let r_i = utils::serialize(&r_poly.evaluate(&x));
-        let b_i = utils::serialize(&b_poly.evaluate(&x));
-        let q_i = utils::serialize(&q_poly.evaluate(&x));
+                let mut msg: [u8; 96] = [0; 96];
+                msg[0..32].copy_from_slice(&s_i);
+                msg[32..64].copy_from_slice(&r_i);
+                msg[64..96].copy_from_slice(&q_i);
+                (utils::digest_sha256(&[&s_i, &r_i]), (utils::digest_sha256(&[&b_i, &q_i]), msg))
+            })
+            .unzip();
+        (csh, cpt, plaintexts)
+    }
+
+    /// completes the sharing with the response to its Fiat-Shamir challenge
+    fn respond<I: IbeScheme>(
+        self,
+        pub_keys: &[&I::MasterPublicKey],
+        access: (u64, u64),
+        session_id: &SessionId,
+        dealer_id: u64,
+        (csh, cpt, ciphertexts): (Vec<Hash>, Vec<Hash>, Vec<I::Ciphertext>)
+    ) -> Sharing<I> {
+        let chal = challenge::<I>(session_id, dealer_id, access, &csh, &cpt, &ciphertexts, pub_keys);
+        let w_poly = self.b - utils::poly_eval_mult_c(&self.s, &chal);
+        let committed_secret = w_poly.coeffs;
+
+        Sharing {
+            session_id: *session_id,
+            dealer_id,
+            access,
+            committed_secret,
+            csh,
+            cpt,
+            ciphertexts
+        }
+    }
+}
+
+/// Fiat-Shamir challenge of a sharing: the hash of the session, the dealer, the access structure, all
+/// share and pad hashes, all ciphertexts and the receivers' public keys.
+fn challenge<I: IbeScheme>(
+    session_id: &SessionId,
+    dealer_id: u64,
+    access: (u64, u64),
+    csh: &[Hash],
+    cpt: &[Hash],
+    ciphertexts: &[I::Ciphertext],
+    pub_keys: &[&I::MasterPublicKey]
+) -> F {
+    let (t, n) = access;
+    let mut hasher = Sha256::new();
+    hasher.update(CHALLENGE_TAG);
+    hasher.update(session_id);
+    hasher.update(dealer_id.to_le_bytes());
+    hasher.update(t.to_le_bytes());
+    hasher.update(n.to_le_bytes());
+    // ciphertext and key encodings are length-prefixed, the rest has fixed size
+    let mut absorb = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    for h in csh.iter().chain(cpt) {
+        absorb(h);
+    }
+    for ct in ciphertexts {
+        absorb(&I::ciphertext_to_bytes(ct));
+    }
+    for pk in pub_keys {
+        absorb(&I::public_key_to_bytes(pk));
+    }
+    let digest: [u8; 32] = hasher.finalize().into();
+    F::from_le_bytes_mod_order(&digest)
+}
+
+impl<I: IbeScheme> Sharing<I> {
+    /// identity receiver `index` needs the key of to decrypt its share
+    pub fn receiver_id(&self, index: usize) -> [u8; 96] {
+        receiver_id(LABEL, &self.session_id, self.dealer_id, index)
+    }
+
+    /// Encodes the sharing in the format of [`crate::serialization`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.serialized_size());
+        self.header().write(TAG_HASH_IBE, &mut out);
+        for c in &self.committed_secret {
+            write_element(c, &mut out);
+        }
+        for h in self.csh.iter().chain(&self.cpt) {
+            out.extend_from_slice(h);
+        }
+        write_ciphertexts::<I>(&self.ciphertexts, &mut out);
+        out
+    }
+
+    /// Decodes a sharing written by [`Sharing::to_bytes`]. The sharing still
+    /// has to be verified.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PVSSError> {
+        let mut reader = bytes;
+        let Header { session_id, dealer_id, access } = Header::read(TAG_HASH_IBE, &mut reader)?;
+        let (t, n) = access;
+        let mut committed_secret = Vec::new();
+        for _ in 0..=t {
+            committed_secret.push(read_element(&mut reader)?);
+        }
+        let mut csh = Vec::new();
+        for _ in 0..n {
+            csh.push(read_array::<32>(&mut reader)?);
+        }
+        let mut cpt = Vec::new();
+        for _ in 0..n {
+            cpt.push(read_array::<32>(&mut reader)?);
+        }
+        let ciphertexts = read_ciphertexts::<I>(n, &mut reader)?;
+        finish(reader)?;
+        Ok(Sharing { session_id, dealer_id, access, committed_secret, csh, cpt, ciphertexts })
+    }
 
-        csh.push(utils::digest_sha256(&[&s_i, &r_i]));
-        cpt.push(utils::digest_sha256(&[&b_i, &q_i]));
+    pub fn serialized_size(&self) -> usize {
+        self.size_report().total
+    }
 
-        let mut msg: [u8; 96] = [0; 96];
-        msg[0..32].copy_from_slice(&s_i);
-        msg[32..64].copy_from_slice(&r_i);
-        msg[64..96].copy_from_slice(&q_i);
-        let id = [i as u8; 96];
-        let ciphertext = ibe_encrypt(&msg, &pub_keys[i as usize], &id);
-        ciphertexts.push(ciphertext);
+    pub fn size_report(&self) -> SizeReport {
+        let polynomial: usize = self.committed_secret.iter().map(CanonicalSerialize::compressed_size).sum();
+        let hashes = 32 * (self.csh.len() + self.cpt.len());
+        SizeReport::new(polynomial + hashes, ciphertexts_size::<I>(&self.ciphertexts))
     }
 
-    let chal = F::from(42);
-    let w_poly = b_poly - utils::poly_eval_mult_c(&s_poly, &chal);
-    let committed_secret = w_poly.coeffs.clone();
+    fn header(&self) -> Header {
+        Header { session_id: self.session_id, dealer_id: self.dealer_id, access: self.access }
+    }
+}
 
-    Sharing {
-        committed_secret,
-        csh,
-        cpt,
-        ciphertexts
+/// The checks anybody can run on a sharing, without a receiver key: the
+/// access structure, the lengths, and the degree of the committed polynomial.
+/// That the shares lie on a polynomial of degree t only follows from the
+/// receivers' checks in [`decrypt_share`].
+pub fn verify_public<I: IbeScheme>(
+    sharing: &Sharing<I>,
+    pub_keys: &[&I::MasterPublicKey],
+    access: (u64, u64)
+) -> Result<(), PVSSError> {
+    let (t, n) = access;
+    if sharing.access != access {
+        return Err(PVSSError::AccessStructureMismatch { expected: access, actual: sharing.access });
+    }
+    check_len(n, pub_keys.len())?;
+    check_len(n, sharing.csh.len())?;
+    check_len(n, sharing.cpt.len())?;
+    check_len(n, sharing.ciphertexts.len())?;
+    if sharing.committed_secret.len() as u64 != t + 1 {
+        return Err(PVSSError::DegreeCheckFailed);
     }
+    Ok(())
 }
 
-pub fn verify(
-    sharing: &Sharing,
+/// A share decrypted by its receiver and checked against the sharing, ready to
+/// be published for reconstruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecryptedShare {
+    pub index: usize,
+    pub s: F,
+    pub r: F,
+}
+
+/// Decrypts the share at `index` and verifies it against the sharing.
+pub fn decrypt_share<I: IbeScheme>(
+    sharing: &Sharing<I>,
+    pub_keys: &[&I::MasterPublicKey],
     access: (u64, u64),
     index: usize,
-    sk: &IbeSecretKeyID
-) {
-    let (t, _n) = access;
-    // degree check on the coefficients
-    assert!(sharing.committed_secret.len() == (t + 1) as usize);
-    let msg = ibe_decrypt(&sharing.ciphertexts[index], sk);
-    let s = utils::deserialize::<F>(&msg[0..32]);
-    let _r = utils::deserialize::<F>(&msg[32..64]);
-    let _q = utils::deserialize::<F>(&msg[64..96]);
-    let chal = F::from(42);
+    sk: &I::SecretKeyID
+) -> Result<DecryptedShare, PVSSError> {
+    verify_public(sharing, pub_keys, access)?;
+    let n = access.1;
+    if index as u64 >= n {
+        return Err(PVSSError::IndexOutOfRange { index, n });
+    }
+    let msg = ibe_decrypt::<I>(&sharing.ciphertexts[index], sk);
+    let s = utils::deserialize::<F>(&msg[0..32]).ok_or(PVSSError::MalformedShare { index })?;
+    let r = utils::deserialize::<F>(&msg[32..64]).ok_or(PVSSError::MalformedShare { index })?;
+    let _q = utils::deserialize::<F>(&msg[64..96]).ok_or(PVSSError::MalformedShare { index })?;
+    let chal = challenge::<I>(&sharing.session_id, sharing.dealer_id, access, &sharing.csh, &sharing.cpt, &sharing.ciphertexts, pub_keys);
     let w_poly = DensePolynomial { coeffs: sharing.committed_secret.clone() };
     let lhs = w_poly.evaluate(&F::from(index as u64 + 1)) + chal * s;
-    assert!(sharing.csh[index] == utils::digest_sha256(&[&msg[0..32], &msg[32..64]]));
-    assert!(sharing.cpt[index] == utils::digest_sha256(&[&utils::serialize(&lhs), &msg[64..96]]));
+    if sharing.csh[index] != utils::digest_sha256(&[&msg[0..32], &msg[32..64]])
+        || sharing.cpt[index] != utils::digest_sha256(&[&utils::serialize(&lhs), &msg[64..96]]) {
+        return Err(PVSSError::HashMismatch { index });
+    }
+    Ok(DecryptedShare { index, s, r })
+}
+
+pub fn verify<I: IbeScheme>(
+    sharing: &Sharing<I>,
+    pub_keys: &[&I::MasterPublicKey],
+    access: (u64, u64),
+    index: usize,
+    sk: &I::SecretKeyID
+) -> Result<(), PVSSError> {
+    decrypt_share(sharing, pub_keys, access, index, sk).map(|_| ())
+}
+
+fn check_share<I: IbeScheme>(sharing: &Sharing<I>, share: &DecryptedShare) -> Result<(), PVSSError> {
+    let index = share.index;
+    if index >= sharing.csh.len() {
+        return Err(PVSSError::IndexOutOfRange { index, n: sharing.csh.len() as u64 });
+    }
+    if sharing.csh[index] != utils::digest_sha256(&[&utils::serialize(&share.s), &utils::serialize(&share.r)]) {
+        return Err(PVSSError::HashMismatch { index });
+    }
+    Ok(())
+}
+
+/// Recovers the 32-byte secret from the published shares. Shares that do not
+/// match their hash `csh`, or repeat an index, are skipped, and the secret is
+/// interpolated from the first t+1 that remain.
+///
+/// # Security
+///
+/// The hashes only bind each share to the dealer's ciphertext; they say
+/// nothing about the shares lying on one polynomial of degree t. That is the
+/// Fiat–Shamir proof, which needs the receivers' public keys: the caller
+/// **must** have run [`verify_public`] on the sharing, and enough receivers
+/// [`verify`] on their shares, before calling this. Otherwise a dealer can make
+/// different sets of t+1 shares open to different secrets.
+pub fn reconstruct<I: IbeScheme>(
+    sharing: &Sharing<I>,
+    shares: &[DecryptedShare]
+) -> Result<[u8; 32], PVSSError> {
+    let (t, n) = sharing.access;
+    check_len(n, sharing.csh.len())?;
+    let mut seen = std::collections::HashSet::new();
+    let points: Vec<(F, F)> = shares.iter()
+        .filter(|share| seen.insert(share.index) && check_share(sharing, share).is_ok())
+        .take((t + 1) as usize)
+        .map(|share| (F::from(share.index as u64 + 1), share.s))
+        .collect();
+    if (points.len() as u64) < t + 1 {
+        return Err(PVSSError::NotEnoughShares { needed: t + 1, got: points.len() });
+    }
+    Ok(utils::field_to_secret_bytes(&utils::lagrange_interpolate_at_zero(&points)))
+}
+
+/// A receiver's proof that the dealer misbehaved: the key of the identity its
+/// share was encrypted to. It opens that one ciphertext for everybody, and
+/// nothing else, as every share has its own identity.
+pub struct Complaint<I: IbeScheme> {
+    pub index: usize,
+    pub sk_id: I::SecretKeyID,
+}
+
+/// Checks a complaint on behalf of a third party; `pub_keys` are the master
+/// public keys of all receivers, as the challenge depends on them. `Ok(())`
+/// means the dealer is at fault: the revealed key belongs to the receiver's
+/// identity under `pub_keys[index]`, and the share it decrypts does not
+/// verify. A complaint against a correct share, or with a wrong key, gives
+/// `InvalidComplaint`.
+pub fn verify_complaint<I: IbeScheme>(
+    sharing: &Sharing<I>,
+    pub_keys: &[&I::MasterPublicKey],
+    complaint: &Complaint<I>
+) -> Result<(), PVSSError> {
+    let index = complaint.index;
+    if index >= pub_keys.len() {
+        return Err(PVSSError::IndexOutOfRange { index, n: pub_keys.len() as u64 });
+    }
+    if !ibe_verify_id_secret_key::<I>(&sharing.receiver_id(index), pub_keys[index], &complaint.sk_id) {
+        return Err(PVSSError::InvalidComplaint { index });
+    }
+    match decrypt_share(sharing, pub_keys, sharing.access, index, &complaint.sk_id) {
+        Err(err) if err.is_dealer_fault() => Ok(()),
+        Err(err) => Err(err),
+        Ok(_) => Err(PVSSError::InvalidComplaint { index }),
+    }
+}
+
+/// The protocol behind the [`Pvss`] trait; it has no parameters.
+pub struct HashPvss<I>(PhantomData<I>);
+
+impl<I: IbeScheme> Pvss for HashPvss<I> {
+    type Ibe = I;
+    type Params = ();
+    type Sharing = Sharing<I>;
+    type DecryptedShare = DecryptedShare;
+    type Error = PVSSError;
+
+    fn setup() {}
+
+    fn deal<R: Rng>(
+        _: &(),
+        pub_keys: &[&I::MasterPublicKey],
+        secret: &[u8; 32],
+        access: (u64, u64),
+        session_id: &SessionId,
+        dealer_id: u64,
+        rng: &mut R,
+    ) -> Result<Sharing<I>, PVSSError> {
+        share(pub_keys, secret, access, session_id, dealer_id, rng)
+    }
+
+    fn receiver_id(sharing: &Sharing<I>, index: usize) -> [u8; 96] {
+        sharing.receiver_id(index)
+    }
+
+    fn verify_public(_: &(), sharing: &Sharing<I>, pub_keys: &[&I::MasterPublicKey], access: (u64, u64)) -> Result<(), PVSSError> {
+        verify_public(sharing, pub_keys, access)
+    }
+
+    fn decrypt_share(
+        _: &(),
+        sharing: &Sharing<I>,
+        pub_keys: &[&I::MasterPublicKey],
+        access: (u64, u64),
+        index: usize,
+        sk: &I::SecretKeyID,
+    ) -> Result<DecryptedShare, PVSSError> {
+        decrypt_share(sharing, pub_keys, access, index, sk)
+    }
+
+    fn reconstruct(_: &(), sharing: &Sharing<I>, shares: &[DecryptedShare]) -> Result<[u8; 32], PVSSError> {
+        reconstruct(sharing, shares)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ibe;
+    use crate::ibe::{self, TestIbe};
     use rand::thread_rng;
 
+    const SESSION: SessionId = [3u8; 32];
+    const DEALER: u64 = 11;
+
+    fn test_id(index: usize) -> [u8; 96] {
+        receiver_id(LABEL, &SESSION, DEALER, index)
+    }
+
     #[test]
     fn test_share_basic() {
         // Setup IBE master keys and public keys
@@ -105,31 +456,348 @@ mod tests {
         let t = 64;
         let mut ibe_keys = Vec::new();
         for _ in 0..n {
-            let key = ibe::ibe_keygen();
+            let key = ibe::ibe_keygen::<TestIbe>();
             ibe_keys.push(key);
         }
-        let pub_keys: Vec<&MasterPublicKey> = ibe_keys.iter().map(|k| k.master_pk()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
 
         // Secret to share
         let secret = [42u8; 32];
 
         // Call share
-        let share_timer = std::time::Instant::now();
-        let sharing = share(
+        let sharing = share::<TestIbe, _>(
             pub_keys.as_slice(),
             &secret,
             (t, n),
+            &SESSION,
+            DEALER,
             &mut thread_rng(),
-        );
-        let share_duration = share_timer.elapsed();
-        println!("Share duration: {:?}", share_duration);
+        ).unwrap();
 
         // Verify the sharing
-        let verify_timer = std::time::Instant::now();
-        let id = [0 as u8; 96];
-        let sk_id_0 = ibe_extract_id_secret_key(&id, &ibe_keys[0]);
-        verify(&sharing, (t, n), 0, &sk_id_0);
-        let verify_duration = verify_timer.elapsed();
-        println!("Verify duration: {:?}", verify_duration);
+        let id = test_id(0);
+        let sk_id_0 = ibe_extract_id_secret_key::<TestIbe>(&id, &ibe_keys[0]);
+        verify(&sharing, &pub_keys, (t, n), 0, &sk_id_0).unwrap();
+    }
+
+    fn setup(n: u64) -> Vec<<TestIbe as IbeScheme>::MasterKeypair> {
+        (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect()
+    }
+
+    fn sk(ibe_keys: &[<TestIbe as IbeScheme>::MasterKeypair], index: usize) -> <TestIbe as IbeScheme>::SecretKeyID {
+        ibe_extract_id_secret_key::<TestIbe>(&test_id(index), &ibe_keys[index])
+    }
+
+    /// A dealer that does not know the challenge in advance: it picks shares
+    /// that lie on no polynomial of degree t, a random W of degree t, and pads
+    /// b_i = W(i) + c s_i for a guessed challenge c. This passes verification
+    /// exactly when c is the challenge the verifier computes.
+    fn forge_with_guessed_challenge(
+        pub_keys: &[&<TestIbe as IbeScheme>::MasterPublicKey],
+        access: (u64, u64),
+        guess: F,
+    ) -> Sharing<TestIbe> {
+        let (t, n) = access;
+        let mut rng = thread_rng();
+        let w_poly = utils::sample_poly::<F, _>(None, t, &mut rng);
+        let (mut csh, mut cpt, mut ciphertexts) = (Vec::new(), Vec::new(), Vec::new());
+        for i in 0..n {
+            let s_i = F::from(1000 + i * i * i);
+            let r_i = utils::serialize(&F::from(7u64));
+            let q_i = utils::serialize(&F::from(9u64));
+            let b_i = w_poly.evaluate(&F::from(i + 1)) + guess * s_i;
+            let s_i = utils::serialize(&s_i);
+            csh.push(utils::digest_sha256(&[&s_i, &r_i]));
+            cpt.push(utils::digest_sha256(&[&utils::serialize(&b_i), &q_i]));
+            let mut msg = [0u8; 96];
+            msg[0..32].copy_from_slice(&s_i);
+            msg[32..64].copy_from_slice(&r_i);
+            msg[64..96].copy_from_slice(&q_i);
+            ciphertexts.push(ibe_encrypt::<TestIbe>(&msg, pub_keys[i as usize], &test_id(i as usize)));
+        }
+        Sharing { session_id: SESSION, dealer_id: DEALER, access, committed_secret: w_poly.coeffs, csh, cpt, ciphertexts }
+    }
+
+    #[test]
+    fn test_verify_all_indices() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let sharing = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        for i in 0..n as usize {
+            verify(&sharing, &pub_keys, (t, n), i, &sk(&ibe_keys, i)).unwrap();
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            let parallel = share_parallel::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+            for i in 0..n as usize {
+                verify(&parallel, &pub_keys, (t, n), i, &sk(&ibe_keys, i)).unwrap();
+            }
+            assert!(matches!(share_parallel::<TestIbe, _>(&pub_keys, &[7u8; 32], (n, n), &SESSION, DEALER, &mut thread_rng()),
+                Err(PVSSError::InvalidThreshold { .. })));
+        }
+    }
+
+    #[test]
+    fn test_forgery_needs_the_challenge() {
+        // sanity check of the forger: with the right challenge, the
+        // inconsistent shares go through, which is why it must be unpredictable
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let mut forged = forge_with_guessed_challenge(&pub_keys, (t, n), F::from(42));
+        let chal = challenge::<TestIbe>(&SESSION, DEALER, (t, n), &forged.csh, &forged.cpt, &forged.ciphertexts, &pub_keys);
+        assert_ne!(chal, F::from(42));
+
+        // re-pad with the now known challenge: this changes cpt, and with it the challenge
+        let w_poly = DensePolynomial { coeffs: forged.committed_secret.clone() };
+        for i in 0..n as usize {
+            let msg = ibe_decrypt::<TestIbe>(&forged.ciphertexts[i], &sk(&ibe_keys, i));
+            let b_i = w_poly.evaluate(&F::from(i as u64 + 1)) + chal * utils::deserialize::<F>(&msg[0..32]).unwrap();
+            forged.cpt[i] = utils::digest_sha256(&[&utils::serialize(&b_i), &msg[64..96]]);
+        }
+        assert_ne!(challenge::<TestIbe>(&SESSION, DEALER, (t, n), &forged.csh, &forged.cpt, &forged.ciphertexts, &pub_keys), chal);
+    }
+
+    #[test]
+    fn test_cheating_dealer_with_constant_challenge() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        // the old, constant challenge
+        let forged = forge_with_guessed_challenge(&pub_keys, (t, n), F::from(42));
+        assert!(matches!(verify(&forged, &pub_keys, (t, n), 0, &sk(&ibe_keys, 0)), Err(PVSSError::HashMismatch { index: 0 })));
+    }
+
+    #[test]
+    fn test_cheating_dealer_with_inconsistent_share() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let mut sharing = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        // replace share 2 by a value off the polynomial, with matching hashes
+        let msg = ibe_decrypt::<TestIbe>(&sharing.ciphertexts[2], &sk(&ibe_keys, 2));
+        let s_2 = utils::serialize(&(utils::deserialize::<F>(&msg[0..32]).unwrap() + F::from(1u64)));
+        let mut forged = msg;
+        forged[0..32].copy_from_slice(&s_2);
+        sharing.csh[2] = utils::digest_sha256(&[&s_2, &msg[32..64]]);
+        sharing.ciphertexts[2] = ibe_encrypt::<TestIbe>(&forged, pub_keys[2], &test_id(2));
+        assert!(matches!(verify(&sharing, &pub_keys, (t, n), 2, &sk(&ibe_keys, 2)), Err(PVSSError::HashMismatch { index: 2 })));
+        // the other receivers are affected too, as the challenge changed
+        assert!(verify(&sharing, &pub_keys, (t, n), 0, &sk(&ibe_keys, 0)).is_err());
+    }
+
+    #[test]
+    fn test_tampered_transcript() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let mut sharing = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        // changing another receiver's pad hash changes the challenge for everyone
+        sharing.cpt[5][0] ^= 1;
+        assert!(matches!(verify(&sharing, &pub_keys, (t, n), 0, &sk(&ibe_keys, 0)), Err(PVSSError::HashMismatch { index: 0 })));
+    }
+
+    #[test]
+    fn test_wrong_receiver_keys() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let sharing = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        let mut swapped = pub_keys.clone();
+        swapped.swap(3, 4);
+        assert!(matches!(verify(&sharing, &swapped, (t, n), 0, &sk(&ibe_keys, 0)), Err(PVSSError::HashMismatch { index: 0 })));
+    }
+
+    #[test]
+    fn test_malformed_sharings_are_rejected() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let sharing = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        let sk_0 = sk(&ibe_keys, 0);
+
+        assert!(matches!(verify(&sharing, &pub_keys, (t + 1, n), 0, &sk_0),
+            Err(PVSSError::AccessStructureMismatch { expected: (4, 8), actual: (3, 8) })));
+        assert!(matches!(verify(&sharing, &pub_keys[1..], (t, n), 0, &sk_0),
+            Err(PVSSError::LengthMismatch { expected: 8, actual: 7 })));
+        assert!(matches!(verify(&sharing, &pub_keys, (t, n), 8, &sk_0),
+            Err(PVSSError::IndexOutOfRange { index: 8, n: 8 })));
+        // a key for another receiver decrypts to garbage
+        assert!(verify(&sharing, &pub_keys, (t, n), 0, &sk(&ibe_keys, 1)).is_err());
+
+        let mut too_long = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        too_long.committed_secret.push(F::from(1u64));
+        assert!(matches!(verify(&too_long, &pub_keys, (t, n), 0, &sk_0), Err(PVSSError::DegreeCheckFailed)));
+
+        // a plaintext that is not a field element
+        let mut malformed = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        malformed.ciphertexts[0] = ibe_encrypt::<TestIbe>(&[0xff; 96], pub_keys[0], &test_id(0));
+        assert!(matches!(verify(&malformed, &pub_keys, (t, n), 0, &sk_0), Err(PVSSError::MalformedShare { index: 0 })));
+
+        // the verifier keeps going after rejecting: the honest sharing still verifies
+        verify(&sharing, &pub_keys, (t, n), 0, &sk_0).unwrap();
+    }
+
+    #[test]
+    fn test_reconstruct_from_any_t_plus_1_shares() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let mut secret = [0u8; 32];
+        thread_rng().fill(&mut secret[1..]);
+        let sharing = share::<TestIbe, _>(&pub_keys, &secret, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        let shares: Vec<DecryptedShare> = (0..n as usize)
+            .map(|i| decrypt_share(&sharing, &pub_keys, (t, n), i, &sk(&ibe_keys, i)).unwrap())
+            .collect();
+
+        for start in 0..=(n - t - 1) as usize {
+            let subset = &shares[start..start + t as usize + 1];
+            assert_eq!(reconstruct(&sharing, subset).unwrap(), secret);
+        }
+        let scattered = [shares[6].clone(), shares[1].clone(), shares[4].clone(), shares[3].clone()];
+        assert_eq!(reconstruct(&sharing, &scattered).unwrap(), secret);
+        assert_eq!(reconstruct(&sharing, &shares).unwrap(), secret);
+
+        assert!(matches!(reconstruct(&sharing, &shares[..3]), Err(PVSSError::NotEnoughShares { needed: 4, got: 3 })));
+        let duplicated = [shares[0].clone(), shares[1].clone(), shares[1].clone(), shares[2].clone()];
+        assert!(matches!(reconstruct(&sharing, &duplicated), Err(PVSSError::NotEnoughShares { needed: 4, got: 3 })));
+        let mut forged = shares[..4].to_vec();
+        forged[2].s += F::from(1u64);
+        assert!(matches!(reconstruct(&sharing, &forged), Err(PVSSError::NotEnoughShares { needed: 4, got: 3 })));
+    }
+
+    #[test]
+    fn test_reconstruct_skips_bad_shares() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let secret = [9u8; 32];
+        let sharing = share::<TestIbe, _>(&pub_keys, &secret, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        let shares: Vec<DecryptedShare> = (0..n as usize)
+            .map(|i| decrypt_share(&sharing, &pub_keys, (t, n), i, &sk(&ibe_keys, i)).unwrap())
+            .collect();
+
+        // forged, repeated and out-of-range shares ahead of t+1 honest ones
+        let mut forged = shares[0].clone();
+        forged.s += F::from(1u64);
+        let mut out_of_range = shares[1].clone();
+        out_of_range.index = n as usize;
+        let mixed = [
+            forged, shares[2].clone(), shares[2].clone(), out_of_range,
+            shares[5].clone(), shares[6].clone(), shares[7].clone(),
+        ];
+        assert_eq!(reconstruct(&sharing, &mixed).unwrap(), secret);
+        assert!(matches!(reconstruct(&sharing, &mixed[..6]), Err(PVSSError::NotEnoughShares { needed: 4, got: 3 })));
+    }
+
+    #[test]
+    fn test_reconstruct_needs_verify_public() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let secret = [9u8; 32];
+
+        // a dealer that shares with degree t+1 but announces threshold t
+        let mut sharing = share::<TestIbe, _>(&pub_keys, &secret, (t + 1, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        let shares: Vec<DecryptedShare> = (0..n as usize)
+            .map(|i| decrypt_share(&sharing, &pub_keys, (t + 1, n), i, &sk(&ibe_keys, i)).unwrap())
+            .collect();
+        sharing.access = (t, n);
+        assert!(matches!(verify_public(&sharing, &pub_keys, (t, n)), Err(PVSSError::DegreeCheckFailed)));
+
+        // reconstruct alone cannot tell: two sets of t+1 shares open differently
+        let first = reconstruct(&sharing, &shares[..4]).unwrap();
+        let second = reconstruct(&sharing, &shares[4..]).unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_complaints() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+
+        // a dealer that guessed the challenge wrong cannot open any share
+        let forged = forge_with_guessed_challenge(&pub_keys, (t, n), F::from(42));
+        let err = decrypt_share(&forged, &pub_keys, (t, n), 4, &sk(&ibe_keys, 4)).unwrap_err();
+        assert!(err.is_dealer_fault());
+        verify_complaint(&forged, &pub_keys, &Complaint { index: 4, sk_id: sk(&ibe_keys, 4) }).unwrap();
+
+        // the key must belong to the receiver's identity and master key
+        let wrong_mpk = Complaint { index: 4, sk_id: ibe_extract_id_secret_key::<TestIbe>(&test_id(4), &ibe_keys[5]) };
+        assert!(matches!(verify_complaint(&forged, &pub_keys, &wrong_mpk), Err(PVSSError::InvalidComplaint { index: 4 })));
+        let wrong_id = Complaint { index: 4, sk_id: ibe_extract_id_secret_key::<TestIbe>(&test_id(5), &ibe_keys[4]) };
+        assert!(matches!(verify_complaint(&forged, &pub_keys, &wrong_id), Err(PVSSError::InvalidComplaint { index: 4 })));
+
+        // complaining about an honest sharing does not incriminate the dealer
+        let honest = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        assert!(matches!(verify_complaint(&honest, &pub_keys, &Complaint { index: 4, sk_id: sk(&ibe_keys, 4) }),
+            Err(PVSSError::InvalidComplaint { index: 4 })));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let (t, n) = (3, 8);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let sharing = share::<TestIbe, _>(&pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        // a replayed dealing verifies and reconstructs like the original
+        let bytes = sharing.to_bytes();
+        let report = sharing.size_report();
+        assert_eq!(bytes.len(), report.total);
+        assert_eq!(report.commitments, 32 * (t as usize + 1) + 64 * n as usize);
+        assert_eq!(report.header + report.commitments + report.ciphertexts, report.total);
+        let decoded = Sharing::<TestIbe>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        let shares: Vec<_> = (0..=t as usize)
+            .map(|i| decrypt_share(&decoded, &pub_keys, (t, n), i, &sk(&ibe_keys, i)).unwrap())
+            .collect();
+        assert_eq!(reconstruct(&decoded, &shares).unwrap(), [7u8; 32]);
+
+        let mut version = bytes.clone();
+        version[0] = 0;
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&version), Err(PVSSError::UnsupportedVersion(0))));
+        let mut tag = bytes.clone();
+        tag[1] = TAG_PEDCOM_IBE;
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&tag), Err(PVSSError::ProtocolMismatch { expected: 2, actual: 1 })));
+        for len in [0, 10, HEADER_BYTES, HEADER_BYTES + 100, bytes.len() - 1] {
+            assert!(matches!(Sharing::<TestIbe>::from_bytes(&bytes[..len]), Err(PVSSError::SerializationError(_))));
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&trailing), Err(PVSSError::SerializationError(_))));
+        // a coefficient that is not reduced modulo the field order
+        let mut coefficient = bytes.clone();
+        coefficient[HEADER_BYTES..HEADER_BYTES + 32].fill(0xff);
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&coefficient), Err(PVSSError::SerializationError(_))));
+    }
+
+    #[test]
+    fn test_more_than_256_receivers() {
+        let (t, n) = (99, 300);
+        let ibe_keys = setup(n);
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let secret = [9u8; 32];
+        let sharing = share::<TestIbe, _>(&pub_keys, &secret, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        // receivers beyond 255 get their own identities, no longer those of i mod 256
+        assert_ne!(sharing.receiver_id(256), sharing.receiver_id(0));
+        let shares: Vec<DecryptedShare> = (0..n as usize)
+            .map(|i| decrypt_share(&sharing, &pub_keys, (t, n), i, &sk(&ibe_keys, i)).unwrap())
+            .collect();
+        assert_eq!(reconstruct(&sharing, &shares[n as usize - t as usize - 1..]).unwrap(), secret);
+
+        // keys of one session do not open the next sharing of the same dealer
+        let next = share::<TestIbe, _>(&pub_keys, &secret, (t, n), &[4u8; 32], DEALER, &mut thread_rng()).unwrap();
+        assert!(verify(&next, &pub_keys, (t, n), 0, &sk(&ibe_keys, 0)).is_err());
+        // and the challenge is bound to the session
+        let mut replayed = share::<TestIbe, _>(&pub_keys, &secret, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        replayed.session_id = [4u8; 32];
+        assert!(matches!(verify(&replayed, &pub_keys, (t, n), 0, &sk(&ibe_keys, 0)), Err(PVSSError::HashMismatch { .. })));
     }
 }
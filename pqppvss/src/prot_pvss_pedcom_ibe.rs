@@ -1,129 +1,966 @@
-use rust_bindings::{lattice_ibe_ffi::MasterPublicKey, IbeCiphertext, IbeSecretKeyID};
+use rust_bindings::IbeScheme;
 use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
-use ark_poly::Polynomial;
+use ark_poly::univariate::DensePolynomial;
+use ark_ff::{UniformRand, Zero};
 use rand::Rng;
+use ark_std::cfg_into_iter;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::marker::PhantomData;
 
 use crate::pedcom::*;
 use crate::ibe::*;
+use crate::pvss::Pvss;
+use crate::serialization::*;
+use crate::multipoint::EvaluationPoints;
 use crate::utils;
 
 pub type F = ark_bls12_381::Fr;
 
-pub struct Sharing {
-    pub commitments: Vec<PedComCommitment>,
-    pub ciphertexts: Vec<IbeCiphertext>,
+/// protocol label of the receiver identities
+pub const LABEL: &[u8] = b"pqppvss/prot_pvss_pedcom_ibe";
+
+pub struct Sharing<I: IbeScheme, G: PedComGroup = Bls12G1> {
+    pub session_id: SessionId,
+    pub dealer_id: u64,
+    pub access: (u64, u64), // (t, n)
+    pub commitments: Vec<PedComCommitment<G>>,
+    pub ciphertexts: Vec<I::Ciphertext>,
 }
 
-pub fn share<R: Rng>(
-    pedcom_params: &PedComParams,
-    pub_keys: &[&MasterPublicKey],
+pub fn share<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    pub_keys: &[&I::MasterPublicKey],
     secret: &[u8; 32], 
     access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R
+) -> Result<Sharing<I, G>, PVSSError> {
+    share_with(pedcom_params, pub_keys, secret, access, session_id, dealer_id, rng, encrypt_shares::<I>)
+}
+
+/// [`share`], with the shares also encrypted in parallel.
+#[cfg(feature = "parallel")]
+pub fn share_parallel<I: ParallelIbe, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    pub_keys: &[&I::MasterPublicKey],
+    secret: &[u8; 32],
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
     rng: &mut R
-) -> Sharing {
+) -> Result<Sharing<I, G>, PVSSError> {
+    share_with(pedcom_params, pub_keys, secret, access, session_id, dealer_id, rng, encrypt_shares_parallel::<I>)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn share_with<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    pub_keys: &[&I::MasterPublicKey],
+    secret: &[u8; 32],
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R,
+    encrypt: EncryptShares<I>
+) -> Result<Sharing<I, G>, PVSSError> {
     // parse the desired access structure.
     // n is the number of shares, while
-    // t <= n is the reconstruction threshold.
+    // t < n is the reconstruction threshold.
+    check_access(access)?;
+    check_len(access.1, pub_keys.len())?;
+    let t = access.0;
+    let secret_poly = utils::sample_poly(Some(utils::secret_to_field(secret)?), t, rng);
+    let random_poly = utils::sample_poly(None, t, rng);
+    let (commitments, plaintexts) = evaluate_shares(pedcom_params, pub_keys.len(), &secret_poly, &random_poly, access);
+    let ciphertexts = encrypt(LABEL, session_id, dealer_id, pub_keys, &plaintexts);
+    Ok(Sharing { session_id: *session_id, dealer_id, access, commitments, ciphertexts })
+}
+
+/// Shares the evaluations of `secret_poly` and `random_poly`, both of degree t.
+pub(crate) fn share_polynomials<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    pub_keys: &[&I::MasterPublicKey],
+    secret_poly: &DensePolynomial<G::Scalar>,
+    random_poly: &DensePolynomial<G::Scalar>,
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64
+) -> Sharing<I, G> {
+    let (commitments, plaintexts) = evaluate_shares(pedcom_params, pub_keys.len(), secret_poly, random_poly, access);
+    let ciphertexts = encrypt_shares::<I>(LABEL, session_id, dealer_id, pub_keys, &plaintexts);
+
+    Sharing {
+        session_id: *session_id,
+        dealer_id,
+        access,
+        commitments,
+        ciphertexts
+    }
+}
+
+/// The commitments to the shares, and the plaintexts of their ciphertexts.
+fn evaluate_shares<G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    keys: usize,
+    secret_poly: &DensePolynomial<G::Scalar>,
+    random_poly: &DensePolynomial<G::Scalar>,
+    access: (u64, u64)
+) -> (Vec<PedComCommitment<G>>, Vec<[u8; 96]>) {
     let (t, n) = access;
 
-    assert!(n == pub_keys.len() as u64,
+    assert!(n == keys as u64,
         "Number of public keys must match the number of shares requested.");
+    assert!(secret_poly.coeffs.len() as u64 <= t + 1 && random_poly.coeffs.len() as u64 <= t + 1);
 
-    let secret_poly = utils::sample_poly(Some(secret), t, rng);
-    let random_poly = utils::sample_poly(None, t, rng);
+    // For each share, we need to evaluate the secret polynomial and the random polynomial
+    // at x = i + 1 (1-indexed).
+    let points = EvaluationPoints::indices(n);
+    let (secret_ys, random_ys) = (points.evaluate(secret_poly), points.evaluate(random_poly));
+    cfg_into_iter!(secret_ys).zip(random_ys)
+        .map(|(secret_y, random_y)| {
+            let mut msg: [u8; 96] = [0; 96];
+            secret_y.serialize_compressed(&mut msg[0..32]).unwrap();
+            random_y.serialize_compressed(&mut msg[32..64]).unwrap();
+            (commit(pedcom_params, secret_y, random_y), msg)
+        })
+        .unzip()
+}
 
-    let mut secret_shares = Vec::new();
-    let mut random_shares = Vec::new();
-    let mut commitments = Vec::new();
-    let mut ciphertexts = Vec::new();
+impl<I: IbeScheme, G: PedComGroup> Sharing<I, G> {
+    /// identity receiver `index` needs the key of to decrypt its share
+    pub fn receiver_id(&self, index: usize) -> [u8; 96] {
+        receiver_id(LABEL, &self.session_id, self.dealer_id, index)
+    }
 
-    for i in 0..n {
-        // For each share, we need to evaluate the secret polynomial and the random polynomial
-        // at x = i + 1 (1-indexed).
-        let x = F::from((i + 1) as u64);
-        let secret_y = secret_poly.evaluate(&x);
-        let random_y = random_poly.evaluate(&x);
+    /// Encodes the sharing in the format of [`crate::serialization`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.serialized_size());
+        self.header().write(TAG_PEDCOM_IBE, &mut out);
+        for c in &self.commitments {
+            write_element(c, &mut out);
+        }
+        write_ciphertexts::<I>(&self.ciphertexts, &mut out);
+        out
+    }
 
-        secret_shares.push(secret_y);
-        random_shares.push(random_y);
-        commitments.push(commit(pedcom_params, secret_y, random_y));
+    /// Decodes a sharing written by [`Sharing::to_bytes`]. The sharing still
+    /// has to be verified.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PVSSError> {
+        let mut reader = bytes;
+        let Header { session_id, dealer_id, access } = Header::read(TAG_PEDCOM_IBE, &mut reader)?;
+        let mut commitments = Vec::new();
+        for _ in 0..access.1 {
+            commitments.push(read_element(&mut reader)?);
+        }
+        let ciphertexts = read_ciphertexts::<I>(access.1, &mut reader)?;
+        finish(reader)?;
+        Ok(Sharing { session_id, dealer_id, access, commitments, ciphertexts })
+    }
 
-        let mut msg: [u8; 96] = [0; 96];
-        secret_y.serialize_compressed(&mut msg[0..32]).unwrap();
-        random_y.serialize_compressed(&mut msg[32..64]).unwrap();
-        let id = [i as u8; 96];
-        let ciphertext = ibe_encrypt(&msg, &pub_keys[i as usize], &id);
-        ciphertexts.push(ciphertext);
+    pub fn serialized_size(&self) -> usize {
+        self.size_report().total
     }
 
-    Sharing {
-        commitments,
-        ciphertexts
+    pub fn size_report(&self) -> SizeReport {
+        let commitments = self.commitments.iter().map(CanonicalSerialize::compressed_size).sum();
+        SizeReport::new(commitments, ciphertexts_size::<I>(&self.ciphertexts))
+    }
+
+    fn header(&self) -> Header {
+        Header { session_id: self.session_id, dealer_id: self.dealer_id, access: self.access }
+    }
+}
+
+/// The checks anybody can run on a sharing, without a receiver key: the
+/// access structure, the lengths, and that the commitments lie on a
+/// polynomial of degree t. The randomness of the degree check is derived
+/// from the session and the commitments, so every verifier of a sharing runs
+/// the same check, and an auditor can replay it.
+pub fn verify_public<I: IbeScheme, G: PedComGroup>(
+    sharing: &Sharing<I, G>,
+    access: (u64, u64)
+) -> Result<(), PVSSError> {
+    check_lengths(sharing, access)?;
+    if !degree_check_transcript::<G>(&sharing.commitments, access.0, &sharing.session_id) {
+        return Err(PVSSError::DegreeCheckFailed);
     }
+    Ok(())
 }
 
-pub fn verify(
-    pedcom_params: &PedComParams,
-    sharing: &Sharing,
+/// [`verify_public`] with the randomness of the degree check drawn from
+/// `rng`, for a verifier that challenges the dealer interactively; seeding
+/// `rng` makes the check reproducible too.
+pub fn verify_public_with_rng<I: IbeScheme, G: PedComGroup, R: Rng>(
+    sharing: &Sharing<I, G>,
     access: (u64, u64),
+    rng: &mut R
+) -> Result<(), PVSSError> {
+    check_lengths(sharing, access)?;
+    if !degree_check_with_rng::<G, _>(&sharing.commitments, access.0, rng) {
+        return Err(PVSSError::DegreeCheckFailed);
+    }
+    Ok(())
+}
+
+fn check_lengths<I: IbeScheme, G: PedComGroup>(sharing: &Sharing<I, G>, access: (u64, u64)) -> Result<(), PVSSError> {
+    if sharing.access != access {
+        return Err(PVSSError::AccessStructureMismatch { expected: access, actual: sharing.access });
+    }
+    check_len(access.1, sharing.commitments.len())?;
+    check_len(access.1, sharing.ciphertexts.len())
+}
+
+/// A share decrypted by its receiver and checked against the commitments,
+/// ready to be published for reconstruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecryptedShare<S = F> {
+    pub index: usize,
+    pub s: S,
+    pub r: S,
+}
+
+/// Decrypts the share at `index` and verifies it against the sharing.
+pub fn decrypt_share<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    access: (u64, u64),
+    index: usize,
+    sk: &I::SecretKeyID
+) -> Result<DecryptedShare<G::Scalar>, PVSSError> {
+    verify_public(sharing, access)?;
+    let share = open_share(sharing, index, sk)?;
+    check_share(pedcom_params, sharing, &share)?;
+    Ok(share)
+}
+
+/// decrypts the share at `index`, without checking it
+fn open_share<I: IbeScheme, G: PedComGroup>(
+    sharing: &Sharing<I, G>,
     index: usize,
-    sk: &IbeSecretKeyID
+    sk: &I::SecretKeyID
+) -> Result<DecryptedShare<G::Scalar>, PVSSError> {
+    let n = sharing.ciphertexts.len() as u64;
+    if index as u64 >= n {
+        return Err(PVSSError::IndexOutOfRange { index, n });
+    }
+    let msg = ibe_decrypt::<I>(&sharing.ciphertexts[index], sk);
+    let s = G::Scalar::deserialize_compressed(&msg[0..32]).map_err(|_| PVSSError::MalformedShare { index })?;
+    let r = G::Scalar::deserialize_compressed(&msg[32..64]).map_err(|_| PVSSError::MalformedShare { index })?;
+    Ok(DecryptedShare { index, s, r })
+}
+
+/// Verifies the share at `index` of many dealings at once, as a node of a DKG
+/// or beacon round does with the dealings of all others; `sks[k]` is the key
+/// of the receiver identity of `sharings[k]`. The degree checks and openings
+/// of all dealings are combined with random weights into a single
+/// multi-scalar multiplication. Only if that fails is every dealing checked on
+/// its own, to tell the bad ones apart. Returns, per dealing, what
+/// [`decrypt_share`] would.
+pub fn batch_verify<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharings: &[Sharing<I, G>],
+    access: (u64, u64),
+    index: usize,
+    sks: &[I::SecretKeyID]
+) -> Vec<Result<DecryptedShare<G::Scalar>, PVSSError>> {
+    batch_verify_with_rng(pedcom_params, sharings, access, index, sks, &mut rand::thread_rng())
+}
+
+/// [`batch_verify`] with the weights of the combined check drawn from `rng`;
+/// seeding `rng` makes the check reproducible.
+pub fn batch_verify_with_rng<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    sharings: &[Sharing<I, G>],
+    access: (u64, u64),
+    index: usize,
+    sks: &[I::SecretKeyID],
+    rng: &mut R
+) -> Vec<Result<DecryptedShare<G::Scalar>, PVSSError>> {
+    assert_eq!(sharings.len(), sks.len(), "Each sharing needs the key of its receiver identity.");
+    let (t, n) = access;
+    let mut results: Vec<_> = sharings.iter().zip(sks)
+        .map(|(sharing, sk)| {
+            check_lengths(sharing, access)?;
+            open_share(sharing, index, sk)
+        })
+        .collect();
+
+    // sum_k sum_i w_ki v_ki + rho_k (v_k,index - g^s_k h^r_k) = 0
+    let coefficients = scrape_coefficients::<G::Scalar>(n);
+    let mut bases = vec![pedcom_params.g, pedcom_params.h];
+    let mut scalars = vec![G::Scalar::zero(); 2];
+    for (sharing, result) in sharings.iter().zip(&results) {
+        let Ok(share) = result else { continue };
+        let mut weights = degree_check_weights(&coefficients, t, rng);
+        let rho = G::Scalar::rand(rng);
+        weights[index] += rho;
+        scalars[0] -= rho * share.s;
+        scalars[1] -= rho * share.r;
+        bases.extend_from_slice(&sharing.commitments);
+        scalars.extend(weights);
+    }
+    if G::msm(&bases, &scalars) == G::zero() {
+        return results;
+    }
+
+    for (sharing, result) in sharings.iter().zip(results.iter_mut()) {
+        if let Ok(share) = result {
+            if !degree_check_transcript::<G>(&sharing.commitments, t, &sharing.session_id) {
+                *result = Err(PVSSError::DegreeCheckFailed);
+            } else if let Err(err) = check_share(pedcom_params, sharing, share) {
+                *result = Err(err);
+            }
+        }
+    }
+    results
+}
+
+/// what [`decrypt_share`] returns for one index
+pub type ShareResult<S = F> = Result<DecryptedShare<S>, PVSSError>;
+
+/// Decrypts and verifies the shares at every index, as a party holding the
+/// keys of all receiver identities does; `sks[i]` is the key of the identity
+/// of index i. The sharing is checked once, and the openings of all shares
+/// together with random weights in one multi-scalar multiplication. Only if
+/// that fails is every share checked on its own. Returns, per index, what
+/// [`decrypt_share`] would.
+pub fn verify_all_indices<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    access: (u64, u64),
+    sks: &[I::SecretKeyID]
+) -> Result<Vec<ShareResult<G::Scalar>>, PVSSError> {
+    verify_all_indices_with_rng(pedcom_params, sharing, access, sks, &mut rand::thread_rng())
+}
+
+/// [`verify_all_indices`] with the weights of the combined check drawn from
+/// `rng`; seeding `rng` makes the check reproducible.
+pub fn verify_all_indices_with_rng<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    access: (u64, u64),
+    sks: &[I::SecretKeyID],
+    rng: &mut R
+) -> Result<Vec<ShareResult<G::Scalar>>, PVSSError> {
+    verify_public(sharing, access)?;
+    check_len(access.1, sks.len())?;
+    let mut results: Vec<_> = sks.iter().enumerate().map(|(i, sk)| open_share(sharing, i, sk)).collect();
+    check_openings(pedcom_params, sharing, &mut results, rng);
+    Ok(results)
+}
+
+/// [`verify_all_indices`], with the shares also decrypted in parallel.
+#[cfg(feature = "parallel")]
+pub fn verify_all_indices_parallel<I: ParallelIbe, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    access: (u64, u64),
+    sks: &[I::SecretKeyID]
+) -> Result<Vec<ShareResult<G::Scalar>>, PVSSError> {
+    verify_public(sharing, access)?;
+    check_len(access.1, sks.len())?;
+    let mut results: Vec<_> = sks.par_iter().enumerate().map(|(i, sk)| open_share(sharing, i, sk)).collect();
+    check_openings(pedcom_params, sharing, &mut results, &mut rand::thread_rng());
+    Ok(results)
+}
+
+/// checks the opened shares against their commitments, in one multi-scalar
+/// multiplication unless some fail
+fn check_openings<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    results: &mut [ShareResult<G::Scalar>],
+    rng: &mut R
 ) {
-    let (t, _n) = access;
-    assert!(degree_check(&sharing.commitments, t));
-    let msg = ibe_decrypt(&sharing.ciphertexts[index], sk);
-    let s = F::deserialize_compressed(&msg[0..32]).unwrap();
-    let r = F::deserialize_compressed(&msg[32..64]).unwrap();
-    assert!(sharing.commitments[index] == commit(pedcom_params, s, r));
+    // sum_i rho_i (v_i - g^s_i h^r_i) = 0
+    let mut bases = vec![pedcom_params.g, pedcom_params.h];
+    let mut scalars = vec![G::Scalar::zero(); 2];
+    for share in results.iter().flatten() {
+        let rho = G::Scalar::rand(rng);
+        scalars[0] -= rho * share.s;
+        scalars[1] -= rho * share.r;
+        bases.push(sharing.commitments[share.index]);
+        scalars.push(rho);
+    }
+    if G::msm(&bases, &scalars) == G::zero() {
+        return;
+    }
+    for result in results.iter_mut() {
+        if let Ok(share) = result
+            && let Err(err) = check_share(pedcom_params, sharing, share) {
+            *result = Err(err);
+        }
+    }
+}
+
+pub fn verify<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    access: (u64, u64),
+    index: usize,
+    sk: &I::SecretKeyID
+) -> Result<(), PVSSError> {
+    decrypt_share(pedcom_params, sharing, access, index, sk).map(|_| ())
+}
+
+fn check_share<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    share: &DecryptedShare<G::Scalar>
+) -> Result<(), PVSSError> {
+    let index = share.index;
+    if index >= sharing.commitments.len() {
+        return Err(PVSSError::IndexOutOfRange { index, n: sharing.commitments.len() as u64 });
+    }
+    if sharing.commitments[index] != commit(pedcom_params, share.s, share.r) {
+        return Err(PVSSError::CommitmentMismatch { index });
+    }
+    Ok(())
+}
+
+/// Recovers the 32-byte secret from the published shares. Shares that do not
+/// open their commitment, repeat an index or are out of range are skipped;
+/// the commitments are checked against the degree t, so any t+1 of the
+/// remaining shares give the same secret.
+pub fn reconstruct<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    shares: &[DecryptedShare<G::Scalar>]
+) -> Result<[u8; 32], PVSSError> {
+    let (t, n) = sharing.access;
+    check_len(n, sharing.commitments.len())?;
+    if !degree_check_transcript::<G>(&sharing.commitments, t, &sharing.session_id) {
+        return Err(PVSSError::DegreeCheckFailed);
+    }
+    let mut seen = std::collections::HashSet::new();
+    let points: Vec<(G::Scalar, G::Scalar)> = shares.iter()
+        .filter(|share| seen.insert(share.index) && check_share(pedcom_params, sharing, share).is_ok())
+        .take((t + 1) as usize)
+        .map(|share| (G::Scalar::from(share.index as u64 + 1), share.s))
+        .collect();
+    if (points.len() as u64) < t + 1 {
+        return Err(PVSSError::NotEnoughShares { needed: t + 1, got: points.len() });
+    }
+    Ok(utils::field_to_secret_bytes(&utils::lagrange_interpolate_at_zero(&points)))
+}
+
+/// A receiver's proof that the dealer misbehaved: the key of the identity its
+/// share was encrypted to. It opens that one ciphertext for everybody, and
+/// nothing else, as every share has its own identity.
+pub struct Complaint<I: IbeScheme> {
+    pub index: usize,
+    pub sk_id: I::SecretKeyID,
+}
+
+/// Checks a complaint on behalf of a third party. `Ok(())` means the dealer is
+/// at fault: the revealed key belongs to the receiver's identity under
+/// `receiver_mpk`, and the share it decrypts does not verify. A complaint
+/// against a correct share, or with a wrong key, gives `InvalidComplaint`.
+pub fn verify_complaint<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &Sharing<I, G>,
+    complaint: &Complaint<I>,
+    receiver_mpk: &I::MasterPublicKey
+) -> Result<(), PVSSError> {
+    let index = complaint.index;
+    if index >= sharing.ciphertexts.len() {
+        return Err(PVSSError::IndexOutOfRange { index, n: sharing.ciphertexts.len() as u64 });
+    }
+    if !ibe_verify_id_secret_key::<I>(&sharing.receiver_id(index), receiver_mpk, &complaint.sk_id) {
+        return Err(PVSSError::InvalidComplaint { index });
+    }
+    match decrypt_share(pedcom_params, sharing, sharing.access, index, &complaint.sk_id) {
+        Err(err) if err.is_dealer_fault() => Ok(()),
+        Err(err) => Err(err),
+        Ok(_) => Err(PVSSError::InvalidComplaint { index }),
+    }
+}
+
+/// The protocol behind the [`Pvss`] trait, committing in `G`.
+pub struct PedersenPvss<I, G = Bls12G1>(PhantomData<(I, G)>);
+
+impl<I: IbeScheme, G: PedComGroup> Pvss for PedersenPvss<I, G> {
+    type Ibe = I;
+    type Params = PedComParams<G>;
+    type Sharing = Sharing<I, G>;
+    type DecryptedShare = DecryptedShare<G::Scalar>;
+    type Error = PVSSError;
+
+    fn setup() -> PedComParams<G> {
+        setup()
+    }
+
+    fn deal<R: Rng>(
+        params: &PedComParams<G>,
+        pub_keys: &[&I::MasterPublicKey],
+        secret: &[u8; 32],
+        access: (u64, u64),
+        session_id: &SessionId,
+        dealer_id: u64,
+        rng: &mut R,
+    ) -> Result<Sharing<I, G>, PVSSError> {
+        share(params, pub_keys, secret, access, session_id, dealer_id, rng)
+    }
+
+    fn receiver_id(sharing: &Sharing<I, G>, index: usize) -> [u8; 96] {
+        sharing.receiver_id(index)
+    }
+
+    fn verify_public(_: &PedComParams<G>, sharing: &Sharing<I, G>, pub_keys: &[&I::MasterPublicKey], access: (u64, u64)) -> Result<(), PVSSError> {
+        check_len(access.1, pub_keys.len())?;
+        verify_public(sharing, access)
+    }
+
+    fn decrypt_share(
+        params: &PedComParams<G>,
+        sharing: &Sharing<I, G>,
+        _: &[&I::MasterPublicKey],
+        access: (u64, u64),
+        index: usize,
+        sk: &I::SecretKeyID,
+    ) -> Result<DecryptedShare<G::Scalar>, PVSSError> {
+        decrypt_share(params, sharing, access, index, sk)
+    }
+
+    fn reconstruct(params: &PedComParams<G>, sharing: &Sharing<I, G>, shares: &[DecryptedShare<G::Scalar>]) -> Result<[u8; 32], PVSSError> {
+        reconstruct(params, sharing, shares)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::pedcom;
-    use crate::ibe;
+    use crate::ibe::{self, TestIbe};
     use rand::thread_rng;
 
+    const SESSION: SessionId = [3u8; 32];
+    const DEALER: u64 = 11;
+
+    fn test_id(index: usize) -> [u8; 96] {
+        receiver_id(LABEL, &SESSION, DEALER, index)
+    }
+
     #[test]
     fn test_share_basic() {
         // Setup PedComParams (dummy for test)
-        let pedcom_params = pedcom::setup();
+        let pedcom_params = pedcom::setup::<Bls12G1>();
 
         // Setup IBE master keys and public keys
         let n = 128;
         let t = 64;
         let mut ibe_keys = Vec::new();
         for _ in 0..n {
-            let key = ibe::ibe_keygen();
+            let key = ibe::ibe_keygen::<TestIbe>();
             ibe_keys.push(key);
         }
-        let pub_keys: Vec<&MasterPublicKey> = ibe_keys.iter().map(|k| k.master_pk()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
 
         // Secret to share
         let secret = [42u8; 32];
 
         // Call share
-        let share_timer = std::time::Instant::now();
-        let sharing = share(
+        let sharing = share::<TestIbe, _, _>(
             &pedcom_params,
             pub_keys.as_slice(),
             &secret,
             (t, n),
+            &SESSION,
+            DEALER,
             &mut thread_rng(),
-        );
-        let share_duration = share_timer.elapsed();
-        println!("Share duration: {:?}", share_duration);
+        ).unwrap();
 
         // Verify the sharing
-        let verify_timer = std::time::Instant::now();
-        let id = [0 as u8; 96];
-        let sk_id_0 = ibe_extract_id_secret_key(&id, &ibe_keys[0]);
-        verify(&pedcom_params, &sharing, (t, n), 0, &sk_id_0);
-        let verify_duration = verify_timer.elapsed();
-        println!("Verify duration: {:?}", verify_duration);
+        let id = test_id(0);
+        let sk_id_0 = ibe_extract_id_secret_key::<TestIbe>(&id, &ibe_keys[0]);
+        verify(&pedcom_params, &sharing, (t, n), 0, &sk_id_0).unwrap();
 
         // Check lengths
         assert_eq!(sharing.commitments.len(), n as usize);
         assert_eq!(sharing.ciphertexts.len(), n as usize);
     }
+
+    #[test]
+    fn test_share_rejects_bad_access() {
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let ibe_keys: Vec<_> = (0..4).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let deal = |access: (u64, u64), keys: &[_]| share::<TestIbe, Bls12G1, _>(&pedcom_params, keys, &[7u8; 32], access, &SESSION, DEALER, &mut thread_rng());
+        assert!(matches!(deal((1, 5), &pub_keys), Err(PVSSError::LengthMismatch { expected: 5, actual: 4 })));
+        assert!(matches!(deal((4, 4), &pub_keys), Err(PVSSError::InvalidThreshold { t: 4, n: 4 })));
+        assert!(matches!(deal((0, 0), &[]), Err(PVSSError::InvalidThreshold { t: 0, n: 0 })));
+        deal((3, 4), &pub_keys).unwrap();
+    }
+
+    #[test]
+    fn test_cheating_dealer_is_rejected() {
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (3, 8);
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let sk = |i: usize| ibe_extract_id_secret_key::<TestIbe>(&test_id(i), &ibe_keys[i]);
+        let honest = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        for i in 0..n as usize {
+            verify(&pedcom_params, &honest, (t, n), i, &sk(i)).unwrap();
+        }
+
+        // shares dealt with a polynomial of too high degree
+        let mut too_high = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t + 1, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        assert!(matches!(verify(&pedcom_params, &too_high, (t, n), 0, &sk(0)), Err(PVSSError::AccessStructureMismatch { .. })));
+        too_high.access = (t, n);
+        assert!(matches!(verify(&pedcom_params, &too_high, (t, n), 0, &sk(0)), Err(PVSSError::DegreeCheckFailed)));
+
+        // a ciphertext that does not open its commitment
+        let mut swapped = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        let mut msg = [0u8; 96];
+        F::from(5u64).serialize_compressed(&mut msg[0..32]).unwrap();
+        swapped.ciphertexts[2] = ibe_encrypt::<TestIbe>(&msg, pub_keys[2], &test_id(2));
+        assert!(matches!(verify(&pedcom_params, &swapped, (t, n), 2, &sk(2)), Err(PVSSError::CommitmentMismatch { index: 2 })));
+
+        // a plaintext that is not a field element
+        swapped.ciphertexts[3] = ibe_encrypt::<TestIbe>(&[0xff; 96], pub_keys[3], &test_id(3));
+        assert!(matches!(verify(&pedcom_params, &swapped, (t, n), 3, &sk(3)), Err(PVSSError::MalformedShare { index: 3 })));
+
+        assert!(matches!(verify(&pedcom_params, &honest, (t, n), 9, &sk(0)), Err(PVSSError::IndexOutOfRange { index: 9, n: 8 })));
+        let mut short = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        short.ciphertexts.pop();
+        assert!(matches!(verify(&pedcom_params, &short, (t, n), 0, &sk(0)), Err(PVSSError::LengthMismatch { expected: 8, actual: 7 })));
+    }
+
+    #[test]
+    fn test_deterministic_degree_check() {
+        use rand_chacha::{ChaCha20Rng, rand_core::SeedableRng};
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (3, 8);
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let mut dealer_rng = ChaCha20Rng::from_seed([1u8; 32]);
+        let honest = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut dealer_rng).unwrap();
+        let mut too_high = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t + 1, n), &SESSION, DEALER, &mut dealer_rng).unwrap();
+        too_high.access = (t, n);
+
+        // the transcript mode: every run derives the same z from the sharing
+        let coefficients = scrape_coefficients(n);
+        let weights = |sharing: &Sharing<TestIbe>| {
+            degree_check_weights(&coefficients, t, &mut transcript_rng::<Bls12G1>(&sharing.commitments, t, &sharing.session_id))
+        };
+        assert_eq!(weights(&too_high), weights(&too_high));
+        assert_ne!(weights(&too_high), weights(&honest));
+        assert_ne!(<Bls12G1 as PedComGroup>::msm(&too_high.commitments, &weights(&too_high)), <Bls12G1 as PedComGroup>::zero());
+        for _ in 0..3 {
+            verify_public(&honest, (t, n)).unwrap();
+            assert!(matches!(verify_public(&too_high, (t, n)), Err(PVSSError::DegreeCheckFailed)));
+        }
+
+        // the interactive mode, replayed with the same seed
+        for seed in [[2u8; 32], [3u8; 32]] {
+            verify_public_with_rng(&honest, (t, n), &mut ChaCha20Rng::from_seed(seed)).unwrap();
+            assert!(matches!(verify_public_with_rng(&too_high, (t, n), &mut ChaCha20Rng::from_seed(seed)), Err(PVSSError::DegreeCheckFailed)));
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_from_any_t_plus_1_shares() {
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (3, 8);
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let mut secret = [0u8; 32];
+        thread_rng().fill(&mut secret[1..]);
+        let sharing = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &secret, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        let shares: Vec<DecryptedShare> = (0..n as usize)
+            .map(|i| {
+                let sk = ibe_extract_id_secret_key::<TestIbe>(&test_id(i), &ibe_keys[i]);
+                decrypt_share(&pedcom_params, &sharing, (t, n), i, &sk).unwrap()
+            })
+            .collect();
+
+        // every window of t+1 consecutive receivers, and a scattered subset
+        for start in 0..=(n - t - 1) as usize {
+            let subset = &shares[start..start + t as usize + 1];
+            assert_eq!(reconstruct(&pedcom_params, &sharing, subset).unwrap(), secret);
+        }
+        let scattered = [shares[7].clone(), shares[0].clone(), shares[5].clone(), shares[2].clone()];
+        assert_eq!(reconstruct(&pedcom_params, &sharing, &scattered).unwrap(), secret);
+        assert_eq!(reconstruct(&pedcom_params, &sharing, &shares).unwrap(), secret);
+
+        // duplicated, forged and out-of-range shares do not count towards t+1
+        assert!(matches!(reconstruct(&pedcom_params, &sharing, &shares[..3]),
+            Err(PVSSError::NotEnoughShares { needed: 4, got: 3 })));
+        let duplicated = [shares[0].clone(), shares[1].clone(), shares[0].clone(), shares[2].clone()];
+        assert!(matches!(reconstruct(&pedcom_params, &sharing, &duplicated),
+            Err(PVSSError::NotEnoughShares { needed: 4, got: 3 })));
+        let mut forged = shares[..4].to_vec();
+        forged[1].s += F::from(1u64);
+        assert!(matches!(reconstruct(&pedcom_params, &sharing, &forged),
+            Err(PVSSError::NotEnoughShares { needed: 4, got: 3 })));
+        let mut out_of_range = shares[..4].to_vec();
+        out_of_range[3].index = 8;
+        assert!(matches!(reconstruct(&pedcom_params, &sharing, &out_of_range),
+            Err(PVSSError::NotEnoughShares { needed: 4, got: 3 })));
+
+        // ... and are skipped when t+1 honest shares are there as well
+        let mut mixed = vec![forged[1].clone(), shares[0].clone(), shares[0].clone(), out_of_range[3].clone()];
+        mixed.extend_from_slice(&shares[4..7]);
+        assert_eq!(reconstruct(&pedcom_params, &sharing, &mixed).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_complaints() {
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (3, 8);
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let sk = |i: usize| ibe_extract_id_secret_key::<TestIbe>(&test_id(i), &ibe_keys[i]);
+
+        // the dealer encrypts garbage to receiver 2
+        let mut sharing = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        let mut msg = [0u8; 96];
+        F::from(5u64).serialize_compressed(&mut msg[0..32]).unwrap();
+        sharing.ciphertexts[2] = ibe_encrypt::<TestIbe>(&msg, pub_keys[2], &test_id(2));
+
+        // receiver 2 complains, and anybody can check it
+        let err = decrypt_share(&pedcom_params, &sharing, (t, n), 2, &sk(2)).unwrap_err();
+        assert!(err.is_dealer_fault());
+        let complaint = Complaint { index: 2, sk_id: sk(2) };
+        verify_complaint(&pedcom_params, &sharing, &complaint, pub_keys[2]).unwrap();
+
+        // the key must belong to the receiver's identity and master key
+        let wrong_mpk = Complaint { index: 2, sk_id: sk(2) };
+        assert!(matches!(verify_complaint(&pedcom_params, &sharing, &wrong_mpk, pub_keys[3]),
+            Err(PVSSError::InvalidComplaint { index: 2 })));
+        let wrong_id = Complaint { index: 2, sk_id: ibe_extract_id_secret_key::<TestIbe>(&test_id(3), &ibe_keys[2]) };
+        assert!(matches!(verify_complaint(&pedcom_params, &sharing, &wrong_id, pub_keys[2]),
+            Err(PVSSError::InvalidComplaint { index: 2 })));
+
+        // complaining about a correct share does not incriminate the dealer
+        let false_accusation = Complaint { index: 1, sk_id: sk(1) };
+        assert!(matches!(verify_complaint(&pedcom_params, &sharing, &false_accusation, pub_keys[1]),
+            Err(PVSSError::InvalidComplaint { index: 1 })));
+        assert!(matches!(verify_complaint(&pedcom_params, &sharing, &Complaint { index: 8, sk_id: sk(1) }, pub_keys[1]),
+            Err(PVSSError::IndexOutOfRange { index: 8, n: 8 })));
+    }
+
+    /// `count` dealings of the same (t, n) by dealers 0..count, and the keys of receiver `index`
+    fn dealings(
+        pedcom_params: &PedComParams,
+        ibe_keys: &[<TestIbe as IbeScheme>::MasterKeypair],
+        access: (u64, u64),
+        count: u64,
+        index: usize,
+    ) -> (Vec<Sharing<TestIbe>>, Vec<<TestIbe as IbeScheme>::SecretKeyID>) {
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        (0..count)
+            .map(|dealer| {
+                let sharing = share::<TestIbe, _, _>(pedcom_params, &pub_keys, &[7u8; 32], access, &SESSION, dealer, &mut thread_rng()).unwrap();
+                let sk = ibe_extract_id_secret_key::<TestIbe>(&sharing.receiver_id(index), &ibe_keys[index]);
+                (sharing, sk)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (15, 32);
+        let index = 5;
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let (mut sharings, sks) = dealings(&pedcom_params, &ibe_keys, (t, n), n, index);
+
+        let batched = batch_verify(&pedcom_params, &sharings, (t, n), index, &sks);
+        let single: Vec<_> = sharings.iter().zip(&sks)
+            .map(|(sharing, sk)| decrypt_share(&pedcom_params, sharing, (t, n), index, sk).unwrap())
+            .collect();
+        assert_eq!(batched.into_iter().map(Result::unwrap).collect::<Vec<_>>(), single);
+
+        // dealings broken in different ways are all told apart from the honest ones
+        sharings[3] = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t + 1, n), &SESSION, 3, &mut thread_rng()).unwrap();
+        sharings[3].access = (t, n);
+        let mut msg = [0u8; 96];
+        F::from(5u64).serialize_compressed(&mut msg[0..32]).unwrap();
+        sharings[8].ciphertexts[index] = ibe_encrypt::<TestIbe>(&msg, pub_keys[index], &sharings[8].receiver_id(index));
+        sharings[9].access = (t, n + 1);
+        sharings[20].ciphertexts[index] = ibe_encrypt::<TestIbe>(&[0xff; 96], pub_keys[index], &sharings[20].receiver_id(index));
+        // the commitment to the share of receiver `index` of dealing 25 taken from dealing 24
+        sharings[25].commitments[index] = sharings[24].commitments[index];
+
+        let results = batch_verify(&pedcom_params, &sharings, (t, n), index, &sks);
+        for (k, result) in results.iter().enumerate() {
+            match k {
+                3 => assert!(matches!(result, Err(PVSSError::DegreeCheckFailed))),
+                8 => assert!(matches!(result, Err(PVSSError::CommitmentMismatch { index: 5 }))),
+                9 => assert!(matches!(result, Err(PVSSError::AccessStructureMismatch { .. }))),
+                20 => assert!(matches!(result, Err(PVSSError::MalformedShare { index: 5 }))),
+                25 => assert!(result.is_err()),
+                _ => assert_eq!(result.as_ref().unwrap(), &single[k]),
+            }
+        }
+        assert!(batch_verify(&pedcom_params, &sharings[..1], (t, n), n as usize, &sks[..1])[0].is_err());
+        assert!(batch_verify::<TestIbe, Bls12G1>(&pedcom_params, &[], (t, n), index, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_seeded_batch_checks() {
+        use rand_chacha::{ChaCha20Rng, rand_core::{RngCore, SeedableRng}};
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (2, 6);
+        let index = 1;
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let (mut sharings, sks) = dealings(&pedcom_params, &ibe_keys, (t, n), 4, index);
+        let mut msg = [0u8; 96];
+        F::from(5u64).serialize_compressed(&mut msg[0..32]).unwrap();
+        sharings[2].ciphertexts[index] = ibe_encrypt::<TestIbe>(&msg, pub_keys[index], &sharings[2].receiver_id(index));
+
+        // the same seed gives the same results, drawing the weights from the given rng
+        let run = |seed: [u8; 32]| {
+            let mut rng = ChaCha20Rng::from_seed(seed);
+            let results = batch_verify_with_rng(&pedcom_params, &sharings, (t, n), index, &sks, &mut rng);
+            (results, rng.next_u64())
+        };
+        let (results, next) = run([4u8; 32]);
+        let (again, again_next) = run([4u8; 32]);
+        assert_eq!((format!("{:?}", again), again_next), (format!("{:?}", results), next));
+        assert_ne!(next, ChaCha20Rng::from_seed([4u8; 32]).next_u64());
+        assert!(matches!(results[2], Err(PVSSError::CommitmentMismatch { index: 1 })));
+        assert!(results.iter().enumerate().all(|(k, result)| k == 2 || result.is_ok()));
+
+        let all_sks: Vec<_> = (0..n as usize).map(|i| ibe_extract_id_secret_key::<TestIbe>(&sharings[0].receiver_id(i), &ibe_keys[i])).collect();
+        let run = |seed: [u8; 32]| {
+            let mut rng = ChaCha20Rng::from_seed(seed);
+            let results = verify_all_indices_with_rng(&pedcom_params, &sharings[0], (t, n), &all_sks, &mut rng).unwrap();
+            (results, rng.next_u64())
+        };
+        let (results, next) = run([5u8; 32]);
+        let (again, again_next) = run([5u8; 32]);
+        assert_eq!((format!("{:?}", again), again_next), (format!("{:?}", results), next));
+        assert_ne!(next, ChaCha20Rng::from_seed([5u8; 32]).next_u64());
+        assert!(results.iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn test_verify_all_indices() {
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (4, 12);
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let mut sharing = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+        let sks: Vec<_> = (0..n as usize).map(|i| ibe_extract_id_secret_key::<TestIbe>(&test_id(i), &ibe_keys[i])).collect();
+        let expected: Vec<_> = (0..n as usize).map(|i| decrypt_share(&pedcom_params, &sharing, (t, n), i, &sks[i]).unwrap()).collect();
+
+        let results = verify_all_indices(&pedcom_params, &sharing, (t, n), &sks).unwrap();
+        assert_eq!(results.into_iter().map(Result::unwrap).collect::<Vec<_>>(), expected);
+
+        #[cfg(feature = "parallel")]
+        {
+            let parallel = share_parallel::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+            assert!(verify_all_indices_parallel(&pedcom_params, &parallel, (t, n), &sks).unwrap().iter().all(Result::is_ok));
+            assert!(matches!(share_parallel::<TestIbe, _, _>(&pedcom_params, &pub_keys[1..], &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()),
+                Err(PVSSError::LengthMismatch { .. })));
+        }
+
+        let mut msg = [0u8; 96];
+        F::from(5u64).serialize_compressed(&mut msg[0..32]).unwrap();
+        sharing.ciphertexts[2] = ibe_encrypt::<TestIbe>(&msg, pub_keys[2], &test_id(2));
+        sharing.ciphertexts[7] = ibe_encrypt::<TestIbe>(&[0xff; 96], pub_keys[7], &test_id(7));
+        let check = |results: Vec<ShareResult>| {
+            for (i, result) in results.iter().enumerate() {
+                match i {
+                    2 => assert!(matches!(result, Err(PVSSError::CommitmentMismatch { index: 2 }))),
+                    7 => assert!(matches!(result, Err(PVSSError::MalformedShare { index: 7 }))),
+                    _ => assert_eq!(result.as_ref().unwrap(), &expected[i]),
+                }
+            }
+        };
+        check(verify_all_indices(&pedcom_params, &sharing, (t, n), &sks).unwrap());
+        #[cfg(feature = "parallel")]
+        check(verify_all_indices_parallel(&pedcom_params, &sharing, (t, n), &sks).unwrap());
+        assert!(matches!(verify_all_indices(&pedcom_params, &sharing, (t, n), &sks[1..]), Err(PVSSError::LengthMismatch { .. })));
+        assert!(verify_all_indices(&pedcom_params, &sharing, (t + 1, n), &sks).is_err());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (3, 8);
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let sk = |i: usize| ibe_extract_id_secret_key::<TestIbe>(&test_id(i), &ibe_keys[i]);
+        let sharing = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &[7u8; 32], (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        // a replayed dealing verifies and reconstructs like the original
+        let bytes = sharing.to_bytes();
+        let report = sharing.size_report();
+        assert_eq!(bytes.len(), report.total);
+        assert_eq!(report.commitments, 48 * n as usize);
+        assert_eq!(report.header + report.commitments + report.ciphertexts, report.total);
+        let decoded = Sharing::<TestIbe>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        let shares: Vec<_> = (0..=t as usize)
+            .map(|i| decrypt_share(&pedcom_params, &decoded, (t, n), i, &sk(i)).unwrap())
+            .collect();
+        assert_eq!(reconstruct(&pedcom_params, &decoded, &shares).unwrap(), [7u8; 32]);
+
+        let mut version = bytes.clone();
+        version[0] = 2;
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&version), Err(PVSSError::UnsupportedVersion(2))));
+        let mut tag = bytes.clone();
+        tag[1] = TAG_HASH_IBE;
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&tag), Err(PVSSError::ProtocolMismatch { expected: 1, actual: 2 })));
+        for len in [0, 10, HEADER_BYTES, HEADER_BYTES + 100, bytes.len() - 1] {
+            assert!(matches!(Sharing::<TestIbe>::from_bytes(&bytes[..len]), Err(PVSSError::SerializationError(_))));
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&trailing), Err(PVSSError::SerializationError(_))));
+        // a commitment that is not a curve point
+        let mut point = bytes.clone();
+        point[HEADER_BYTES..HEADER_BYTES + 48].fill(0x1f);
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&point), Err(PVSSError::SerializationError(_))));
+        // a huge n in the header fails on the missing bytes
+        let mut huge = bytes.clone();
+        huge[HEADER_BYTES - 8..HEADER_BYTES].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(Sharing::<TestIbe>::from_bytes(&huge), Err(PVSSError::SerializationError(_))));
+    }
+
+    #[test]
+    fn test_more_than_256_receivers() {
+        let pedcom_params = pedcom::setup::<Bls12G1>();
+        let (t, n) = (99, 300);
+        let ibe_keys: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = ibe_keys.iter().map(TestIbe::master_pk).collect();
+        let secret = [9u8; 32];
+        let sharing = share::<TestIbe, _, _>(&pedcom_params, &pub_keys, &secret, (t, n), &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        // receivers beyond 255 get their own identities, no longer those of i mod 256
+        assert_ne!(sharing.receiver_id(256), sharing.receiver_id(0));
+        for i in [0usize, 299] {
+            let sk = ibe_extract_id_secret_key::<TestIbe>(&sharing.receiver_id(i), &ibe_keys[i]);
+            verify(&pedcom_params, &sharing, (t, n), i, &sk).unwrap();
+        }
+
+        // reconstruct from the last t+1 receivers, all above 255
+        let shares: Vec<DecryptedShare> = (n as usize - t as usize - 1..n as usize)
+            .map(|i| {
+                let sk = ibe_extract_id_secret_key::<TestIbe>(&sharing.receiver_id(i), &ibe_keys[i]);
+                let msg = ibe_decrypt::<TestIbe>(&sharing.ciphertexts[i], &sk);
+                DecryptedShare {
+                    index: i,
+                    s: F::deserialize_compressed(&msg[0..32]).unwrap(),
+                    r: F::deserialize_compressed(&msg[32..64]).unwrap(),
+                }
+            })
+            .collect();
+        assert_eq!(reconstruct(&pedcom_params, &sharing, &shares).unwrap(), secret);
+
+        // keys of one session do not open the next sharing of the same dealer
+        let next = share::<TestIbe, _, _>(&pedcom_params, &pub_keys[..8], &secret, (3, 8), &[4u8; 32], DEALER, &mut thread_rng()).unwrap();
+        assert_ne!(next.receiver_id(0), sharing.receiver_id(0));
+        let sk_0 = ibe_extract_id_secret_key::<TestIbe>(&sharing.receiver_id(0), &ibe_keys[0]);
+        assert!(verify(&pedcom_params, &next, (3, 8), 0, &sk_0).is_err());
+    }
 }
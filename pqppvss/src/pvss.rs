@@ -0,0 +1,197 @@
+//! A common interface over the PVSS protocols of the crate, so applications
+//! can switch schemes by a type parameter:
+//!
+//! - [`PedersenPvss`](crate::prot_pvss_pedcom_ibe::PedersenPvss): shares committed with Pedersen commitments,
+//! - [`HashPvss`](crate::prot_pvss_hash_ibe::HashPvss): shares committed with hashes and a Fiat-Shamir proof.
+//!
+//! Every method takes the receivers' master public keys, even where a
+//! protocol does not need them, and the access structure `(t, n)` the caller
+//! expects rather than the one the dealer claims.
+
+use rand::Rng;
+use rust_bindings::IbeScheme;
+
+use crate::ibe::{ibe_extract_id_secret_key, SessionId};
+
+pub trait Pvss {
+    type Ibe: IbeScheme;
+    /// public parameters shared by dealers and receivers
+    type Params;
+    /// what a dealer publishes
+    type Sharing;
+    /// a share opened by its receiver, for reconstruction
+    type DecryptedShare;
+    type Error;
+
+    fn setup() -> Self::Params;
+
+    /// Shares `secret` among the owners of `pub_keys` with threshold t: any
+    /// t+1 shares reconstruct it. The secret, read big-endian, must be below
+    /// the modulus of the scalar field of the protocol.
+    fn deal<R: Rng>(
+        params: &Self::Params,
+        pub_keys: &[&<Self::Ibe as IbeScheme>::MasterPublicKey],
+        secret: &[u8; 32],
+        access: (u64, u64),
+        session_id: &SessionId,
+        dealer_id: u64,
+        rng: &mut R,
+    ) -> Result<Self::Sharing, Self::Error>;
+
+    /// identity receiver `index` needs the key of to decrypt its share
+    fn receiver_id(sharing: &Self::Sharing, index: usize) -> [u8; 96];
+
+    /// The key of receiver `index` for its share of `sharing`, extracted with
+    /// the receiver's master keypair.
+    fn extract_key(
+        keypair: &<Self::Ibe as IbeScheme>::MasterKeypair,
+        sharing: &Self::Sharing,
+        index: usize,
+    ) -> <Self::Ibe as IbeScheme>::SecretKeyID {
+        ibe_extract_id_secret_key::<Self::Ibe>(&Self::receiver_id(sharing, index), keypair)
+    }
+
+    /// The checks anybody can run on a sharing, without a receiver key.
+    fn verify_public(
+        params: &Self::Params,
+        sharing: &Self::Sharing,
+        pub_keys: &[&<Self::Ibe as IbeScheme>::MasterPublicKey],
+        access: (u64, u64),
+    ) -> Result<(), Self::Error>;
+
+    /// Decrypts the share at `index` and verifies it against the sharing.
+    fn decrypt_share(
+        params: &Self::Params,
+        sharing: &Self::Sharing,
+        pub_keys: &[&<Self::Ibe as IbeScheme>::MasterPublicKey],
+        access: (u64, u64),
+        index: usize,
+        sk: &<Self::Ibe as IbeScheme>::SecretKeyID,
+    ) -> Result<Self::DecryptedShare, Self::Error>;
+
+    /// The check of receiver `index`, without keeping its share.
+    fn verify_share(
+        params: &Self::Params,
+        sharing: &Self::Sharing,
+        pub_keys: &[&<Self::Ibe as IbeScheme>::MasterPublicKey],
+        access: (u64, u64),
+        index: usize,
+        sk: &<Self::Ibe as IbeScheme>::SecretKeyID,
+    ) -> Result<(), Self::Error> {
+        Self::decrypt_share(params, sharing, pub_keys, access, index, sk).map(|_| ())
+    }
+
+    /// Recovers the secret from at least t+1 published shares.
+    fn reconstruct(
+        params: &Self::Params,
+        sharing: &Self::Sharing,
+        shares: &[Self::DecryptedShare],
+    ) -> Result<[u8; 32], Self::Error>;
+}
+
+/// The same tests for every protocol.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ibe::{self, TestIbe};
+    use crate::pedcom::PVSSError;
+    use crate::prot_pvss_hash_ibe::HashPvss;
+    use crate::prot_pvss_pedcom_ibe::PedersenPvss;
+    use rand::thread_rng;
+
+    const SESSION: SessionId = [5u8; 32];
+    const DEALER: u64 = 2;
+
+    type Keys = Vec<<TestIbe as IbeScheme>::MasterKeypair>;
+
+    fn deal<P: Pvss<Ibe = TestIbe, Error = PVSSError>>(params: &P::Params, keys: &Keys, secret: &[u8; 32], access: (u64, u64)) -> P::Sharing {
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        P::deal(params, &pub_keys, secret, access, &SESSION, DEALER, &mut thread_rng()).unwrap()
+    }
+
+    fn decrypt_all<P: Pvss<Ibe = TestIbe, Error = PVSSError>>(
+        params: &P::Params,
+        sharing: &P::Sharing,
+        keys: &Keys,
+        access: (u64, u64),
+    ) -> Vec<P::DecryptedShare> {
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        (0..keys.len())
+            .map(|i| {
+                let sk = P::extract_key(&keys[i], sharing, i);
+                P::verify_share(params, sharing, &pub_keys, access, i, &sk).unwrap();
+                P::decrypt_share(params, sharing, &pub_keys, access, i, &sk).unwrap()
+            })
+            .collect()
+    }
+
+    fn honest_dealing<P: Pvss<Ibe = TestIbe, Error = PVSSError>>() {
+        let params = P::setup();
+        let (t, n) = (2, 6);
+        let keys: Keys = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let mut secret = [0u8; 32];
+        thread_rng().fill(&mut secret[1..]);
+        let sharing = deal::<P>(&params, &keys, &secret, (t, n));
+
+        P::verify_public(&params, &sharing, &pub_keys, (t, n)).unwrap();
+        let shares = decrypt_all::<P>(&params, &sharing, &keys, (t, n));
+        assert_eq!(P::reconstruct(&params, &sharing, &shares[..3]).unwrap(), secret);
+        assert_eq!(P::reconstruct(&params, &sharing, &shares[3..]).unwrap(), secret);
+        assert!(matches!(P::reconstruct(&params, &sharing, &shares[..2]), Err(PVSSError::NotEnoughShares { needed: 3, got: 2 })));
+    }
+
+    fn wrong_access_structure<P: Pvss<Ibe = TestIbe, Error = PVSSError>>() {
+        let params = P::setup();
+        let (t, n) = (2, 6);
+        let keys: Keys = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let sharing = deal::<P>(&params, &keys, &[9u8; 32], (t + 1, n));
+        assert!(matches!(P::verify_public(&params, &sharing, &pub_keys, (t, n)),
+            Err(PVSSError::AccessStructureMismatch { expected: (2, 6), actual: (3, 6) })));
+        let sk = P::extract_key(&keys[0], &sharing, 0);
+        assert!(P::verify_share(&params, &sharing, &pub_keys, (t, n), 0, &sk).is_err());
+    }
+
+    fn out_of_range_secret<P: Pvss<Ibe = TestIbe, Error = PVSSError>>() {
+        let params = P::setup();
+        let keys: Keys = (0..4).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let dealt = P::deal(&params, &pub_keys, &[0xffu8; 32], (1, 4), &SESSION, DEALER, &mut thread_rng());
+        assert!(matches!(dealt, Err(PVSSError::SecretOutOfRange)));
+    }
+
+    #[test]
+    fn test_out_of_range_secret() {
+        out_of_range_secret::<PedersenPvss<TestIbe>>();
+        out_of_range_secret::<PedersenPvss<TestIbe, ark_secp256k1::Projective>>();
+        out_of_range_secret::<HashPvss<TestIbe>>();
+    }
+
+    #[test]
+    fn test_pedersen_honest_dealing() {
+        honest_dealing::<PedersenPvss<TestIbe>>();
+    }
+
+    #[test]
+    fn test_hash_honest_dealing() {
+        honest_dealing::<HashPvss<TestIbe>>();
+    }
+
+    #[test]
+    fn test_pedersen_other_curves() {
+        honest_dealing::<PedersenPvss<TestIbe, ark_secp256k1::Projective>>();
+        #[cfg(feature = "ristretto")]
+        honest_dealing::<PedersenPvss<TestIbe, crate::ristretto::Ristretto>>();
+    }
+
+    #[test]
+    fn test_pedersen_wrong_access_structure() {
+        wrong_access_structure::<PedersenPvss<TestIbe>>();
+    }
+
+    #[test]
+    fn test_hash_wrong_access_structure() {
+        wrong_access_structure::<HashPvss<TestIbe>>();
+    }
+}
@@ -0,0 +1,279 @@
+//! Registry of the receivers' IBE master public keys, by participant.
+//!
+//! A participant registers its key with a proof of possession: the IBE key
+//! of an identity derived from the registry context and its participant ID,
+//! extracted with its master secret key. This is a GPV signature on the
+//! identity, which anybody verifies against the master public key. A key
+//! registered without its master secret key, or copied from another
+//! participant, has no such proof. The identity of the proof never is the
+//! identity of a share, so revealing its key opens nothing.
+//!
+//! Dealers take the keys of a list of participants from the registry:
+//! receiver i of the sharing is the i-th participant of the list.
+
+use rand::Rng;
+use rust_bindings::IbeScheme;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ibe::*;
+use crate::pedcom::PVSSError;
+use crate::pvss::Pvss;
+use crate::serialization::*;
+use crate::utils;
+
+/// protocol label of the identities of the proofs of possession
+pub const LABEL: &[u8] = b"pqppvss/registry";
+
+/// A master public key, with the proof that `participant` holds its secret
+/// key.
+pub struct Registration<I: IbeScheme> {
+    pub participant: u64,
+    pub public_key: I::PublicKey,
+    pub proof: I::SecretKeyID,
+}
+
+impl<I: IbeScheme> Registration<I> {
+    /// The registration of `keypair` by `participant` in the registry of
+    /// `context`.
+    pub fn new(context: &[u8; 32], participant: u64, keypair: &I::MasterKeypair) -> Result<Self, PVSSError> {
+        let public_key = I::public_key_from_bytes(&I::public_key_to_bytes(I::master_pk(keypair)))?;
+        let proof = ibe_extract_id_secret_key::<I>(&pop_id(context, participant), keypair);
+        Ok(Registration { participant, public_key, proof })
+    }
+
+    /// whether the proof is the key of the identity of `participant` in the
+    /// registry of `context`
+    pub fn verify(&self, context: &[u8; 32]) -> bool {
+        ibe_verify_id_secret_key::<I>(&pop_id(context, self.participant), self.public_key.borrow(), &self.proof)
+    }
+}
+
+/// identity whose key proves possession of a master secret key
+pub fn pop_id(context: &[u8; 32], participant: u64) -> [u8; 96] {
+    receiver_id(LABEL, context, participant, 0)
+}
+
+/// The keys of the participants, each registered with a valid proof of
+/// possession. `context` names the registry, e.g. a committee and epoch, so
+/// that proofs of one registry are not accepted by another.
+pub struct KeyRegistry<I: IbeScheme> {
+    context: [u8; 32],
+    registrations: BTreeMap<u64, Registration<I>>,
+    /// participant of each registered key, by the hash of the key
+    key_owners: HashMap<[u8; 32], u64>,
+}
+
+impl<I: IbeScheme> KeyRegistry<I> {
+    pub fn new(context: [u8; 32]) -> Self {
+        KeyRegistry { context, registrations: BTreeMap::new(), key_owners: HashMap::new() }
+    }
+
+    pub fn context(&self) -> &[u8; 32] {
+        &self.context
+    }
+
+    /// Adds a registration after checking its proof of possession. A
+    /// participant registers once, and no two participants register the same
+    /// key.
+    pub fn register(&mut self, registration: Registration<I>) -> Result<(), PVSSError> {
+        let participant = registration.participant;
+        if self.registrations.contains_key(&participant) {
+            return Err(PVSSError::DuplicateParticipant { participant });
+        }
+        let key_hash = utils::digest_sha256(&[&I::public_key_to_bytes(registration.public_key.borrow())]);
+        if let Some(&registered_by) = self.key_owners.get(&key_hash) {
+            return Err(PVSSError::DuplicateKey { participant, registered_by });
+        }
+        if !registration.verify(&self.context) {
+            return Err(PVSSError::InvalidProofOfPossession { participant });
+        }
+        self.key_owners.insert(key_hash, participant);
+        self.registrations.insert(participant, registration);
+        Ok(())
+    }
+
+    pub fn get(&self, participant: u64) -> Option<&I::MasterPublicKey> {
+        self.registrations.get(&participant).map(|registration| registration.public_key.borrow())
+    }
+
+    /// the registered participants, in increasing order
+    pub fn participants(&self) -> impl Iterator<Item = u64> + '_ {
+        self.registrations.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+
+    /// The keys of `participants`, in order, as `share` takes them: receiver
+    /// i of a sharing is `participants[i]`. Every participant is registered
+    /// and listed once.
+    pub fn pub_keys(&self, participants: &[u64]) -> Result<Vec<&I::MasterPublicKey>, PVSSError> {
+        let mut listed = std::collections::HashSet::new();
+        participants.iter()
+            .map(|&participant| {
+                if !listed.insert(participant) {
+                    return Err(PVSSError::DuplicateParticipant { participant });
+                }
+                self.get(participant).ok_or(PVSSError::UnknownParticipant { participant })
+            })
+            .collect()
+    }
+
+    /// Shares `secret` among `participants` with threshold t, with the
+    /// protocol `P`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn deal<P: Pvss<Ibe = I, Error = PVSSError>, R: Rng>(
+        &self,
+        params: &P::Params,
+        participants: &[u64],
+        secret: &[u8; 32],
+        t: u64,
+        session_id: &SessionId,
+        dealer_id: u64,
+        rng: &mut R
+    ) -> Result<P::Sharing, PVSSError> {
+        let pub_keys = self.pub_keys(participants)?;
+        P::deal(params, &pub_keys, secret, (t, participants.len() as u64), session_id, dealer_id, rng)
+    }
+
+    /// Encodes the registry in the format of [`crate::serialization`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![FORMAT_VERSION, TAG_KEY_REGISTRY];
+        out.extend_from_slice(&self.context);
+        out.extend_from_slice(&(self.registrations.len() as u64).to_le_bytes());
+        for registration in self.registrations.values() {
+            out.extend_from_slice(&registration.participant.to_le_bytes());
+            write_prefixed(&I::public_key_to_bytes(registration.public_key.borrow()), &mut out);
+            write_prefixed(&I::secret_key_to_bytes(&registration.proof), &mut out);
+        }
+        out
+    }
+
+    /// Decodes a registry written by [`KeyRegistry::to_bytes`], checking
+    /// every registration again.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PVSSError> {
+        let mut reader = bytes;
+        let [version, tag] = read_array::<2>(&mut reader)?;
+        if version != FORMAT_VERSION {
+            return Err(PVSSError::UnsupportedVersion(version));
+        }
+        if tag != TAG_KEY_REGISTRY {
+            return Err(PVSSError::ProtocolMismatch { expected: TAG_KEY_REGISTRY, actual: tag });
+        }
+        let mut registry = KeyRegistry::new(read_array::<32>(&mut reader)?);
+        for _ in 0..read_u64(&mut reader)? {
+            let participant = read_u64(&mut reader)?;
+            let public_key = I::public_key_from_bytes(read_prefixed(&mut reader)?)?;
+            let proof = I::secret_key_from_bytes(read_prefixed(&mut reader)?)?;
+            registry.register(Registration { participant, public_key, proof })?;
+        }
+        finish(reader)?;
+        Ok(registry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ibe::{self, TestIbe};
+    use crate::prot_pvss_pedcom_ibe::PedersenPvss;
+    use crate::prot_pvss_hash_ibe::HashPvss;
+    use rand::thread_rng;
+
+    const CONTEXT: [u8; 32] = [8u8; 32];
+    const SESSION: SessionId = [9u8; 32];
+
+    /// a registry of participants 100, 101, ... and their keypairs
+    fn registry(n: u64) -> (KeyRegistry<TestIbe>, Vec<<TestIbe as IbeScheme>::MasterKeypair>) {
+        let keypairs: Vec<_> = (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let mut registry = KeyRegistry::new(CONTEXT);
+        for (i, keypair) in keypairs.iter().enumerate() {
+            registry.register(Registration::new(&CONTEXT, 100 + i as u64, keypair).unwrap()).unwrap();
+        }
+        (registry, keypairs)
+    }
+
+    fn deal_and_reconstruct<P: Pvss<Ibe = TestIbe, Error = PVSSError>>(registry: &KeyRegistry<TestIbe>, keypairs: &[<TestIbe as IbeScheme>::MasterKeypair]) {
+        let params = P::setup();
+        let (t, participants) = (2, [103, 100, 104, 101]);
+        let sharing = registry.deal::<P, _>(&params, &participants, &[7u8; 32], t, &SESSION, 0, &mut thread_rng()).unwrap();
+        let pub_keys = registry.pub_keys(&participants).unwrap();
+        let shares: Vec<_> = participants.iter().enumerate()
+            .map(|(i, &participant)| {
+                let sk = ibe_extract_id_secret_key::<TestIbe>(&P::receiver_id(&sharing, i), &keypairs[(participant - 100) as usize]);
+                P::decrypt_share(&params, &sharing, &pub_keys, (t, 4), i, &sk).unwrap()
+            })
+            .collect();
+        assert_eq!(P::reconstruct(&params, &sharing, &shares[1..]).unwrap(), [7u8; 32]);
+    }
+
+    #[test]
+    fn test_registry_feeds_share() {
+        let (registry, keypairs) = registry(5);
+        assert_eq!(registry.len(), 5);
+        assert_eq!(registry.participants().collect::<Vec<_>>(), vec![100, 101, 102, 103, 104]);
+        deal_and_reconstruct::<PedersenPvss<TestIbe>>(&registry, &keypairs);
+        deal_and_reconstruct::<HashPvss<TestIbe>>(&registry, &keypairs);
+
+        assert!(matches!(registry.pub_keys(&[100, 7]), Err(PVSSError::UnknownParticipant { participant: 7 })));
+        assert!(matches!(registry.pub_keys(&[100, 101, 100]), Err(PVSSError::DuplicateParticipant { participant: 100 })));
+        let params = <PedersenPvss<TestIbe> as Pvss>::setup();
+        assert!(registry.deal::<PedersenPvss<TestIbe>, _>(&params, &[100, 5], &[7u8; 32], 0, &SESSION, 0, &mut thread_rng()).is_err());
+    }
+
+    #[test]
+    fn test_registration_needs_proof_of_possession() {
+        let (mut registry, keypairs) = registry(2);
+        let keypair = ibe::ibe_keygen::<TestIbe>();
+
+        // a proof for another participant, or for another registry
+        let mut stolen = Registration::<TestIbe>::new(&CONTEXT, 7, &keypair).unwrap();
+        stolen.participant = 8;
+        assert!(matches!(registry.register(stolen), Err(PVSSError::InvalidProofOfPossession { participant: 8 })));
+        let other_context = Registration::<TestIbe>::new(&[0u8; 32], 7, &keypair).unwrap();
+        assert!(matches!(registry.register(other_context), Err(PVSSError::InvalidProofOfPossession { participant: 7 })));
+
+        // a key without its master secret key: the proof of another key
+        let mut rogue = Registration::<TestIbe>::new(&CONTEXT, 7, &keypair).unwrap();
+        rogue.proof = Registration::<TestIbe>::new(&CONTEXT, 7, &ibe::ibe_keygen::<TestIbe>()).unwrap().proof;
+        assert!(matches!(registry.register(rogue), Err(PVSSError::InvalidProofOfPossession { participant: 7 })));
+
+        // the key of participant 100, registered again
+        let copied = Registration::<TestIbe>::new(&CONTEXT, 7, &keypairs[0]).unwrap();
+        assert!(matches!(registry.register(copied), Err(PVSSError::DuplicateKey { participant: 7, registered_by: 100 })));
+        let again = Registration::<TestIbe>::new(&CONTEXT, 101, &keypair).unwrap();
+        assert!(matches!(registry.register(again), Err(PVSSError::DuplicateParticipant { participant: 101 })));
+
+        registry.register(Registration::new(&CONTEXT, 7, &keypair).unwrap()).unwrap();
+        assert_eq!(registry.len(), 3);
+    }
+
+    #[test]
+    fn test_registry_serialization() {
+        let (registry, keypairs) = registry(5);
+        let bytes = registry.to_bytes();
+        let decoded = KeyRegistry::<TestIbe>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.context(), &CONTEXT);
+        deal_and_reconstruct::<PedersenPvss<TestIbe>>(&decoded, &keypairs);
+
+        assert!(KeyRegistry::<TestIbe>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(KeyRegistry::<TestIbe>::from_bytes(&trailing).is_err());
+        let mut tag = bytes.clone();
+        tag[1] = TAG_PEDCOM_IBE;
+        assert!(matches!(KeyRegistry::<TestIbe>::from_bytes(&tag), Err(PVSSError::ProtocolMismatch { .. })));
+
+        // a proof moved to another registry does not verify there
+        let mut context = bytes.clone();
+        context[2] ^= 1;
+        assert!(matches!(KeyRegistry::<TestIbe>::from_bytes(&context), Err(PVSSError::InvalidProofOfPossession { participant: 100 })));
+    }
+}
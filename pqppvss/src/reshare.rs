@@ -0,0 +1,473 @@
+//! Resharing a secret to a new committee, and refreshing the shares of a
+//! committee, for secrets that outlive committee membership.
+//!
+//! Both start from a secret shared with Pedersen commitments C_i = g^s_i h^r_i
+//! to the shares of the holders, as a [`Sharing`] of `prot_pvss_pedcom_ibe`
+//! or a DKG leaves them. Holder i deals with `prot_pvss_pedcom_ibe`, as dealer
+//! i of a fresh session:
+//!
+//! - to reshare, its own share: the polynomials of its dealing have constant
+//!   terms s_i and r_i, so that their commitment at zero is C_i. New holders
+//!   combine the dealings of any t+1 holders with the Lagrange weights of the
+//!   old holders;
+//! - to refresh, zero: the polynomials have constant terms 0, so that their
+//!   commitment at zero is the identity. Holders add the shares of zero to
+//!   their own, and the commitments to theirs.
+//!
+//! Anybody can check each dealing against the old commitments, and the
+//! commitments that result. A dealing can pass those checks and still give a
+//! holder a bad share; that holder complains as in a DKG, with
+//! [`receive`](crate::dkg::receive), and dealings with a valid complaint are
+//! left out when combining.
+
+use ark_ff::Zero;
+use rand::Rng;
+use rust_bindings::IbeScheme;
+
+use crate::dkg::DkgComplaint;
+use crate::ibe::SessionId;
+use crate::pedcom::*;
+use crate::prot_pvss_pedcom_ibe::{decrypt_share, share_polynomials, verify_complaint, verify_public, DecryptedShare, Sharing};
+use crate::utils;
+
+/// the commitment to the value a dealing shares, interpolated from its first t+1 commitments
+fn commitment_at_zero<I: IbeScheme, G: PedComGroup>(dealing: &Sharing<I, G>) -> G::Element {
+    let t = dealing.access.0 as usize;
+    let xs: Vec<G::Scalar> = (1..=t as u64 + 1).map(G::Scalar::from).collect();
+    interpolate_commitment::<G>(&xs, &dealing.commitments[..t + 1], G::Scalar::zero())
+}
+
+/// the public checks of a dealing of the session, and that it shares the
+/// value committed to by `expected`
+fn check_dealing<I: IbeScheme, G: PedComGroup>(
+    dealing: &Sharing<I, G>,
+    access: (u64, u64),
+    session_id: &SessionId,
+    expected: impl FnOnce(usize) -> Result<G::Element, PVSSError>
+) -> Result<(), PVSSError> {
+    check_access(access)?;
+    verify_public(dealing, access)?;
+    let dealer = dealing.dealer_id as usize;
+    if dealing.session_id != *session_id || commitment_at_zero(dealing) != expected(dealer)? {
+        return Err(PVSSError::ResharingMismatch { dealer });
+    }
+    Ok(())
+}
+
+/// Deals the share of an old holder to the new committee, as dealer
+/// `old_share.index` of the new session.
+pub fn reshare<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    old_share: &DecryptedShare<G::Scalar>,
+    pub_keys: &[&I::MasterPublicKey],
+    new_access: (u64, u64),
+    session_id: &SessionId,
+    rng: &mut R
+) -> Result<Sharing<I, G>, PVSSError> {
+    check_access(new_access)?;
+    check_len(new_access.1, pub_keys.len())?;
+    let t = new_access.0;
+    let secret_poly = utils::sample_poly(Some(old_share.s), t, rng);
+    let random_poly = utils::sample_poly(Some(old_share.r), t, rng);
+    Ok(share_polynomials(pedcom_params, pub_keys, &secret_poly, &random_poly, new_access, session_id, old_share.index as u64))
+}
+
+/// Checks that a dealing of the session reshares the share of its dealer,
+/// committed to in `old_commitments`.
+pub fn verify_reshare<I: IbeScheme, G: PedComGroup>(
+    dealing: &Sharing<I, G>,
+    new_access: (u64, u64),
+    session_id: &SessionId,
+    old_commitments: &[G::Element]
+) -> Result<(), PVSSError> {
+    check_dealing(dealing, new_access, session_id, |dealer| {
+        old_commitments.get(dealer).copied()
+            .ok_or(PVSSError::IndexOutOfRange { index: dealer, n: old_commitments.len() as u64 })
+    })
+}
+
+/// which dealings a valid complaint was raised against; `complaint.dealer` is
+/// the position of the dealing in `dealings`, as [`receive`](crate::dkg::receive)
+/// reports it, and `pub_keys` are the master public keys of the receivers
+fn accused<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    dealings: &[Sharing<I, G>],
+    complaints: &[DkgComplaint<I>],
+    pub_keys: &[&I::MasterPublicKey]
+) -> Vec<bool> {
+    let mut accused = vec![false; dealings.len()];
+    for DkgComplaint { dealer, complaint } in complaints {
+        let (Some(dealing), Some(&receiver_mpk)) = (dealings.get(*dealer), pub_keys.get(complaint.index)) else { continue };
+        if !accused[*dealer] && verify_complaint(pedcom_params, dealing, complaint, receiver_mpk).is_ok() {
+            accused[*dealer] = true;
+        }
+    }
+    accused
+}
+
+/// What everybody agrees on after a resharing.
+pub struct Resharing<G: PedComGroup> {
+    /// the positions of the t+1 dealings combined
+    pub dealings: Vec<usize>,
+    /// their Lagrange weights
+    pub weights: Vec<G::Scalar>,
+    /// the commitments to the shares of the new holders
+    pub commitments: Vec<G::Element>,
+}
+
+/// Picks the first t+1 dealings of distinct old holders that verify and that
+/// no valid complaint was raised against, and combines their commitments into
+/// those of the new holders. The result is checked against the old commitment
+/// to the secret. `pub_keys` are the master public keys of the new holders.
+#[allow(clippy::too_many_arguments)]
+pub fn combine_commitments<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    old_access: (u64, u64),
+    old_commitments: &[G::Element],
+    dealings: &[Sharing<I, G>],
+    new_access: (u64, u64),
+    session_id: &SessionId,
+    complaints: &[DkgComplaint<I>],
+    pub_keys: &[&I::MasterPublicKey]
+) -> Result<Resharing<G>, PVSSError> {
+    let (t, n) = old_access;
+    check_access(old_access)?;
+    check_access(new_access)?;
+    check_len(n, old_commitments.len())?;
+    if !degree_check::<G>(old_commitments, t) {
+        return Err(PVSSError::DegreeCheckFailed);
+    }
+    let accused = accused(pedcom_params, dealings, complaints, pub_keys);
+    let mut chosen: Vec<usize> = Vec::new();
+    for (k, dealing) in dealings.iter().enumerate() {
+        if (chosen.len() as u64) == t + 1 {
+            break;
+        }
+        if !accused[k]
+            && chosen.iter().all(|&c| dealings[c].dealer_id != dealing.dealer_id)
+            && verify_reshare(dealing, new_access, session_id, old_commitments).is_ok() {
+            chosen.push(k);
+        }
+    }
+    if (chosen.len() as u64) < t + 1 {
+        return Err(PVSSError::NotEnoughShares { needed: t + 1, got: chosen.len() });
+    }
+
+    let xs: Vec<G::Scalar> = chosen.iter().map(|&k| G::Scalar::from(dealings[k].dealer_id + 1)).collect();
+    let weights = lagrange_coefficients(&xs, G::Scalar::zero());
+    let commitments: Vec<G::Element> = (0..new_access.1 as usize)
+        .map(|j| {
+            let column: Vec<G::Element> = chosen.iter().map(|&k| dealings[k].commitments[j]).collect();
+            G::msm(&column, &weights)
+        })
+        .collect();
+
+    let old_xs: Vec<G::Scalar> = (1..=t + 1).map(G::Scalar::from).collect();
+    let old_secret = interpolate_commitment::<G>(&old_xs, &old_commitments[..(t + 1) as usize], G::Scalar::zero());
+    let new_xs: Vec<G::Scalar> = (1..=new_access.0 + 1).map(G::Scalar::from).collect();
+    let new_secret = interpolate_commitment::<G>(&new_xs, &commitments[..(new_access.0 + 1) as usize], G::Scalar::zero());
+    if old_secret != new_secret {
+        return Err(PVSSError::SecretMismatch);
+    }
+    Ok(Resharing { dealings: chosen, weights, commitments })
+}
+
+/// The share of new holder `index`, from the dealings picked by
+/// [`combine_commitments`]; `sks[m]` is its key for `resharing.dealings[m]`.
+pub fn combine_share<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    dealings: &[Sharing<I, G>],
+    resharing: &Resharing<G>,
+    new_access: (u64, u64),
+    index: usize,
+    sks: &[I::SecretKeyID]
+) -> Result<DecryptedShare<G::Scalar>, PVSSError> {
+    check_len(resharing.dealings.len() as u64, sks.len())?;
+    let mut share = DecryptedShare { index, s: G::Scalar::zero(), r: G::Scalar::zero() };
+    for ((&k, weight), sk) in resharing.dealings.iter().zip(&resharing.weights).zip(sks) {
+        let part = decrypt_share(pedcom_params, &dealings[k], new_access, index, sk)?;
+        share.s += *weight * part.s;
+        share.r += *weight * part.r;
+    }
+    if resharing.commitments.get(index) != Some(&commit(pedcom_params, share.s, share.r)) {
+        return Err(PVSSError::CommitmentMismatch { index });
+    }
+    Ok(share)
+}
+
+/// Deals a sharing of zero to the committee, as dealer `dealer_id` of the
+/// refresh session.
+pub fn refresh_deal<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    pub_keys: &[&I::MasterPublicKey],
+    access: (u64, u64),
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R
+) -> Result<Sharing<I, G>, PVSSError> {
+    check_access(access)?;
+    check_len(access.1, pub_keys.len())?;
+    let secret_poly = utils::sample_poly(Some(G::Scalar::zero()), access.0, rng);
+    let random_poly = utils::sample_poly(Some(G::Scalar::zero()), access.0, rng);
+    Ok(share_polynomials(pedcom_params, pub_keys, &secret_poly, &random_poly, access, session_id, dealer_id))
+}
+
+/// Checks that a dealing of the refresh session shares zero.
+pub fn verify_refresh<I: IbeScheme, G: PedComGroup>(
+    dealing: &Sharing<I, G>,
+    access: (u64, u64),
+    session_id: &SessionId
+) -> Result<(), PVSSError> {
+    check_dealing(dealing, access, session_id, |_| Ok(G::zero()))
+}
+
+/// What everybody agrees on after a refresh.
+pub struct Refresh<G: PedComGroup> {
+    /// the positions of the dealings added
+    pub dealings: Vec<usize>,
+    /// the commitments to the refreshed shares
+    pub commitments: Vec<G::Element>,
+}
+
+/// Adds the commitments of all dealings of distinct dealers that verify, and
+/// that no valid complaint was raised against, to the old ones. `pub_keys`
+/// are the master public keys of the holders.
+pub fn refresh_commitments<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    access: (u64, u64),
+    old_commitments: &[G::Element],
+    dealings: &[Sharing<I, G>],
+    session_id: &SessionId,
+    complaints: &[DkgComplaint<I>],
+    pub_keys: &[&I::MasterPublicKey]
+) -> Result<Refresh<G>, PVSSError> {
+    check_access(access)?;
+    check_len(access.1, old_commitments.len())?;
+    let accused = accused(pedcom_params, dealings, complaints, pub_keys);
+    let mut chosen: Vec<usize> = Vec::new();
+    for (k, dealing) in dealings.iter().enumerate() {
+        if !accused[k]
+            && chosen.iter().all(|&c| dealings[c].dealer_id != dealing.dealer_id)
+            && verify_refresh(dealing, access, session_id).is_ok() {
+            chosen.push(k);
+        }
+    }
+    let commitments = old_commitments.iter().enumerate()
+        .map(|(j, c)| chosen.iter().fold(*c, |acc, &k| G::add(&acc, &dealings[k].commitments[j])))
+        .collect();
+    Ok(Refresh { dealings: chosen, commitments })
+}
+
+/// The refreshed share of holder `old_share.index`; `sks[m]` is its key for
+/// `refresh.dealings[m]`.
+pub fn refresh_share<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    dealings: &[Sharing<I, G>],
+    refresh: &Refresh<G>,
+    access: (u64, u64),
+    old_share: &DecryptedShare<G::Scalar>,
+    sks: &[I::SecretKeyID]
+) -> Result<DecryptedShare<G::Scalar>, PVSSError> {
+    check_len(refresh.dealings.len() as u64, sks.len())?;
+    let index = old_share.index;
+    let mut share = old_share.clone();
+    for (&k, sk) in refresh.dealings.iter().zip(sks) {
+        let part = decrypt_share(pedcom_params, &dealings[k], access, index, sk)?;
+        share.s += part.s;
+        share.r += part.r;
+    }
+    if refresh.commitments.get(index) != Some(&commit(pedcom_params, share.s, share.r)) {
+        return Err(PVSSError::CommitmentMismatch { index });
+    }
+    Ok(share)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dkg::receive;
+    use crate::ibe::{self, ibe_encrypt, ibe_extract_id_secret_key, TestIbe};
+    use crate::prot_pvss_pedcom_ibe::{share, Complaint};
+    use ark_serialize::CanonicalSerialize;
+    use rand::thread_rng;
+
+    type F = ark_bls12_381::Fr;
+    type Keys = Vec<<TestIbe as IbeScheme>::MasterKeypair>;
+
+    const OLD_SESSION: SessionId = [1u8; 32];
+    const NEW_SESSION: SessionId = [2u8; 32];
+
+    fn committee(n: u64) -> Keys {
+        (0..n).map(|_| ibe::ibe_keygen::<TestIbe>()).collect()
+    }
+
+    fn secret_of(shares: &[DecryptedShare<F>]) -> [u8; 32] {
+        let points: Vec<(F, F)> = shares.iter().map(|s| (F::from(s.index as u64 + 1), s.s)).collect();
+        utils::field_to_secret_bytes(&utils::lagrange_interpolate_at_zero(&points))
+    }
+
+    /// a secret shared among `keys` with threshold t, and the decrypted shares
+    fn old_sharing(params: &PedComParams, keys: &Keys, t: u64, secret: &[u8; 32]) -> (Sharing<TestIbe>, Vec<DecryptedShare<F>>) {
+        let n = keys.len() as u64;
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let sharing = share::<TestIbe, _, _>(params, &pub_keys, secret, (t, n), &OLD_SESSION, 0, &mut thread_rng()).unwrap();
+        let shares = (0..n as usize)
+            .map(|i| {
+                let sk = ibe_extract_id_secret_key::<TestIbe>(&sharing.receiver_id(i), &keys[i]);
+                decrypt_share(params, &sharing, (t, n), i, &sk).unwrap()
+            })
+            .collect();
+        (sharing, shares)
+    }
+
+    fn keys_for(dealings: &[Sharing<TestIbe>], chosen: &[usize], keys: &Keys, index: usize) -> Vec<<TestIbe as IbeScheme>::SecretKeyID> {
+        chosen.iter().map(|&k| ibe_extract_id_secret_key::<TestIbe>(&dealings[k].receiver_id(index), &keys[index])).collect()
+    }
+
+    #[test]
+    fn test_reshare_to_new_committee() {
+        let params = setup::<Bls12G1>();
+        let (t, n) = (2, 5);
+        let (new_t, new_n) = (3, 7);
+        let secret = [0x2au8; 32];
+        let old_keys = committee(n);
+        let (sharing, old_shares) = old_sharing(&params, &old_keys, t, &secret);
+        let new_keys = committee(new_n);
+        let new_pub_keys: Vec<_> = new_keys.iter().map(TestIbe::master_pk).collect();
+
+        // holder 1 is offline, holder 3 reshares another value
+        let mut dealings: Vec<Sharing<TestIbe>> = [0, 2, 3, 4].iter()
+            .map(|&i| reshare(&params, &old_shares[i], &new_pub_keys, (new_t, new_n), &NEW_SESSION, &mut thread_rng()).unwrap())
+            .collect();
+        let mut lie = old_shares[3].clone();
+        lie.s += F::from(1u64);
+        dealings[2] = reshare(&params, &lie, &new_pub_keys, (new_t, new_n), &NEW_SESSION, &mut thread_rng()).unwrap();
+        assert!(matches!(reshare::<TestIbe, _, _>(&params, &old_shares[0], &new_pub_keys, (new_n, new_n), &NEW_SESSION, &mut thread_rng()),
+            Err(PVSSError::InvalidThreshold { t: 7, n: 7 })));
+        assert!(matches!(reshare::<TestIbe, _, _>(&params, &old_shares[0], &new_pub_keys[1..], (new_t, new_n), &NEW_SESSION, &mut thread_rng()),
+            Err(PVSSError::LengthMismatch { expected: 7, actual: 6 })));
+        assert!(matches!(verify_reshare(&dealings[2], (new_t, new_n), &NEW_SESSION, &sharing.commitments),
+            Err(PVSSError::ResharingMismatch { dealer: 3 })));
+        verify_reshare(&dealings[0], (new_t, new_n), &NEW_SESSION, &sharing.commitments).unwrap();
+        assert!(verify_reshare(&dealings[0], (new_t, new_n), &OLD_SESSION, &sharing.commitments).is_err());
+
+        let resharing = combine_commitments(&params, (t, n), &sharing.commitments, &dealings, (new_t, new_n), &NEW_SESSION, &[], &new_pub_keys).unwrap();
+        assert_eq!(resharing.dealings, vec![0, 1, 3]);
+        let new_shares: Vec<_> = (0..new_n as usize)
+            .map(|j| {
+                let sks = keys_for(&dealings, &resharing.dealings, &new_keys, j);
+                combine_share(&params, &dealings, &resharing, (new_t, new_n), j, &sks).unwrap()
+            })
+            .collect();
+        assert!(degree_check::<Bls12G1>(&resharing.commitments, new_t));
+        assert_eq!(secret_of(&new_shares[..4]), secret);
+        assert_eq!(secret_of(&new_shares[3..]), secret);
+        assert_ne!(secret_of(&new_shares[..3]), secret);
+
+        // without t+1 honest holders, there is nothing to combine
+        assert!(matches!(combine_commitments(&params, (t, n), &sharing.commitments, &dealings[1..3], (new_t, new_n), &NEW_SESSION, &[], &new_pub_keys),
+            Err(PVSSError::NotEnoughShares { needed: 3, got: 1 })));
+
+        // thresholds of at least n are rejected, not indexed past the commitments
+        assert!(matches!(combine_commitments(&params, (n, n), &sharing.commitments, &dealings, (new_t, new_n), &NEW_SESSION, &[], &new_pub_keys),
+            Err(PVSSError::InvalidThreshold { t: 5, n: 5 })));
+        assert!(matches!(combine_commitments(&params, (t, n), &sharing.commitments, &dealings, (new_n, new_n), &NEW_SESSION, &[], &new_pub_keys),
+            Err(PVSSError::InvalidThreshold { t: 7, n: 7 })));
+        assert!(matches!(verify_reshare(&dealings[0], (new_n + 1, new_n), &NEW_SESSION, &sharing.commitments),
+            Err(PVSSError::InvalidThreshold { t: 8, n: 7 })));
+    }
+
+    #[test]
+    fn test_reshare_with_complaint() {
+        let params = setup::<Bls12G1>();
+        let (t, n) = (1, 4);
+        let (new_t, new_n) = (1, 4);
+        let secret = [0x33u8; 32];
+        let old_keys = committee(n);
+        let (sharing, old_shares) = old_sharing(&params, &old_keys, t, &secret);
+        let new_keys = committee(new_n);
+        let new_pub_keys: Vec<_> = new_keys.iter().map(TestIbe::master_pk).collect();
+
+        // holder 0 passes the public checks but gives new holder 2 a bad share
+        let mut dealings: Vec<Sharing<TestIbe>> = (0..n as usize)
+            .map(|i| reshare(&params, &old_shares[i], &new_pub_keys, (new_t, new_n), &NEW_SESSION, &mut thread_rng()).unwrap())
+            .collect();
+        let mut msg = [0u8; 96];
+        F::from(5u64).serialize_compressed(&mut msg[0..32]).unwrap();
+        dealings[0].ciphertexts[2] = ibe_encrypt::<TestIbe>(&msg, new_pub_keys[2], &dealings[0].receiver_id(2));
+        verify_reshare(&dealings[0], (new_t, new_n), &NEW_SESSION, &sharing.commitments).unwrap();
+
+        let all: Vec<usize> = (0..dealings.len()).collect();
+        let (_, complaints) = receive(&params, &dealings, (new_t, new_n), 2, keys_for(&dealings, &all, &new_keys, 2));
+        assert_eq!(complaints.len(), 1);
+        let resharing = combine_commitments(&params, (t, n), &sharing.commitments, &dealings, (new_t, new_n), &NEW_SESSION, &complaints, &new_pub_keys).unwrap();
+        assert_eq!(resharing.dealings, vec![1, 2]);
+        let new_shares: Vec<_> = (0..new_n as usize)
+            .map(|j| {
+                let sks = keys_for(&dealings, &resharing.dealings, &new_keys, j);
+                combine_share(&params, &dealings, &resharing, (new_t, new_n), j, &sks).unwrap()
+            })
+            .collect();
+        assert_eq!(secret_of(&new_shares[2..]), secret);
+    }
+
+    #[test]
+    fn test_refresh() {
+        let params = setup::<Bls12G1>();
+        let (t, n) = (2, 5);
+        let secret = [0x17u8; 32];
+        let keys = committee(n);
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let (sharing, old_shares) = old_sharing(&params, &keys, t, &secret);
+
+        let mut dealings: Vec<Sharing<TestIbe>> = (0..n)
+            .map(|i| refresh_deal(&params, &pub_keys, (t, n), &NEW_SESSION, i, &mut thread_rng()).unwrap())
+            .collect();
+        // holder 4 deals a sharing of something else than zero
+        dealings[4] = share::<TestIbe, _, _>(&params, &pub_keys, &[1u8; 32], (t, n), &NEW_SESSION, 4, &mut thread_rng()).unwrap();
+        assert!(matches!(verify_refresh(&dealings[4], (t, n), &NEW_SESSION), Err(PVSSError::ResharingMismatch { dealer: 4 })));
+        assert!(matches!(refresh_deal::<TestIbe, _, _>(&params, &pub_keys, (n, n), &NEW_SESSION, 0, &mut thread_rng()),
+            Err(PVSSError::InvalidThreshold { t: 5, n: 5 })));
+
+        // holder 1 passes the public checks but gives holder 3 a bad share of zero
+        let mut msg = [0u8; 96];
+        F::from(5u64).serialize_compressed(&mut msg[0..32]).unwrap();
+        dealings[1].ciphertexts[3] = ibe_encrypt::<TestIbe>(&msg, pub_keys[3], &dealings[1].receiver_id(3));
+        verify_refresh(&dealings[1], (t, n), &NEW_SESSION).unwrap();
+        // without the complaint of holder 3, its share would be lost
+        let unchecked = refresh_commitments(&params, (t, n), &sharing.commitments, &dealings, &NEW_SESSION, &[], &pub_keys).unwrap();
+        let sks = keys_for(&dealings, &unchecked.dealings, &keys, 3);
+        assert!(refresh_share(&params, &dealings, &unchecked, (t, n), &old_shares[3], &sks).is_err());
+
+        let all: Vec<usize> = (0..dealings.len()).collect();
+        let (_, mut complaints) = receive(&params, &dealings, (t, n), 3, keys_for(&dealings, &all, &keys, 3));
+        assert_eq!(complaints.len(), 1);
+        // a complaint about an honest dealing does not count
+        let sk_id = ibe_extract_id_secret_key::<TestIbe>(&dealings[0].receiver_id(2), &keys[2]);
+        complaints.push(DkgComplaint { dealer: 0, complaint: Complaint { index: 2, sk_id } });
+        let refresh = refresh_commitments(&params, (t, n), &sharing.commitments, &dealings, &NEW_SESSION, &complaints, &pub_keys).unwrap();
+        assert_eq!(refresh.dealings, vec![0, 2, 3]);
+        let new_shares: Vec<_> = (0..n as usize)
+            .map(|i| {
+                let sks = keys_for(&dealings, &refresh.dealings, &keys, i);
+                refresh_share(&params, &dealings, &refresh, (t, n), &old_shares[i], &sks).unwrap()
+            })
+            .collect();
+        assert!(degree_check::<Bls12G1>(&refresh.commitments, t));
+        for (old, new) in old_shares.iter().zip(&new_shares) {
+            assert_ne!(old.s, new.s);
+        }
+        assert_eq!(secret_of(&new_shares[1..4]), secret);
+        // old and new shares do not mix
+        let mixed = [old_shares[0].clone(), new_shares[1].clone(), new_shares[2].clone()];
+        assert_ne!(secret_of(&mixed), secret);
+        // a stale share does not open the new commitments
+        let sks = keys_for(&dealings, &refresh.dealings[1..], &keys, 0);
+        let partial = Refresh { dealings: refresh.dealings[1..].to_vec(), commitments: refresh.commitments.clone() };
+        assert!(matches!(refresh_share(&params, &dealings, &partial, (t, n), &old_shares[0], &sks),
+            Err(PVSSError::CommitmentMismatch { index: 0 })));
+
+        assert!(matches!(refresh_commitments(&params, (n, n), &sharing.commitments, &dealings, &NEW_SESSION, &[], &pub_keys),
+            Err(PVSSError::InvalidThreshold { t: 5, n: 5 })));
+    }
+}
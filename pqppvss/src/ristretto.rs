@@ -0,0 +1,122 @@
+//! Pedersen commitments in the Ristretto group of curve25519-dalek, the group
+//! the private polling stack commits in.
+//!
+//! Ristretto has prime order ℓ = 2^252 + 27742317777372353535851937790883648493,
+//! the order of the ed25519 scalar field, so the shares are `ark_ed25519::Fr`
+//! elements; [`to_dalek_scalar`] and [`from_dalek_scalar`] convert them.
+
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate, Write};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::traits::{Identity, VartimeMultiscalarMul};
+use curve25519_dalek::Scalar;
+use sha2::{Digest, Sha512};
+
+use crate::pedcom::{PVSSError, PedComGroup};
+
+pub type Fr = ark_ed25519::Fr;
+
+/// The Ristretto group, as a [`PedComGroup`].
+pub struct Ristretto;
+
+/// A Ristretto point, encoded in its 32-byte compressed form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RistrettoElement(pub RistrettoPoint);
+
+pub fn to_dalek_scalar(s: &Fr) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&s.into_bigint().to_bytes_le());
+    // canonical, as s < ℓ
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+pub fn from_dalek_scalar(s: &Scalar) -> Fr {
+    Fr::from_le_bytes_mod_order(s.as_bytes())
+}
+
+impl PedComGroup for Ristretto {
+    type Scalar = Fr;
+    type Element = RistrettoElement;
+
+    fn generator() -> RistrettoElement {
+        RistrettoElement(RISTRETTO_BASEPOINT_POINT)
+    }
+
+    /// the Elligator-based map of RFC 9496 on a SHA-512 digest
+    fn hash_to_generator(msg: &[u8]) -> Result<RistrettoElement, PVSSError> {
+        const DST: &[u8] = b"pqppvss/ristretto/hash-to-group";
+        let digest: [u8; 64] = Sha512::new().chain_update(DST).chain_update(msg).finalize().into();
+        Ok(RistrettoElement(RistrettoPoint::from_uniform_bytes(&digest)))
+    }
+
+    fn zero() -> RistrettoElement {
+        RistrettoElement(RistrettoPoint::identity())
+    }
+
+    fn add(a: &RistrettoElement, b: &RistrettoElement) -> RistrettoElement {
+        RistrettoElement(a.0 + b.0)
+    }
+
+    fn mul(a: &RistrettoElement, s: &Fr) -> RistrettoElement {
+        RistrettoElement(a.0 * to_dalek_scalar(s))
+    }
+
+    fn msm(bases: &[RistrettoElement], scalars: &[Fr]) -> RistrettoElement {
+        assert_eq!(bases.len(), scalars.len());
+        RistrettoElement(RistrettoPoint::vartime_multiscalar_mul(scalars.iter().map(to_dalek_scalar), bases.iter().map(|b| b.0)))
+    }
+}
+
+impl CanonicalSerialize for RistrettoElement {
+    fn serialize_with_mode<W: Write>(&self, mut writer: W, _: Compress) -> Result<(), SerializationError> {
+        writer.write_all(self.0.compress().as_bytes())?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, _: Compress) -> usize {
+        32
+    }
+}
+
+impl Valid for RistrettoElement {
+    // decoding only ever yields valid points
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for RistrettoElement {
+    fn deserialize_with_mode<R: Read>(mut reader: R, _: Compress, _: Validate) -> Result<Self, SerializationError> {
+        let mut bytes = [0u8; 32];
+        reader.read_exact(&mut bytes)?;
+        CompressedRistretto(bytes).decompress().map(RistrettoElement).ok_or(SerializationError::InvalidData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+
+    #[test]
+    fn test_scalars_convert() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..32 {
+            let s = Fr::rand(&mut rng);
+            assert_eq!(from_dalek_scalar(&to_dalek_scalar(&s)), s);
+        }
+        assert_eq!(to_dalek_scalar(&-Fr::from(1u64)), -Scalar::ONE);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let h = Ristretto::hash_to_generator(b"Pedersen Commitment Generator").unwrap();
+        let mut bytes = Vec::new();
+        h.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(RistrettoElement::deserialize_compressed(&bytes[..]).unwrap(), h);
+        // not a canonical encoding
+        assert!(RistrettoElement::deserialize_compressed(&[0xffu8; 32][..]).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Byte format of PVSS sharings, as broadcast by a dealer.
+//!
+//! All integers are little-endian, field and group elements use the compressed
+//! `ark_serialize` encoding (32 bytes for a scalar, 48 bytes for a BLS12-381 G1
+//! point, 33 for secp256k1, 32 for Ristretto), and ciphertexts the encoding of
+//! the IBE backend, prefixed by their length. The group of a Pedersen sharing
+//! is not part of the encoding: it is fixed by the type decoded into.
+//!
+//! ```text
+//! header      version (u8) | protocol tag (u8) | session id (32) | dealer id (u64) | t (u64) | n (u64)
+//! pedcom      header | n commitments | n ciphertexts
+//! hash        header | t+1 committed_secret (Fr) | n csh (32) | n cpt (32) | n ciphertexts
+//! ciphertext  length (u32) | bytes
+//! registry    version (u8) | tag (u8) | context (32) | count (u64) | count entries
+//! entry       participant id (u64) | public key length (u32) | bytes | proof length (u32) | bytes
+//! ```
+//!
+//! Decoding rejects other versions and protocol tags, points off the curve or
+//! outside the prime-order subgroup, malformed ciphertexts and trailing bytes.
+//! A decoded key registry has every proof of possession checked again.
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use rust_bindings::IbeScheme;
+
+use crate::ibe::SessionId;
+use crate::pedcom::PVSSError;
+
+/// version written by this library, and the only one it reads
+pub const FORMAT_VERSION: u8 = 1;
+/// protocol tag of `prot_pvss_pedcom_ibe::Sharing`
+pub const TAG_PEDCOM_IBE: u8 = 1;
+/// protocol tag of `prot_pvss_hash_ibe::Sharing`
+pub const TAG_HASH_IBE: u8 = 2;
+/// tag of `registry::KeyRegistry`
+pub const TAG_KEY_REGISTRY: u8 = 3;
+
+pub(crate) const HEADER_BYTES: usize = 2 + 32 + 3 * 8;
+
+/// Byte count of each part of an encoded sharing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SizeReport {
+    /// version, protocol tag, session, dealer and access structure
+    pub header: usize,
+    /// Pedersen commitments, or the committed polynomial and the hashes
+    pub commitments: usize,
+    /// the ciphertexts, with their length prefixes
+    pub ciphertexts: usize,
+    pub total: usize,
+}
+
+impl SizeReport {
+    pub(crate) fn new(commitments: usize, ciphertexts: usize) -> Self {
+        SizeReport { header: HEADER_BYTES, commitments, ciphertexts, total: HEADER_BYTES + commitments + ciphertexts }
+    }
+}
+
+pub(crate) struct Header {
+    pub session_id: SessionId,
+    pub dealer_id: u64,
+    pub access: (u64, u64),
+}
+
+impl Header {
+    pub fn write(&self, tag: u8, out: &mut Vec<u8>) {
+        out.push(FORMAT_VERSION);
+        out.push(tag);
+        out.extend_from_slice(&self.session_id);
+        out.extend_from_slice(&self.dealer_id.to_le_bytes());
+        out.extend_from_slice(&self.access.0.to_le_bytes());
+        out.extend_from_slice(&self.access.1.to_le_bytes());
+    }
+
+    pub fn read(tag: u8, reader: &mut &[u8]) -> Result<Self, PVSSError> {
+        let [version, actual] = read_array::<2>(reader)?;
+        if version != FORMAT_VERSION {
+            return Err(PVSSError::UnsupportedVersion(version));
+        }
+        if actual != tag {
+            return Err(PVSSError::ProtocolMismatch { expected: tag, actual });
+        }
+        let session_id = read_array::<32>(reader)?;
+        let dealer_id = read_u64(reader)?;
+        let t = read_u64(reader)?;
+        let n = read_u64(reader)?;
+        Ok(Header { session_id, dealer_id, access: (t, n) })
+    }
+}
+
+pub(crate) fn read_array<const L: usize>(reader: &mut &[u8]) -> Result<[u8; L], PVSSError> {
+    if reader.len() < L {
+        return Err(SerializationError::InvalidData.into());
+    }
+    let (bytes, rest) = reader.split_at(L);
+    *reader = rest;
+    Ok(bytes.try_into().unwrap())
+}
+
+pub(crate) fn read_u64(reader: &mut &[u8]) -> Result<u64, PVSSError> {
+    Ok(u64::from_le_bytes(read_array::<8>(reader)?))
+}
+
+/// compressed `ark_serialize` encoding of `t`
+pub(crate) fn write_element<T: CanonicalSerialize>(t: &T, out: &mut Vec<u8>) {
+    // writing into a vector cannot fail
+    t.serialize_compressed(out).unwrap();
+}
+
+/// reads a compressed and validated element
+pub(crate) fn read_element<T: CanonicalDeserialize>(reader: &mut &[u8]) -> Result<T, PVSSError> {
+    Ok(T::deserialize_compressed(reader)?)
+}
+
+pub(crate) fn write_ciphertexts<I: IbeScheme>(ciphertexts: &[I::Ciphertext], out: &mut Vec<u8>) {
+    for ct in ciphertexts {
+        write_prefixed(&I::ciphertext_to_bytes(ct), out);
+    }
+}
+
+/// `n` ciphertexts; `n` comes from untrusted input, so nothing is allocated
+/// ahead of the bytes actually present
+pub(crate) fn read_ciphertexts<I: IbeScheme>(n: u64, reader: &mut &[u8]) -> Result<Vec<I::Ciphertext>, PVSSError> {
+    let mut ciphertexts = Vec::new();
+    for _ in 0..n {
+        ciphertexts.push(I::ciphertext_from_bytes(read_prefixed(reader)?)?);
+    }
+    Ok(ciphertexts)
+}
+
+/// `bytes`, prefixed by their length
+pub(crate) fn write_prefixed(bytes: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+pub(crate) fn read_prefixed<'a>(reader: &mut &'a [u8]) -> Result<&'a [u8], PVSSError> {
+    let len = u32::from_le_bytes(read_array::<4>(reader)?) as usize;
+    if reader.len() < len {
+        return Err(SerializationError::InvalidData.into());
+    }
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(bytes)
+}
+
+pub(crate) fn ciphertexts_size<I: IbeScheme>(ciphertexts: &[I::Ciphertext]) -> usize {
+    ciphertexts.iter().map(|ct| 4 + I::ciphertext_to_bytes(ct).len()).sum()
+}
+
+/// rejects trailing bytes after a sharing
+pub(crate) fn finish(reader: &[u8]) -> Result<(), PVSSError> {
+    if !reader.is_empty() {
+        return Err(SerializationError::InvalidData.into());
+    }
+    Ok(())
+}
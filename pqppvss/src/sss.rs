@@ -3,37 +3,70 @@
 //! # Shamir Secret Sharing Implementation
 //!
 //! This module provides an implementation of Shamir's Secret Sharing scheme for 256-bit secret.
-//! The intended purpose is to secret-share an AES key, which can be used to protect larger secrets.
+//! The intended purpose is to secret-share an AES key, which can be used to protect larger secrets
+//! (see [`crate::long_secret::share_bytes`] for the PVSS counterpart).
 //!
 //! ## Overview
 //!
 //! Shamir's Secret Sharing allows a secret to be split into `n` shares such that any `t` out of `n`
 //! shares can reconstruct the secret, but fewer than `t` shares reveal nothing about the secret.
+//! Note that the PVSS modules count differently: there, `(t, n)` needs t+1 shares.
 //!
-//! The implementation uses the scalar field of the `ark_bw6_761` curve, which is large enough for 256-bit secret.
+//! The implementation is generic over the prime field; the verifiable variants use the scalar field
+//! of a [`PedComGroup`].
 //!
 //! ## Public Functions
 //!
-//! - [`share`] - Splits a secret into `n` shares with a reconstruction threshold of `t`.
+//! - [`share`] - Splits a secret into `n` shares with a reconstruction threshold of `t`, at random x-coordinates.
+//! - [`share_with`] - The same, with a choice of [`XCoordinates`].
 //! - [`recover`] - Recovers the original secret from a set of valid Shamir shares using Lagrange interpolation.
+//! - [`feldman_share`], [`feldman_verify`], [`feldman_recover`] - Feldman VSS: the dealer publishes
+//!   g^{a_k} for every coefficient a_k, which binds the shares but reveals g^secret.
+//! - [`pedersen_share`], [`pedersen_verify`], [`pedersen_recover`] - Pedersen VSS: the dealer publishes
+//!   g^{a_k} h^{b_k} with a second random polynomial, which hides the secret.
 //!
 //! ## Details
 //!
-//! - Each share is a tuple `(x, y)` where `x` is a random field element and `y` is the evaluation of a random
+//! - Each share is a tuple `(x, y)` where `x` is an evaluation point and `y` is the evaluation of a random
 //!   degree `t-1` polynomial (with the secret as the constant term) at `x`.
-//! - Serialization and deserialization of field elements is handled using compressed representations.
+//! - With [`XCoordinates::Sequential`], share i is at x = i+1, as in the PVSS modules.
 //! - The secret is encoded as the constant term of the polynomial and is recovered by interpolating at `x = 0`.
+//! - The verifiable variants are meant for deployments with trusted channels to the receivers: the shares
+//!   are not encrypted, but every receiver can check its own against the published commitments.
 //!
 
-use ark_ff::PrimeField;
+use ark_ff::{PrimeField, UniformRand};
 use ark_poly::{Polynomial, univariate::DensePolynomial};
 use rand::Rng;
 
+use crate::pedcom::{commit, lagrange_coefficients, PVSSError, PedComGroup, PedComParams};
+
+/// How the x-coordinates of the shares are chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XCoordinates {
+    /// x = i+1 for share i, as in the PVSS modules
+    Sequential,
+    /// uniformly random, distinct and nonzero
+    Random,
+}
+
+/// A Shamir share `(x, y)`.
+pub type Share<F> = (F, F);
+
+/// A share of the Pedersen VSS: the evaluations of the secret and of the
+/// blinding polynomial at `x`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PedersenShare<F> {
+    pub x: F,
+    pub y: F,
+    pub r: F,
+}
+
 /// Splits a 256-bit secret into Shamir shares with a specified threshold and total number of shares.
 ///
 /// # Arguments
 ///
-/// * `secret` - A reference to a 32-byte array representing the secret to be shared.
+/// * `secret` - The field element to be shared.
 /// * `access` - A tuple `(t, n)` where:
 ///     - `t` is the reconstruction threshold (minimum number of shares required to recover the secret),
 ///     - `n` is the total number of shares to generate.
@@ -41,46 +74,32 @@ use rand::Rng;
 ///
 /// # Returns
 ///
-/// A vector of `(Vec<u8>, Vec<u8>)` tuples, where each tuple represents a Shamir share:
-/// - The first element is the serialized x-coordinate (as a field element).
-/// - The second element is the serialized y-coordinate (as a field element).
+/// A vector of `(F, F)` tuples, where each tuple represents a Shamir share:
+/// - The first element is the x-coordinate, chosen at random.
+/// - The second element is the y-coordinate.
 ///
 pub fn share<R: Rng, F: PrimeField>(secret: &F, access: (u64, u64), rng: &mut R) -> Vec<(F, F)> {
-    // parse the desired access structure.
-    // n is the number of shares, while
-    // t <= n is the reconstruction threshold.
-    let (t, n) = access;
-
-    // let us sample a random degree t-1 polynomial, with t coefficients,
-    // except that the first coefficient is the secret.
-    let coeffs: Vec<F> = (0..t)
-        .map(|i| if i == 0 { secret.clone() } else { F::rand(rng) })
-        .collect();
-    let poly = DensePolynomial { coeffs };
-
-    // Shamir shares are just evaluations of our polynomial above
-    let shares = (0..n)
-        .map(|_| {
-            let x = F::rand(rng);
-            let y = poly.evaluate(&x);
-            (x, y)
-        })
-        .collect();
+    share_with(secret, access, XCoordinates::Random, rng)
+}
 
-    shares
+/// Splits a secret as [`share`] does, at the x-coordinates `xs` chooses.
+pub fn share_with<R: Rng, F: PrimeField>(secret: &F, access: (u64, u64), xs: XCoordinates, rng: &mut R) -> Vec<(F, F)> {
+    let (t, n) = access;
+    let poly = sample_poly(*secret, t, rng);
+    x_coordinates(xs, n, rng).into_iter()
+        .map(|x| (x, poly.evaluate(&x)))
+        .collect()
 }
 
 /// Recovers the 256-bit secret from a set of Shamir shares.
 ///
 /// # Arguments
 ///
-/// * `shares` - A vector of tuples, where each tuple contains two byte vectors:
-///   - The first element is the serialized x-coordinate of the share (as a field element).
-///   - The second element is the serialized y-coordinate of the share (as a field element).
+/// * `shares` - A vector of `(x, y)` tuples, one per share.
 ///
 /// # Returns
 ///
-/// * `[u8; 32]` - The recovered secret as a 32-byte array.
+/// * `F` - The recovered secret as a field element.
 ///
 pub fn recover<F: PrimeField>(shares: Vec<(F, F)>) -> F {
     // let us separate out the x and y coordinates of the shares.
@@ -92,36 +111,144 @@ pub fn recover<F: PrimeField>(shares: Vec<(F, F)>) -> F {
     let lagrange_coeffs = lagrange_coefficients(&xs[..], F::from(0));
 
     //secret f(0) as a field element
-    let secret = ys
-        .iter()
+    ys.iter()
         .zip(lagrange_coeffs.iter())
-        .fold(F::from(0), |acc, (&a, &b)| acc + (a * b));
+        .fold(F::from(0), |acc, (&a, &b)| acc + (a * b))
+}
 
-    secret
+/// Splits `secret` as [`share_with`] does, and commits to the coefficients
+/// of the polynomial in the exponent of g.
+pub fn feldman_share<G: PedComGroup, R: Rng>(
+    params: &PedComParams<G>,
+    secret: &G::Scalar,
+    access: (u64, u64),
+    xs: XCoordinates,
+    rng: &mut R
+) -> (Vec<Share<G::Scalar>>, Vec<G::Element>) {
+    let (t, n) = access;
+    let poly = sample_poly(*secret, t, rng);
+    let commitments = poly.coeffs.iter().map(|a| commit(params, *a, G::Scalar::from(0))).collect();
+    let shares = x_coordinates(xs, n, rng).into_iter()
+        .map(|x| (x, poly.evaluate(&x)))
+        .collect();
+    (shares, commitments)
 }
 
-// Naive lagrange interpolation over the input x-coordinates.
-// This method computes the lagrange coefficients, which should
-// be used to compute an inner product with the y-coordinates.
-// reference: https://en.wikipedia.org/wiki/Lagrange_polynomial
-fn lagrange_coefficients<F: PrimeField>(xs: &[F], x: F) -> Vec<F> {
-    let mut output = Vec::new();
+/// Whether `share` lies on the polynomial committed to by [`feldman_share`].
+pub fn feldman_verify<G: PedComGroup>(
+    params: &PedComParams<G>,
+    commitments: &[G::Element],
+    share: &Share<G::Scalar>
+) -> bool {
+    let (x, y) = *share;
+    commit(params, y, G::Scalar::from(0)) == evaluate_commitments::<G>(commitments, x)
+}
 
-    for (i, &x_i) in xs.iter().enumerate() {
-        let mut l_i = F::from(1);
-        for (j, &x_j) in xs.iter().enumerate() {
-            if i != j {
-                l_i *= (x - x_j) / (x_i - x_j);
+/// Recovers the secret from at least t shares, each verified first.
+pub fn feldman_recover<G: PedComGroup>(
+    params: &PedComParams<G>,
+    commitments: &[G::Element],
+    shares: &[Share<G::Scalar>]
+) -> Result<G::Scalar, PVSSError> {
+    let xs: Vec<G::Scalar> = shares.iter().map(|(x, _)| *x).collect();
+    check_shares(commitments.len(), &xs, |k| feldman_verify(params, commitments, &shares[k]))?;
+    Ok(recover(shares[..commitments.len()].to_vec()))
+}
+
+/// Splits `secret` as [`share_with`] does, with a second random polynomial
+/// to blind the commitments g^{a_k} h^{b_k} to the coefficients.
+pub fn pedersen_share<G: PedComGroup, R: Rng>(
+    params: &PedComParams<G>,
+    secret: &G::Scalar,
+    access: (u64, u64),
+    xs: XCoordinates,
+    rng: &mut R
+) -> (Vec<PedersenShare<G::Scalar>>, Vec<G::Element>) {
+    let (t, n) = access;
+    let secret_poly = sample_poly(*secret, t, rng);
+    let random_poly = sample_poly(G::Scalar::rand(rng), t, rng);
+    let commitments = secret_poly.coeffs.iter().zip(&random_poly.coeffs)
+        .map(|(a, b)| commit(params, *a, *b))
+        .collect();
+    let shares = x_coordinates(xs, n, rng).into_iter()
+        .map(|x| PedersenShare { x, y: secret_poly.evaluate(&x), r: random_poly.evaluate(&x) })
+        .collect();
+    (shares, commitments)
+}
+
+/// Whether `share` lies on the polynomials committed to by [`pedersen_share`].
+pub fn pedersen_verify<G: PedComGroup>(
+    params: &PedComParams<G>,
+    commitments: &[G::Element],
+    share: &PedersenShare<G::Scalar>
+) -> bool {
+    commit(params, share.y, share.r) == evaluate_commitments::<G>(commitments, share.x)
+}
+
+/// Recovers the secret from at least t shares, each verified first.
+pub fn pedersen_recover<G: PedComGroup>(
+    params: &PedComParams<G>,
+    commitments: &[G::Element],
+    shares: &[PedersenShare<G::Scalar>]
+) -> Result<G::Scalar, PVSSError> {
+    let xs: Vec<G::Scalar> = shares.iter().map(|share| share.x).collect();
+    check_shares(commitments.len(), &xs, |k| pedersen_verify(params, commitments, &shares[k]))?;
+    Ok(recover(shares[..commitments.len()].iter().map(|share| (share.x, share.y)).collect()))
+}
+
+/// a random polynomial with t coefficients, the first of which is `secret`
+fn sample_poly<F: PrimeField, R: Rng>(secret: F, t: u64, rng: &mut R) -> DensePolynomial<F> {
+    assert!(t >= 1, "The threshold must be at least 1.");
+    let coeffs: Vec<F> = (0..t)
+        .map(|i| if i == 0 { secret } else { F::rand(rng) })
+        .collect();
+    DensePolynomial { coeffs }
+}
+
+fn x_coordinates<F: PrimeField, R: Rng>(xs: XCoordinates, n: u64, rng: &mut R) -> Vec<F> {
+    match xs {
+        XCoordinates::Sequential => (1..=n).map(F::from).collect(),
+        XCoordinates::Random => {
+            let mut xs = Vec::new();
+            while (xs.len() as u64) < n {
+                let x = F::rand(rng);
+                if !x.is_zero() && !xs.contains(&x) {
+                    xs.push(x);
+                }
             }
+            xs
+        }
+    }
+}
+
+/// commitment to the polynomial at `x`: the sum of x^k C_k
+fn evaluate_commitments<G: PedComGroup>(commitments: &[G::Element], x: G::Scalar) -> G::Element {
+    let powers: Vec<G::Scalar> = std::iter::successors(Some(G::Scalar::from(1)), |p| Some(*p * x))
+        .take(commitments.len())
+        .collect();
+    G::msm(commitments, &powers)
+}
+
+/// at least t shares, at distinct x-coordinates, all of which verify
+fn check_shares<F: PrimeField>(t: usize, xs: &[F], verify: impl Fn(usize) -> bool) -> Result<(), PVSSError> {
+    if xs.len() < t {
+        return Err(PVSSError::NotEnoughShares { needed: t as u64, got: xs.len() });
+    }
+    for (k, x) in xs.iter().enumerate() {
+        if xs[..k].contains(x) {
+            return Err(PVSSError::DuplicateIndex { index: k });
+        }
+        if !verify(k) {
+            return Err(PVSSError::CommitmentMismatch { index: k });
         }
-        output.push(l_i);
     }
-    output
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pedcom::{setup, Bls12G1};
     use rand::thread_rng;
     use rand_chacha::rand_core::SeedableRng;
 
@@ -145,4 +272,47 @@ mod tests {
 
         assert_eq!(secret, recovered);
     }
+
+    #[test]
+    fn test_sequential_x_coordinates() {
+        let secret = F::from(7);
+        let shares = share_with(&secret, (3, 5), XCoordinates::Sequential, &mut thread_rng());
+        let xs: Vec<F> = shares.iter().map(|(x, _)| *x).collect();
+        assert_eq!(xs, (1..=5u64).map(F::from).collect::<Vec<_>>());
+        assert_eq!(recover(shares[2..].to_vec()), secret);
+        assert_ne!(recover(shares[3..].to_vec()), secret);
+    }
+
+    #[test]
+    fn test_feldman_vss() {
+        type Fr = ark_bls12_381::Fr;
+        let params = setup::<Bls12G1>();
+        let secret = Fr::from(1234u64);
+        for xs in [XCoordinates::Sequential, XCoordinates::Random] {
+            let (shares, commitments) = feldman_share(&params, &secret, (3, 6), xs, &mut thread_rng());
+            assert!(shares.iter().all(|share| feldman_verify(&params, &commitments, share)));
+            assert_eq!(feldman_recover(&params, &commitments, &shares[1..4]).unwrap(), secret);
+
+            let mut forged = shares[..3].to_vec();
+            forged[2].1 += Fr::from(1u64);
+            assert!(matches!(feldman_recover(&params, &commitments, &forged), Err(PVSSError::CommitmentMismatch { index: 2 })));
+            assert!(matches!(feldman_recover(&params, &commitments, &shares[..2]), Err(PVSSError::NotEnoughShares { needed: 3, got: 2 })));
+        }
+    }
+
+    #[test]
+    fn test_pedersen_vss() {
+        type Fr = ark_bls12_381::Fr;
+        let params = setup::<Bls12G1>();
+        let secret = Fr::from(99u64);
+        let (shares, commitments) = pedersen_share(&params, &secret, (2, 4), XCoordinates::Sequential, &mut thread_rng());
+        assert!(shares.iter().all(|share| pedersen_verify(&params, &commitments, share)));
+        assert_eq!(pedersen_recover(&params, &commitments, &shares[2..]).unwrap(), secret);
+
+        let mut forged = shares[1].clone();
+        forged.r += Fr::from(1u64);
+        assert!(!pedersen_verify(&params, &commitments, &forged));
+        let duplicated = [shares[0].clone(), shares[0].clone()];
+        assert!(matches!(pedersen_recover(&params, &commitments, &duplicated), Err(PVSSError::DuplicateIndex { index: 1 })));
+    }
 }
@@ -3,6 +3,10 @@ use ark_poly::univariate::DensePolynomial;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use sha2::{Digest, Sha256};
 use rand::Rng;
+use std::any::{Any, TypeId};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::pedcom::PVSSError;
 
 pub fn bytes_to_bits_be(x: &[u8]) -> Vec<bool> {
     //convert byte array to bit array for BigInt conversion
@@ -19,7 +23,7 @@ pub fn bytes_to_bits_be(x: &[u8]) -> Vec<bool> {
 }
 
 pub fn sample_poly<F: PrimeField, R: Rng>(
-    secret: Option<&[u8; 32]>,
+    secret: Option<F>,
     degree: u64,
     rng: &mut R,
 ) -> DensePolynomial<F> {
@@ -28,25 +32,92 @@ pub fn sample_poly<F: PrimeField, R: Rng>(
         .map(|_| F::rand(rng))
         .collect();
 
-    if secret.is_some() {
+    if let Some(secret) = secret {
         // But we don't want a completely random polynomial, 
         // but rather one whose evaluation at x=0 is the secret.
         // So, let us replace zero-th coefficient with our secret.
-        let secret_bigint = BigInteger::from_bits_be(
-            &bytes_to_bits_be(secret.unwrap()));
-        coeffs[0] = F::from_bigint(secret_bigint).unwrap();
+        coeffs[0] = secret;
     }
 
     DensePolynomial { coeffs }
 }
 
+/// Reads a 32-byte secret as a big-endian integer. Secrets are not reduced:
+/// one not below the modulus of `F` (about half of all 32-byte strings for
+/// BLS12-381) is rejected, so that reconstruction always returns the bytes
+/// that were shared.
+pub fn secret_to_field<F: PrimeField>(secret: &[u8; 32]) -> Result<F, PVSSError> {
+    let bigint = BigInteger::from_bits_be(&bytes_to_bits_be(secret));
+    F::from_bigint(bigint).ok_or(PVSSError::SecretOutOfRange)
+}
+
+/// evaluates at x = 0 the polynomial of degree < points.len() through `points`
+pub fn lagrange_interpolate_at_zero<F: PrimeField>(points: &[(F, F)]) -> F {
+    points.iter().enumerate().map(|(j, &(x_j, y_j))| {
+        // lambda_j = product( x_m / (x_m - x_j) ) for all m != j
+        let lambda_j = points.iter().enumerate()
+            .filter(|&(m, _)| m != j)
+            .fold(F::one(), |acc, (_, &(x_m, _))| acc * x_m / (x_m - x_j));
+        lambda_j * y_j
+    }).sum()
+}
+
+/// inverse of [`secret_to_field`]: the big-endian bytes of `x`
+pub fn field_to_secret_bytes<F: PrimeField>(x: &F) -> [u8; 32] {
+    let be = x.into_bigint().to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - be.len()..].copy_from_slice(&be);
+    out
+}
+
+/// number of values [`cached`] keeps; the least recently used one is dropped
+/// beyond that
+pub const CACHE_ENTRIES: usize = 16;
+
+type CacheEntry = ((TypeId, u64), Arc<dyn Any + Send + Sync>);
+
+/// the entries of [`cached`], the most recently used last
+fn cache() -> &'static Mutex<Vec<CacheEntry>> {
+    static CACHE: OnceLock<Mutex<Vec<CacheEntry>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// The value `compute` returns for n, computed once per n and type of value,
+/// such as the SCRAPE coefficients of n shares in a scalar field. Only the
+/// [`CACHE_ENTRIES`] most recently used values are kept; callers that need a
+/// value for longer hold on to the returned `Arc`.
+pub fn cached<T: Any + Send + Sync>(n: u64, compute: impl FnOnce() -> T) -> Arc<T> {
+    let key = (TypeId::of::<T>(), n);
+    let hit = |entries: &mut Vec<CacheEntry>| {
+        let position = entries.iter().position(|(k, _)| *k == key)?;
+        let entry = entries.remove(position);
+        let value = entry.1.clone();
+        entries.push(entry);
+        Some(value)
+    };
+    if let Some(value) = hit(&mut cache().lock().unwrap()) {
+        return value.downcast().unwrap();
+    }
+    // computed without the lock held, which at worst computes a value twice
+    let value: Arc<dyn Any + Send + Sync> = Arc::new(compute());
+    let mut entries = cache().lock().unwrap();
+    let value = hit(&mut entries).unwrap_or_else(|| {
+        if entries.len() == CACHE_ENTRIES {
+            entries.remove(0);
+        }
+        entries.push((key, value.clone()));
+        value
+    });
+    value.downcast().unwrap()
+}
+
 /// computes polynomial c . f(x), for some constant c and input polynomial f(x)
 pub fn poly_eval_mult_c<F: PrimeField>(f: &DensePolynomial<F>, c: &F) -> DensePolynomial<F> {
     if f.coeffs.is_empty() {
         return f.clone();
     }
 
-    DensePolynomial { coeffs: f.coeffs.iter().map(|a| a.clone() * c.clone()).collect() }
+    DensePolynomial { coeffs: f.coeffs.iter().map(|a| *a * *c).collect() }
 }
 
 pub fn digest_sha256(data: &[&[u8]]) -> [u8; 32] {
@@ -71,6 +142,62 @@ pub fn serialize<T: CanonicalSerialize>(t: &T) -> Vec<u8> {
     buf
 }
 
-pub fn deserialize<T: CanonicalDeserialize>(buf: &[u8]) -> T {
-    T::deserialize_compressed(buf).unwrap()
-}
\ No newline at end of file
+/// `None` if `buf` does not encode a `T`, e.g. a decrypted share gone wrong
+pub fn deserialize<T: CanonicalDeserialize>(buf: &[u8]) -> Option<T> {
+    T::deserialize_compressed(buf).ok()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// secrets below the modulus round-trip, all others are rejected
+    fn check_secret_encoding<F: PrimeField>() {
+        let modulus: [u8; 32] = F::MODULUS.to_bytes_be().try_into().unwrap();
+        let mut below_modulus = modulus;
+        below_modulus[31] -= 1;
+        let mut secrets = vec![[0u8; 32], [0xffu8; 32], modulus, below_modulus];
+        let mut rng = rand::thread_rng();
+        secrets.extend((0..1000).map(|_| rng.r#gen::<[u8; 32]>()));
+
+        for secret in &secrets {
+            match secret_to_field::<F>(secret) {
+                Ok(x) => {
+                    assert!(*secret < modulus);
+                    assert_eq!(field_to_secret_bytes(&x), *secret);
+                }
+                Err(PVSSError::SecretOutOfRange) => assert!(*secret >= modulus),
+                Err(err) => panic!("unexpected error {}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        struct Marker;
+        let computed = |n: u64| {
+            let mut ran = false;
+            cached(n, || {
+                ran = true;
+                Marker
+            });
+            ran
+        };
+        assert!(computed(0));
+        for n in 1..=CACHE_ENTRIES as u64 {
+            computed(n);
+        }
+        assert!(cache().lock().unwrap().len() <= CACHE_ENTRIES);
+        // the least recently used value was dropped
+        assert!(computed(0));
+    }
+
+    #[test]
+    fn test_secret_encoding() {
+        check_secret_encoding::<ark_bls12_381::Fr>();
+        check_secret_encoding::<ark_secp256k1::Fr>();
+        #[cfg(feature = "ristretto")]
+        check_secret_encoding::<ark_ed25519::Fr>();
+    }
+}
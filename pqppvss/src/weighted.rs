@@ -0,0 +1,376 @@
+//! Weighted PVSS over Pedersen commitments, for receivers with unequal
+//! stake.
+//!
+//! Receiver i of weight w_i holds a block of w_i consecutive evaluation
+//! points of the same polynomials as `prot_pvss_pedcom_ibe`, with one
+//! commitment per point. The threshold counts points: the secret polynomial
+//! has degree `threshold`, so receivers of total weight more than
+//! `threshold` reconstruct.
+//!
+//! The 2·w_i scalars of a receiver (s and r per point) are packed three to a
+//! 96-byte IBE plaintext, so it gets ⌈2·w_i / 3⌉ ciphertexts, each to its own
+//! identity.
+
+use rand::Rng;
+use rust_bindings::IbeScheme;
+use std::ops::Range;
+
+use crate::ibe::*;
+use crate::pedcom::*;
+use crate::prot_pvss_pedcom_ibe::DecryptedShare;
+use crate::multipoint::EvaluationPoints;
+use crate::utils;
+
+/// protocol label of the receiver identities
+pub const LABEL: &[u8] = b"pqppvss/weighted";
+
+/// The weighted counterpart of `(t, n)`: the degree of the sharing, and the
+/// weight of every receiver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedAccess {
+    pub threshold: u64,
+    pub weights: Vec<u64>,
+}
+
+impl WeightedAccess {
+    /// Fails with [`PVSSError::InvalidThreshold`] unless the threshold is
+    /// below the total weight.
+    pub fn new(threshold: u64, weights: Vec<u64>) -> Result<Self, PVSSError> {
+        let access = WeightedAccess { threshold, weights };
+        check_access((threshold, access.total_weight()))?;
+        Ok(access)
+    }
+
+    pub fn total_weight(&self) -> u64 {
+        self.weights.iter().sum()
+    }
+
+    /// positions of the evaluation points of `receiver`, at x = position + 1
+    pub fn points(&self, receiver: usize) -> Range<usize> {
+        let start: u64 = self.weights[..receiver].iter().sum();
+        start as usize..(start + self.weights[receiver]) as usize
+    }
+
+    /// positions of the ciphertexts of `receiver`
+    pub fn ciphertexts(&self, receiver: usize) -> Range<usize> {
+        let start: usize = self.weights[..receiver].iter().map(|&w| ciphertexts_for(w)).sum();
+        start..start + ciphertexts_for(self.weights[receiver])
+    }
+
+    fn total_ciphertexts(&self) -> usize {
+        self.weights.iter().map(|&w| ciphertexts_for(w)).sum()
+    }
+}
+
+fn ciphertexts_for(weight: u64) -> usize {
+    plaintexts_for(2 * weight as usize)
+}
+
+pub struct WeightedSharing<I: IbeScheme, G: PedComGroup = Bls12G1> {
+    pub session_id: SessionId,
+    pub dealer_id: u64,
+    pub access: WeightedAccess,
+    /// one per evaluation point
+    pub commitments: Vec<PedComCommitment<G>>,
+    /// the ciphertexts of all receivers, in order
+    pub ciphertexts: Vec<I::Ciphertext>,
+}
+
+/// The shares of one receiver, one per point of its block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedShare<S> {
+    pub receiver: usize,
+    pub points: Vec<DecryptedShare<S>>,
+}
+
+pub fn share<I: IbeScheme, G: PedComGroup, R: Rng>(
+    pedcom_params: &PedComParams<G>,
+    pub_keys: &[&I::MasterPublicKey],
+    secret: &[u8; 32],
+    access: &WeightedAccess,
+    session_id: &SessionId,
+    dealer_id: u64,
+    rng: &mut R
+) -> Result<WeightedSharing<I, G>, PVSSError> {
+    // the fields are public, so `access` need not come from `WeightedAccess::new`
+    check_access((access.threshold, access.total_weight()))?;
+    check_len(access.weights.len() as u64, pub_keys.len())?;
+    let secret_poly = utils::sample_poly(Some(utils::secret_to_field::<G::Scalar>(secret)?), access.threshold, rng);
+    let random_poly = utils::sample_poly::<G::Scalar, _>(None, access.threshold, rng);
+
+    let points = EvaluationPoints::indices(access.total_weight());
+    let (secret_ys, random_ys) = (points.evaluate(&secret_poly), points.evaluate(&random_poly));
+    let mut commitments = Vec::new();
+    let mut ciphertexts = Vec::new();
+    for (receiver, pub_key) in pub_keys.iter().enumerate() {
+        let mut scalars = Vec::new();
+        for j in access.points(receiver) {
+            let (s, r) = (secret_ys[j], random_ys[j]);
+            commitments.push(commit(pedcom_params, s, r));
+            scalars.extend([s, r]);
+        }
+        for (msg, c) in pack_scalars(&scalars).iter().zip(access.ciphertexts(receiver)) {
+            let id = receiver_id(LABEL, session_id, dealer_id, c);
+            ciphertexts.push(ibe_encrypt::<I>(msg, pub_key, &id));
+        }
+    }
+
+    Ok(WeightedSharing {
+        session_id: *session_id,
+        dealer_id,
+        access: access.clone(),
+        commitments,
+        ciphertexts,
+    })
+}
+
+impl<I: IbeScheme, G: PedComGroup> WeightedSharing<I, G> {
+    /// identities `receiver` needs the keys of to decrypt its shares, one
+    /// per ciphertext
+    pub fn receiver_ids(&self, receiver: usize) -> Vec<[u8; 96]> {
+        self.access.ciphertexts(receiver)
+            .map(|c| receiver_id(LABEL, &self.session_id, self.dealer_id, c))
+            .collect()
+    }
+}
+
+/// The checks anybody can run on a sharing, without a receiver key: the
+/// weights, the lengths, and that the commitments lie on a polynomial of
+/// degree `threshold`.
+pub fn verify_public<I: IbeScheme, G: PedComGroup>(
+    sharing: &WeightedSharing<I, G>,
+    access: &WeightedAccess
+) -> Result<(), PVSSError> {
+    let (expected, actual) = (&access, &sharing.access);
+    if (expected.threshold, expected.total_weight()) != (actual.threshold, actual.total_weight()) {
+        return Err(PVSSError::AccessStructureMismatch {
+            expected: (expected.threshold, expected.total_weight()),
+            actual: (actual.threshold, actual.total_weight()),
+        });
+    }
+    check_len(expected.weights.len() as u64, actual.weights.len())?;
+    if let Some(receiver) = (0..expected.weights.len()).find(|&i| expected.weights[i] != actual.weights[i]) {
+        return Err(PVSSError::WeightMismatch { receiver });
+    }
+    check_len(access.total_weight(), sharing.commitments.len())?;
+    check_len(access.total_ciphertexts() as u64, sharing.ciphertexts.len())?;
+    if !degree_check_transcript::<G>(&sharing.commitments, access.threshold, &sharing.session_id) {
+        return Err(PVSSError::DegreeCheckFailed);
+    }
+    Ok(())
+}
+
+/// Decrypts the shares of `receiver` and verifies them against the sharing;
+/// `sks[k]` is the key of its k-th identity in [`WeightedSharing::receiver_ids`].
+pub fn decrypt_share<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &WeightedSharing<I, G>,
+    access: &WeightedAccess,
+    receiver: usize,
+    sks: &[I::SecretKeyID]
+) -> Result<WeightedShare<G::Scalar>, PVSSError> {
+    verify_public(sharing, access)?;
+    let n = access.weights.len() as u64;
+    if receiver as u64 >= n {
+        return Err(PVSSError::IndexOutOfRange { index: receiver, n });
+    }
+    let ciphertexts = access.ciphertexts(receiver);
+    check_len(ciphertexts.len() as u64, sks.len())?;
+
+    let plaintexts: Vec<[u8; 96]> = ciphertexts.zip(sks)
+        .map(|(c, sk)| ibe_decrypt::<I>(&sharing.ciphertexts[c], sk))
+        .collect();
+    let block = access.points(receiver);
+    let scalars: Vec<G::Scalar> = unpack_scalars(&plaintexts, 2 * block.len())
+        .map_err(|k| PVSSError::MalformedShare { index: block.start + k / 2 })?;
+    let points = block
+        .zip(scalars.chunks(2))
+        .map(|(index, pair)| {
+            let point = DecryptedShare { index, s: pair[0], r: pair[1] };
+            check_point(pedcom_params, sharing, &point)?;
+            Ok(point)
+        })
+        .collect::<Result<_, PVSSError>>()?;
+    Ok(WeightedShare { receiver, points })
+}
+
+pub fn verify<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &WeightedSharing<I, G>,
+    access: &WeightedAccess,
+    receiver: usize,
+    sks: &[I::SecretKeyID]
+) -> Result<(), PVSSError> {
+    decrypt_share(pedcom_params, sharing, access, receiver, sks).map(|_| ())
+}
+
+fn check_point<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &WeightedSharing<I, G>,
+    point: &DecryptedShare<G::Scalar>
+) -> Result<(), PVSSError> {
+    let index = point.index;
+    if sharing.commitments[index] != commit(pedcom_params, point.s, point.r) {
+        return Err(PVSSError::CommitmentMismatch { index });
+    }
+    Ok(())
+}
+
+fn check_block<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &WeightedSharing<I, G>,
+    share: &WeightedShare<G::Scalar>
+) -> Result<(), PVSSError> {
+    let access = &sharing.access;
+    let (receiver, n) = (share.receiver, access.weights.len() as u64);
+    if receiver as u64 >= n {
+        return Err(PVSSError::IndexOutOfRange { index: receiver, n });
+    }
+    let block = access.points(receiver);
+    check_len(block.len() as u64, share.points.len())?;
+    for (index, point) in block.zip(&share.points) {
+        if point.index != index {
+            return Err(PVSSError::WeightMismatch { receiver });
+        }
+        check_point(pedcom_params, sharing, point)?;
+    }
+    Ok(())
+}
+
+/// Recovers the 32-byte secret from the published shares of receivers of
+/// total weight more than the threshold. A receiver counts only with the
+/// whole block of its points, each checked against its commitment; invalid
+/// and repeated blocks are skipped.
+pub fn reconstruct<I: IbeScheme, G: PedComGroup>(
+    pedcom_params: &PedComParams<G>,
+    sharing: &WeightedSharing<I, G>,
+    shares: &[WeightedShare<G::Scalar>]
+) -> Result<[u8; 32], PVSSError> {
+    let access = &sharing.access;
+    check_len(access.total_weight(), sharing.commitments.len())?;
+    if !degree_check_transcript::<G>(&sharing.commitments, access.threshold, &sharing.session_id) {
+        return Err(PVSSError::DegreeCheckFailed);
+    }
+    let needed = access.threshold + 1;
+    let mut seen = std::collections::HashSet::new();
+    let mut points = Vec::new();
+    for share in shares {
+        if (points.len() as u64) >= needed {
+            break;
+        }
+        if seen.contains(&share.receiver) || check_block(pedcom_params, sharing, share).is_err() {
+            continue;
+        }
+        seen.insert(share.receiver);
+        points.extend(share.points.iter().map(|point| (G::Scalar::from(point.index as u64 + 1), point.s)));
+    }
+    if (points.len() as u64) < needed {
+        return Err(PVSSError::NotEnoughShares { needed, got: points.len() });
+    }
+    points.truncate(needed as usize);
+    Ok(utils::field_to_secret_bytes(&utils::lagrange_interpolate_at_zero(&points)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ibe::{self, TestIbe};
+    use rand::thread_rng;
+
+    type Keys = Vec<<TestIbe as IbeScheme>::MasterKeypair>;
+
+    const SESSION: SessionId = [4u8; 32];
+    const DEALER: u64 = 7;
+
+    fn open_all(params: &PedComParams, sharing: &WeightedSharing<TestIbe>, keys: &Keys) -> Vec<WeightedShare<ark_bls12_381::Fr>> {
+        (0..keys.len())
+            .map(|i| {
+                let sks: Vec<_> = sharing.receiver_ids(i).iter()
+                    .map(|id| ibe::ibe_extract_id_secret_key::<TestIbe>(id, &keys[i]))
+                    .collect();
+                decrypt_share(params, sharing, &sharing.access, i, &sks).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_sharing() {
+        let params = setup::<Bls12G1>();
+        let access = WeightedAccess::new(5, vec![1, 3, 2, 5, 1]).unwrap();
+        let keys: Keys = (0..5).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let secret = [0x33u8; 32];
+        let sharing = share::<TestIbe, _, _>(&params, &pub_keys, &secret, &access, &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        assert_eq!(sharing.commitments.len(), 12);
+        // 1 + 2 + 2 + 4 + 1 ciphertexts instead of one per point
+        assert_eq!(sharing.ciphertexts.len(), 10);
+        assert_eq!(access.ciphertexts(3), 5..9);
+        verify_public(&sharing, &access).unwrap();
+
+        let shares = open_all(&params, &sharing, &keys);
+        assert_eq!(shares[3].points.len(), 5);
+        // weight 5 + 1
+        assert_eq!(reconstruct(&params, &sharing, &[shares[3].clone(), shares[0].clone()]).unwrap(), secret);
+        // weight 3 + 2 + 1
+        assert_eq!(reconstruct(&params, &sharing, &shares[1..3].iter().chain(&shares[4..]).cloned().collect::<Vec<_>>()).unwrap(), secret);
+        // weight 3 + 2
+        assert!(matches!(reconstruct(&params, &sharing, &shares[1..3]), Err(PVSSError::NotEnoughShares { needed: 6, got: 5 })));
+
+        // repeated, partial, forged and out-of-range blocks do not count
+        assert!(matches!(reconstruct(&params, &sharing, &[shares[3].clone(), shares[3].clone()]),
+            Err(PVSSError::NotEnoughShares { needed: 6, got: 5 })));
+        let mut partial = shares[3].clone();
+        partial.points.pop();
+        assert!(matches!(reconstruct(&params, &sharing, &[partial.clone(), shares[0].clone()]),
+            Err(PVSSError::NotEnoughShares { needed: 6, got: 1 })));
+        let mut forged = shares[3].clone();
+        forged.points[2].s += ark_bls12_381::Fr::from(1u64);
+        assert!(matches!(reconstruct(&params, &sharing, &[forged.clone(), shares[0].clone()]),
+            Err(PVSSError::NotEnoughShares { needed: 6, got: 1 })));
+        let mut out_of_range = shares[0].clone();
+        out_of_range.receiver = 5;
+
+        // ... and are skipped when valid blocks of enough weight are there
+        let mixed = [forged, partial, out_of_range, shares[1].clone(), shares[1].clone(), shares[2].clone(), shares[4].clone()];
+        assert_eq!(reconstruct(&params, &sharing, &mixed).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_weighted_rejects_bad_access() {
+        let params = setup::<Bls12G1>();
+        assert!(matches!(WeightedAccess::new(4, vec![1, 2, 1]), Err(PVSSError::InvalidThreshold { t: 4, n: 4 })));
+        assert!(matches!(WeightedAccess::new(0, vec![]), Err(PVSSError::InvalidThreshold { t: 0, n: 0 })));
+
+        let keys: Keys = (0..3).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        // built through the public fields, past the check in `new`
+        let too_high = WeightedAccess { threshold: 5, weights: vec![1, 2, 1] };
+        assert!(matches!(share::<TestIbe, _, _>(&params, &pub_keys, &[1u8; 32], &too_high, &SESSION, DEALER, &mut thread_rng()),
+            Err(PVSSError::InvalidThreshold { t: 5, n: 4 })));
+        let access = WeightedAccess::new(2, vec![1, 2, 1]).unwrap();
+        assert!(matches!(share::<TestIbe, _, _>(&params, &pub_keys[..2], &[1u8; 32], &access, &SESSION, DEALER, &mut thread_rng()),
+            Err(PVSSError::LengthMismatch { expected: 3, actual: 2 })));
+    }
+
+    #[test]
+    fn test_weighted_access_mismatch() {
+        let params = setup::<Bls12G1>();
+        let access = WeightedAccess::new(2, vec![2, 1, 1]).unwrap();
+        let keys: Keys = (0..3).map(|_| ibe::ibe_keygen::<TestIbe>()).collect();
+        let pub_keys: Vec<_> = keys.iter().map(TestIbe::master_pk).collect();
+        let sharing = share::<TestIbe, _, _>(&params, &pub_keys, &[1u8; 32], &access, &SESSION, DEALER, &mut thread_rng()).unwrap();
+
+        assert!(matches!(verify_public(&sharing, &WeightedAccess::new(2, vec![1, 2, 1]).unwrap()),
+            Err(PVSSError::WeightMismatch { receiver: 0 })));
+        assert!(matches!(verify_public(&sharing, &WeightedAccess::new(1, vec![2, 1, 1]).unwrap()),
+            Err(PVSSError::AccessStructureMismatch { expected: (1, 4), actual: (2, 4) })));
+
+        // a dealer who shares a polynomial of higher degree
+        let mut cheating = share::<TestIbe, _, _>(&params, &pub_keys, &[1u8; 32], &access, &SESSION, DEALER, &mut thread_rng()).unwrap();
+        cheating.commitments[3] = sharing.commitments[3];
+        assert!(matches!(verify_public(&cheating, &access), Err(PVSSError::DegreeCheckFailed)));
+        let sks: Vec<_> = cheating.receiver_ids(0).iter().map(|id| ibe::ibe_extract_id_secret_key::<TestIbe>(id, &keys[0])).collect();
+        assert!(verify(&params, &cheating, &access, 0, &sks).is_err());
+    }
+}
@@ -5,7 +5,7 @@ use curve25519_dalek::{RistrettoPoint, Scalar};
 use group::Group;
 use rand::{thread_rng};
 use rand_core::OsRng;
-use rust_bindings::{IbeMasterKeypair};
+use rust_bindings::{IbeMasterKeypair, LatticeIbe};
 use private_polling::crypto::ibe_encryption::{ibe_decrypt, ibe_encrypt, ibe_extract_id_secret_key};
 use private_polling::crypto::interpolate::interpolate_scalar;
 use private_polling::crypto::nizk_commit_or::{prove_nizk_pedersen_or_relation_one, prove_nizk_pedersen_or_relation_zero, verify_nizk_pedersen_or_relation, ZkInstancePedersenOr, ZkWitnessPedersenOr};
@@ -16,7 +16,7 @@ use private_polling::crypto::vote::Vote;
 use private_polling::serde_types::compute_ibe_identity;
 
 fn create_vote(poll_answer_bit_vector: &Vec<u16>, num_nodes: u32, threshold: u32,
-               server_key_pairs: &Vec<IbeMasterKeypair>, server_identities: &Vec<Vec<u8>>) -> Vote {
+               server_key_pairs: &Vec<IbeMasterKeypair>, server_identities: &Vec<Vec<u8>>) -> Vote<LatticeIbe> {
     let pedersen_gens = PedersenGens::default();
     let choice_selected_index = poll_answer_bit_vector.iter().position(|x| *x == 1).unwrap();
     let comm_shares = poll_answer_bit_vector
@@ -58,10 +58,10 @@ fn create_vote(poll_answer_bit_vector: &Vec<u16>, num_nodes: u32, threshold: u32
 
             let x_share_server_j = x_shares[j as usize];
             let r_share_server_j = r_shares[j as usize];
-            let x_share_enc = ibe_encrypt(&x_share_server_j,
+            let x_share_enc = ibe_encrypt::<LatticeIbe>(&x_share_server_j,
                                           server_key_pairs[j as usize].master_pk(),
                                           &server_identities[j as usize]);
-            let r_share_enc = ibe_encrypt(&r_share_server_j,
+            let r_share_enc = ibe_encrypt::<LatticeIbe>(&r_share_server_j,
                                           server_key_pairs[j as usize].master_pk(),
                                           &server_identities[j as usize]);
             server_enc_shares.push((x_share_enc, r_share_enc));
@@ -111,7 +111,7 @@ fn compute_vote_size(num_nodes: u32, total_choices: u32){
     println!("Vote size: {} KB, servers: {}, poll_choices: {}", (vote_size_bytes as f64)/(1024.0), num_nodes, total_choices);
 }
 
-fn verify_vote_sc(vote: &Vote){
+fn verify_vote_sc(vote: &Vote<LatticeIbe>){
     let pedersen_gens = PedersenGens::default();
     // 1. verify that sum(comm_i) commit to 1
     let mut sum_commitment = RistrettoPoint::identity();
@@ -142,7 +142,7 @@ fn verify_vote_sc(vote: &Vote){
     }
 }
 
-fn verify_vote_server(vote: &Vote, num_nodes: u32, threshold: u32, server_key_pairs: &Vec<IbeMasterKeypair>, server_identities: &Vec<Vec<u8>>){
+fn verify_vote_server(vote: &Vote<LatticeIbe>, num_nodes: u32, threshold: u32, server_key_pairs: &Vec<IbeMasterKeypair>, server_identities: &Vec<Vec<u8>>){
     let pedersen_gens = PedersenGens::default();
     let total_choices = vote.commited_shares.len();
     // Server share verification
@@ -157,7 +157,7 @@ fn verify_vote_server(vote: &Vote, num_nodes: u32, threshold: u32, server_key_pa
     }
 
     // decrypt shares + verification
-    let sk_id = ibe_extract_id_secret_key(&server_identities[0], &server_key_pairs[0]);
+    let sk_id = ibe_extract_id_secret_key::<LatticeIbe>(&server_identities[0], &server_key_pairs[0]);
     let mut serverj_xr_shares = Vec::new();
     for i in 0..total_choices{
         let comm_shares = vote.commited_shares[i].clone();
@@ -165,8 +165,8 @@ fn verify_vote_server(vote: &Vote, num_nodes: u32, threshold: u32, server_key_pa
         let server_j_index  = 0;
         let x_j_share_enc = &vote.encrypted_shares[i*total_choices + server_j_index].0;
         let r_j_share_enc = &vote.encrypted_shares[i*total_choices + server_j_index].1;
-        let x_j_share = ibe_decrypt(&x_j_share_enc, &sk_id);
-        let r_j_share = ibe_decrypt(&r_j_share_enc, &sk_id);
+        let x_j_share = ibe_decrypt::<LatticeIbe>(&x_j_share_enc, &sk_id);
+        let r_j_share = ibe_decrypt::<LatticeIbe>(&r_j_share_enc, &sk_id);
         serverj_xr_shares.push((x_j_share, r_j_share));
 
         let com = pedersen_gens.commit(x_j_share, r_j_share);
@@ -1,6 +1,12 @@
 use curve25519_dalek::{Scalar};
-use rust_bindings::lattice_ibe_ffi::{MasterPublicKey};
-use rust_bindings::{decrypt, encrypt, IbeCiphertext, IbeMasterKeypair, IbeSecretKeyID};
+use rust_bindings::IbeScheme;
+
+/// IBE backend of the unit tests: the in-memory mock with the `mock-ibe`
+/// feature, the lattice IBE otherwise.
+#[cfg(all(test, feature = "mock-ibe"))]
+pub type TestIbe = rust_bindings::mock::MockIbe;
+#[cfg(all(test, not(feature = "mock-ibe")))]
+pub type TestIbe = rust_bindings::LatticeIbe;
 
 /// maps a 32-byte block <--> N0-length bit vector
 pub mod bitcodec {
@@ -87,38 +93,37 @@ pub mod bitcodec {
     }
 }
 
-pub fn ibe_extract_id_secret_key(id: &Vec<u8>, mkp: &IbeMasterKeypair) -> IbeSecretKeyID {
+pub fn ibe_extract_id_secret_key<I: IbeScheme>(id: &Vec<u8>, mkp: &I::MasterKeypair) -> I::SecretKeyID {
     let id_ibe = bitcodec::convert_id_to_ibe_format(id);
-    mkp.extract_sk_id(&id_ibe)
+    I::extract(mkp, &id_ibe)
 }
 
-pub fn ibe_encrypt(input: &Scalar, master_public_key: &MasterPublicKey, id: &Vec<u8>) ->IbeCiphertext{
+pub fn ibe_encrypt<I: IbeScheme>(input: &Scalar, master_public_key: &I::MasterPublicKey, id: &Vec<u8>) -> I::Ciphertext{
     let id_ibe = bitcodec::convert_id_to_ibe_format(id);
     let input_data_ser = bitcodec::scalar_bytes_to_encryption_input(&input.to_bytes());
-    encrypt(&input_data_ser, master_public_key, &id_ibe)
+    I::encrypt(&input_data_ser, master_public_key, &id_ibe)
 }
 
-pub fn ibe_decrypt(cipher: &IbeCiphertext, sk_id: &IbeSecretKeyID) -> Scalar{
-    let result_ser = bitcodec::decryption_output_to_scalar_bytes(&decrypt(cipher, sk_id));
+pub fn ibe_decrypt<I: IbeScheme>(cipher: &I::Ciphertext, sk_id: &I::SecretKeyID) -> Scalar{
+    let result_ser = bitcodec::decryption_output_to_scalar_bytes(&I::decrypt(cipher, sk_id));
     Scalar::from_bytes_mod_order(result_ser)
 }
 
 #[cfg(test)]
 mod test {
     use rand::thread_rng;
-    use rust_bindings::IbeMasterKeypair;
     use super::*;
 
     #[test]
     fn test_encrypt_decrypt_100() {
-        let master_keypair = IbeMasterKeypair::generate();
+        let master_keypair = TestIbe::keygen();
         
         for i in 0..100{
             let id = [i as u8; 32];
-            let sk_id = ibe_extract_id_secret_key(&id.to_vec(), &master_keypair);
+            let sk_id = ibe_extract_id_secret_key::<TestIbe>(&id.to_vec(), &master_keypair);
             let msg = Scalar::random(&mut thread_rng());
-            let result = ibe_encrypt(&msg, master_keypair.master_pk(), &id.to_vec());
-            let decrypted = ibe_decrypt(&result, &sk_id);
+            let result = ibe_encrypt::<TestIbe>(&msg, TestIbe::master_pk(&master_keypair), &id.to_vec());
+            let decrypted = ibe_decrypt::<TestIbe>(&result, &sk_id);
             assert_eq!(msg, decrypted);
         }
     }
@@ -277,6 +277,43 @@ void IBE_Decrypt(long message[N0], const long C[2][N0], const CC_t * const SKid_
 }
 
 
+//==============================================================================
+// Same as IBE_Decrypt, but also reports for each coefficient the signed noise
+// left after removing the decoded codeword (m[i]*q0/2), centered in (-q0/2, q0/2].
+// A coefficient decrypts correctly as long as |noise[i]| < q0/4.
+//==============================================================================
+void IBE_Decrypt_Diagnostic(long message[N0], long noise[N0], const long C[2][N0], const CC_t * const SKid_FFT)
+{
+    unsigned int i;
+    long v;
+    CC_t c0_FFT[N0], aux_FFT[N0];
+
+    MyIntFFT(c0_FFT, C[0]);
+
+    for(i=0; i<N0; i++)
+    {
+        aux_FFT[i] = c0_FFT[i]*SKid_FFT[i];
+    }
+
+    MyIntReverseFFT(message, aux_FFT);
+
+    for(i=0; i<N0; i++)
+    {
+        v = C[1][i] - message[i];
+        v = ((unsigned long)(v))%q0;
+        message[i] = (v + (q0>>2) )/(q0>>1);
+        message[i] %= 2;
+
+        noise[i] = v - message[i]*(q0>>1);
+        if(noise[i] > (q0>>1))
+        {
+            noise[i] -= q0;
+        }
+    }
+
+}
+
+
 
 //==============================================================================
 //==============================================================================
@@ -16,6 +16,7 @@ void IBE_Extract(ZZX SK_id[2], vec_ZZ id, const MSK_Data * const MSKD);
 unsigned long IBE_Verify_Key(const ZZX SK_id[2], const vec_ZZ id, const MSK_Data * const MSKD);
 void IBE_Encrypt(long C[2][N0], const long m[N0], const long id0[N0], const MPK_Data * const MPKD);
 void IBE_Decrypt(long message[N0], const long C[2][N0], const CC_t * const SKid_FFT);
+void IBE_Decrypt_Diagnostic(long message[N0], long noise[N0], const long C[2][N0], const CC_t * const SKid_FFT);
 void Extract_Bench(const unsigned int nb_extr, MSK_Data * MSKD);
 void Encrypt_Bench(const unsigned int nb_cryp, MPK_Data * MPKD, MSK_Data * MSKD);
 void Extract_Test(const unsigned int nb_extr, MSK_Data * MSKD);
@@ -4,6 +4,8 @@
 
 #include <array>
 
+static_assert(lattice_ibe_ffi::Q0 == q0, "Q0 must match the modulus in params.h");
+
 namespace lattice_ibe_ffi {
 
 class MasterPublicKey {
@@ -21,6 +23,7 @@ public:
 class SkIdFFT {
 public:
     CC_t inner[N0];
+    long coeffs[N0];
 };
 
 MasterKeypair::MasterKeypair(std::unique_ptr<MasterPublicKey>&& p,
@@ -31,6 +34,12 @@ MasterKeypair::MasterKeypair(MasterKeypair&&) noexcept            = default;
 MasterKeypair& MasterKeypair::operator=(MasterKeypair&&) noexcept = default;
 MasterKeypair::~MasterKeypair()                             = default;
 
+PublicKeyBox::PublicKeyBox() noexcept
+    : pk(std::make_unique<MasterPublicKey>()) {};
+PublicKeyBox::PublicKeyBox(PublicKeyBox&&) noexcept            = default;
+PublicKeyBox& PublicKeyBox::operator=(PublicKeyBox&&) noexcept = default;
+PublicKeyBox::~PublicKeyBox()                             = default;
+
 SecretKeyID::SecretKeyID(SecretKeyID&&) noexcept            = default;
 SecretKeyID::SecretKeyID() noexcept
     : sk_id_fft(std::make_unique<SkIdFFT>()) {};
@@ -51,6 +60,7 @@ std::unique_ptr<MasterKeypair> keygen()
 
 const MasterPublicKey* keypair_pk(const MasterKeypair& kp) { return kp.pk.get(); }
 const MasterSecretKey* keypair_sk(const MasterKeypair& kp) { return kp.sk.get(); }
+const MasterPublicKey* publickey_pk(const PublicKeyBox& b) { return b.pk.get(); }
 
 // ---------- encrypt --------------------------------------------------
 std::unique_ptr<Ciphertext> ibe_encrypt(const MasterPublicKey& pk,
@@ -70,6 +80,14 @@ void ibe_decrypt(const Ciphertext& ct,
     IBE_Decrypt(out_msg, ct.C, sk_id.sk_id_fft->inner);
 }
 
+void ibe_decrypt_diagnostic(const Ciphertext& ct,
+                            const SecretKeyID&  sk_id,
+                            long*             out_msg,
+                            long*             out_noise)
+{
+    IBE_Decrypt_Diagnostic(out_msg, out_noise, ct.C, sk_id.sk_id_fft->inner);
+}
+
 std::unique_ptr<SecretKeyID> ibe_extract(const long* id_raw, const MasterSecretKey&  sk){
 
     auto sk_id = std::make_unique<SecretKeyID>();
@@ -82,7 +100,107 @@ std::unique_ptr<SecretKeyID> ibe_extract(const long* id_raw, const MasterSecretK
     ZZX SK_id[2];
     IBE_Extract(SK_id, id, &sk.MSKD);
     ZZXToFFT(sk_id->sk_id_fft->inner, SK_id[1]);
+    for (unsigned i = 0; i < N0; ++i)
+        sk_id->sk_id_fft->coeffs[i] = conv<long>( coeff(SK_id[1], i) );
+    return sk_id;
+}
+
+// ---------- key verification -----------------------------------------
+// Extracted keys are GPV samples of width sigma = 2*||(g, -f)|| ~ 2*sqrt(1.36*q0/2),
+// so ||(s1, s2)|| concentrates around sigma*sqrt(2*N0); the bound leaves 50%
+// of slack, while a forger without the trapdoor cannot get anywhere close.
+static const long double SK_SQ_NORM_BOUND =
+    (1.5L * 2 * sqrtl(1.36L*q0/2)) * (1.5L * 2 * sqrtl(1.36L*q0/2)) * 2 * N0;
+
+bool ibe_verify_key(const MasterPublicKey& pk,
+                    const long*            id_raw,
+                    const SecretKeyID&     sk_id)
+{
+    const long* s2 = sk_id.sk_id_fft->coeffs;
+
+    ZZ_p::init(q1);
+    ZZ_pX phi_p, s2_p;
+    SetCoeff(phi_p, 0, 1);
+    SetCoeff(phi_p, N0, 1);
+    for (unsigned i = 0; i < N0; ++i)
+        SetCoeff(s2_p, i, conv<ZZ_p>(s2[i]));
+    ZZ_pX hs2 = (pk.MPK * s2_p) % phi_p;
+
+    long double sq_norm = 0;
+    for (unsigned i = 0; i < N0; ++i) {
+        // s1 = id - h*s2 mod q0, centered in (-q0/2, q0/2]
+        long s1 = conv<long>( rep(conv<ZZ_p>(id_raw[i]) - coeff(hs2, i)) );
+        if (s1 > (long)(q0/2))
+            s1 -= q0;
+        sq_norm += (long double) s1*s1 + (long double) s2[i]*s2[i];
+    }
+    return sq_norm <= SK_SQ_NORM_BOUND;
+}
+
+// ---------- encoding -------------------------------------------------
+// The FFTs below are taken with MyIntFFT rather than ZZXToFFT, which
+// asserts that the leading coefficient is non-zero.
+void mpk_to_raw(const MasterPublicKey& pk, long* out)
+{
+    for (unsigned i = 0; i < N0; ++i)
+        out[i] = conv<long>( rep(coeff(pk.MPK, i)) );
+}
+
+std::unique_ptr<PublicKeyBox> mpk_from_raw(const long* raw)
+{
+    auto b = std::make_unique<PublicKeyBox>();
+    ZZ_p::init(q1);
+    ZZ_pX h;
+    for (unsigned i = 0; i < N0; ++i)
+        SetCoeff(h, i, conv<ZZ_p>(raw[i]));
+
+    b->pk->MPK = h;
+    b->pk->MPKD.h = h;
+    MyIntFFT(b->pk->MPKD.h_FFT, raw);
+    return b;
+}
+
+void ciphertext_to_raw(const Ciphertext& ct, long* out)
+{
+    for (unsigned i = 0; i < N0; ++i) {
+        out[i]      = ct.C[0][i];
+        out[N0 + i] = ct.C[1][i];
+    }
+}
+
+std::unique_ptr<Ciphertext> ciphertext_from_raw(const long* raw)
+{
+    auto ct = std::make_unique<Ciphertext>();
+    for (unsigned i = 0; i < N0; ++i) {
+        ct->C[0][i] = raw[i];
+        ct->C[1][i] = raw[N0 + i];
+    }
+    return ct;
+}
+
+void sk_id_to_raw(const SecretKeyID& sk_id, long* out)
+{
+    for (unsigned i = 0; i < N0; ++i)
+        out[i] = sk_id.sk_id_fft->coeffs[i];
+}
+
+std::unique_ptr<SecretKeyID> sk_id_from_raw(const long* raw)
+{
+    auto sk_id = std::make_unique<SecretKeyID>();
+    for (unsigned i = 0; i < N0; ++i)
+        sk_id->sk_id_fft->coeffs[i] = raw[i];
+    MyIntFFT(sk_id->sk_id_fft->inner, raw);
     return sk_id;
 }
 
+// ---------- samplers -------------------------------------------------
+unsigned int sample0(unsigned long alea) { return Sample0(alea); }
+unsigned int sample1(unsigned int k)     { return Sample1(k); }
+int          sample2(unsigned int k)     { return Sample2(k); }
+int          sample3(double sigma)       { return Sample3((RR_t) sigma); }
+int          sample4(double c, double sigma)
+{
+    return Sample4((RR_t) c, (RR_t) sigma);
+}
+
 } // namespace lattice_ibe_ffi
\ No newline at end of file
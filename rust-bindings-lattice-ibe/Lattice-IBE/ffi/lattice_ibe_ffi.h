@@ -5,6 +5,7 @@
 namespace lattice_ibe_ffi {
 
 constexpr std::size_t N0 = 1024;
+constexpr std::size_t Q0 = 1 << 27;
 
 // Opaque forward declarations – Rust never sees internals.
 class MasterPublicKey;
@@ -36,9 +37,22 @@ struct SecretKeyID {
     ~SecretKeyID();
 };
 
+// A master public key that lives on its own, e.g. after decoding it from bytes.
+struct PublicKeyBox {
+    std::unique_ptr<MasterPublicKey> pk;
+
+    PublicKeyBox() noexcept;
+    PublicKeyBox(const PublicKeyBox&)            = delete;
+    PublicKeyBox& operator=(const PublicKeyBox&) = delete;
+    PublicKeyBox(PublicKeyBox&&) noexcept;
+    PublicKeyBox& operator=(PublicKeyBox&&) noexcept;
+    ~PublicKeyBox();
+};
+
 std::unique_ptr<MasterKeypair>  keygen();            // returns UniquePtr
 const MasterPublicKey* keypair_pk(const MasterKeypair& kp);
 const MasterSecretKey* keypair_sk(const MasterKeypair& kp);
+const MasterPublicKey* publickey_pk(const PublicKeyBox& b);
 
 // ----------------------------  FFI surface  ----------------------------
 
@@ -52,7 +66,46 @@ void ibe_decrypt(const Ciphertext& ct,
                  const SecretKeyID&  sk_id,
                  long*             out_msg);
 
+// Decrypt into out_msg[0..N0) and write the centered decryption noise of
+// every coefficient into out_noise[0..N0).
+void ibe_decrypt_diagnostic(const Ciphertext& ct,
+                            const SecretKeyID&  sk_id,
+                            long*             out_msg,
+                            long*             out_noise);
+
 
 std::unique_ptr<SecretKeyID> ibe_extract(const long* id_raw, const MasterSecretKey&  sk);
 
+// Checks that sk_id is a valid key for identity id_raw[0..N0) under pk,
+// i.e. that (s1, s2) with s1 = id - h*s2 mod q0 is as short as an extracted key.
+bool ibe_verify_key(const MasterPublicKey& pk,
+                    const long*            id_raw,
+                    const SecretKeyID&     sk_id);
+
+// -----------------------------  Encoding  ------------------------------
+// Raw coefficient views used by the Rust codecs, which range-check every
+// coefficient before handing it back to the *_from_raw constructors.
+
+// out[0..N0): coefficients of h in [0, Q0).
+void mpk_to_raw(const MasterPublicKey& pk, long* out);
+std::unique_ptr<PublicKeyBox> mpk_from_raw(const long* raw);
+
+// out[0..2*N0): C[0] followed by C[1].
+void ciphertext_to_raw(const Ciphertext& ct, long* out);
+std::unique_ptr<Ciphertext> ciphertext_from_raw(const long* raw);
+
+// out[0..N0): coefficients of SK_id[1].
+void sk_id_to_raw(const SecretKeyID& sk_id, long* out);
+std::unique_ptr<SecretKeyID> sk_id_from_raw(const long* raw);
+
+// -----------------------  Discrete Gaussian samplers  -----------------------
+// Thin wrappers around Sample0..Sample4 from Sampling.cc, exposed so that the
+// Rust side can test their output distributions.
+
+unsigned int sample0(unsigned long alea);
+unsigned int sample1(unsigned int k);
+int          sample2(unsigned int k);
+int          sample3(double sigma);
+int          sample4(double c, double sigma);
+
 } // namespace lattice_ibe_ffi
\ No newline at end of file
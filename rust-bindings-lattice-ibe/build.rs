@@ -1,7 +1,15 @@
+#[cfg(feature = "lattice")]
 use std::fs;
+#[cfg(feature = "lattice")]
 use autocxx_build::Builder;
+#[cfg(feature = "lattice")]
 use std::path::PathBuf;
 
+// nothing to build for the mock backend
+#[cfg(not(feature = "lattice"))]
+fn main() {}
+
+#[cfg(feature = "lattice")]
 fn main() -> miette::Result<()> {
     let cpp_dir   = PathBuf::from("./Lattice-IBE");
     let ffi_dir   = cpp_dir.join("ffi");
@@ -9,7 +17,7 @@ fn main() -> miette::Result<()> {
 
     // ①  Directories that hold headers ────────────────────────┐
     let mut build = Builder::new(
-        "src/lib.rs",
+        "src/lattice.rs",
         &[ cpp_dir.to_str().unwrap(),           //  …/Latice-IBE
             ffi_dir.to_str().unwrap() ]           //  …/Latice-IBE/ffi
     )
@@ -0,0 +1,93 @@
+//! Estimates the decryption-failure probability of the lattice IBE for the
+//! parameter set compiled into `Lattice-IBE/params.h`.
+//!
+//! Usage: `cargo run --release --bin ibe_noise -- [trials] [keys]`
+//!
+//! Every trial encrypts a uniformly random bit vector under a fresh identity of
+//! one of `keys` master keypairs and decrypts it with
+//! [`decrypt_with_diagnostics`]. The observed failures are counted, and the
+//! per-coefficient noise is fitted with a centered Gaussian to extrapolate the
+//! failure probability far below what a few thousand trials can observe.
+
+use rand::Rng;
+use rust_bindings::{decrypt_with_diagnostics, encrypt, IbeMasterKeypair, N0, Q0};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let trials: usize = args.get(1).map(|a| a.parse().expect("trials must be a number")).unwrap_or(2000);
+    let keys: usize = args.get(2).map(|a| a.parse().expect("keys must be a number")).unwrap_or(1);
+    assert!(trials > 0 && keys > 0, "trials and keys must be positive");
+
+    println!("parameters: N0 = {}, q0 = {}, threshold q0/4 = {}", N0, Q0, Q0 / 4);
+    println!("running {} encryptions under {} master key(s)", trials, keys);
+
+    let mut rng = rand::thread_rng();
+    let keypairs: Vec<IbeMasterKeypair> = (0..keys).map(|_| IbeMasterKeypair::generate()).collect();
+
+    let mut failed_ciphertexts = 0usize;
+    let mut failed_coefficients = 0usize;
+    let mut min_margin = i64::MAX;
+    let mut max_noise = 0i64;
+    // running sums of the noise, used to fit the Gaussian
+    let mut sum = 0f64;
+    let mut sum_sq = 0f64;
+
+    for trial in 0..trials {
+        let kp = &keypairs[trial % keys];
+
+        let mut id = [0i64; N0];
+        for c in id.iter_mut() { *c = rng.gen_range(0..Q0); }
+        let mut msg = [0i64; N0];
+        for c in msg.iter_mut() { *c = rng.gen_range(0..2); }
+
+        let ct = encrypt(&msg, kp.master_pk(), &id);
+        let sk_id = kp.extract_sk_id(&id);
+        let diag = decrypt_with_diagnostics(&ct, &sk_id);
+
+        let wrong = (0..N0).filter(|&i| diag.message[i] != msg[i]).count();
+        if wrong > 0 {
+            failed_ciphertexts += 1;
+            failed_coefficients += wrong;
+        }
+        min_margin = min_margin.min(diag.margin());
+        for &e in diag.noise.iter() {
+            max_noise = max_noise.max(e.abs());
+            sum += e as f64;
+            sum_sq += (e as f64) * (e as f64);
+        }
+    }
+
+    let samples = (trials * N0) as f64;
+    let mean = sum / samples;
+    let sigma = (sum_sq / samples - mean * mean).sqrt();
+    let z = (Q0 / 4) as f64 / sigma;
+    let log2_coeff = log2_gaussian_tail(z);
+    let log2_ct = log2_coeff + (N0 as f64).log2();
+
+    println!();
+    println!("observed failed ciphertexts:   {} / {}", failed_ciphertexts, trials);
+    println!("observed failed coefficients:  {} / {}", failed_coefficients, trials * N0);
+    println!("smallest margin to threshold:  {}", min_margin);
+    println!("largest |noise|:               {} ({:.2} sigma)", max_noise, max_noise as f64 / sigma);
+    println!("noise mean / std deviation:    {:.2} / {:.2}", mean, sigma);
+    println!("threshold / std deviation:     {:.2}", z);
+    println!("estimated Pr[coefficient fails] ~ 2^{:.1}", log2_coeff);
+    println!("estimated Pr[ciphertext fails]  ~ 2^{:.1}", log2_ct);
+}
+
+/// log2 of Pr[|X| > z * sigma] for a centered Gaussian X, i.e. log2(erfc(z / sqrt(2))).
+fn log2_gaussian_tail(z: f64) -> f64 {
+    let x = z / std::f64::consts::SQRT_2;
+    let ln_erfc = if x < 3.0 {
+        // Abramowitz & Stegun 7.1.26, absolute error below 1.5e-7
+        let t = 1.0 / (1.0 + 0.3275911 * x);
+        let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+        (poly * (-x * x).exp()).ln()
+    } else {
+        // asymptotic expansion, accurate in the tail where the above underflows
+        let x2 = x * x;
+        let series = 1.0 - 1.0 / (2.0 * x2) + 3.0 / (4.0 * x2 * x2) - 15.0 / (8.0 * x2 * x2 * x2);
+        -x2 - (x * std::f64::consts::PI.sqrt()).ln() + series.ln()
+    };
+    ln_erfc / std::f64::consts::LN_2
+}
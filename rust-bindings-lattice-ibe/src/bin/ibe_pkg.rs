@@ -0,0 +1,52 @@
+//! Runs the lattice IBE key authority of [`rust_bindings::pkg`].
+//!
+//! Usage: `cargo run --release --features pkg --bin ibe_pkg -- <listen> <policy> <audit log> <master pk out>`
+//!
+//! `<listen>` is either `unix:<socket path>` or `tcp:<address>`, where the TCP
+//! address must be a loopback address. `<policy>` is a file in the format of
+//! [`Policy::parse`]; decisions are appended to `<audit log>`. A fresh master
+//! keypair is generated at startup and its public key is written to
+//! `<master pk out>`, encoded as by `public_key_to_bytes`.
+
+use std::fs::OpenOptions;
+use std::net::{SocketAddr, TcpListener};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+
+use rust_bindings::pkg::{Pkg, Policy};
+use rust_bindings::{IbeScheme, LatticeIbe};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 5 {
+        eprintln!("usage: {} <unix:path | tcp:addr> <policy> <audit log> <master pk out>", args[0]);
+        std::process::exit(2);
+    }
+
+    let policy_text = std::fs::read_to_string(&args[2]).expect("cannot read policy file");
+    let policy = Policy::parse(&policy_text).expect("invalid policy file");
+    let audit = OpenOptions::new().create(true).append(true).open(&args[3]).expect("cannot open audit log");
+
+    let mut pkg = Pkg::<LatticeIbe>::new(LatticeIbe::keygen(), policy, Box::new(audit));
+    std::fs::write(&args[4], LatticeIbe::public_key_to_bytes(pkg.master_pk())).expect("cannot write master public key");
+
+    if let Some(addr) = args[1].strip_prefix("tcp:") {
+        let addr: SocketAddr = addr.parse().expect("invalid TCP address");
+        // secret keys are returned in the clear
+        assert!(addr.ip().is_loopback(), "the PKG only listens on loopback addresses");
+        let listener = TcpListener::bind(addr).expect("cannot bind TCP address");
+        println!("PKG listening on tcp:{}", listener.local_addr().unwrap());
+        pkg.serve_tcp(listener).expect("PKG stopped");
+    } else if let Some(path) = args[1].strip_prefix("unix:") {
+        #[cfg(unix)]
+        {
+            let listener = UnixListener::bind(path).expect("cannot bind Unix socket");
+            println!("PKG listening on unix:{}", path);
+            pkg.serve_unix(listener).expect("PKG stopped");
+        }
+        #[cfg(not(unix))]
+        panic!("Unix sockets are not supported on this platform: {}", path);
+    } else {
+        panic!("listen address must start with unix: or tcp:");
+    }
+}
@@ -0,0 +1,199 @@
+//! Property-based fuzzing of every byte decoder of the crate.
+//!
+//! Decoded keys and ciphertexts are handed straight to the C++ side, so for
+//! every backend the harnesses check that
+//!
+//! - input of the wrong length is rejected with `InvalidLength`,
+//! - a single coefficient outside the allowed range is rejected with
+//!   `CoefficientOutOfRange` at its index, before anything reaches C++,
+//! - arbitrary in-range input decodes, re-encodes to the same bytes, and can be
+//!   used by `encrypt` and `decrypt` (i.e. `ZZXToFFT`-style conversions and
+//!   `IBE_Decrypt`) without aborting.
+//!
+//! Under `cargo test` every property runs on a fixed number of random cases;
+//! set `PROPTEST_CASES` to fuzz longer.
+
+use std::borrow::Borrow;
+
+use proptest::prelude::*;
+
+use crate::{IbeError, IbeScheme, N0};
+
+/// encoded lengths of the objects of a backend
+struct Lengths {
+    public_key: usize,
+    secret_key: usize,
+    ciphertext: usize,
+}
+
+/// bytes of any length but `expected`, up to twice as long
+fn wrong_length(expected: usize) -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..2 * expected + 8).prop_filter("length must differ", move |b| b.len() != expected)
+}
+
+fn check_wrong_lengths<I: IbeScheme>(lengths: &Lengths, bytes: &[u8]) {
+    let invalid = |expected| Some(IbeError::InvalidLength { expected, actual: bytes.len() });
+    if bytes.len() != lengths.public_key {
+        assert_eq!(I::public_key_from_bytes(bytes).err(), invalid(lengths.public_key));
+    }
+    if bytes.len() != lengths.secret_key {
+        assert_eq!(I::secret_key_from_bytes(bytes).err(), invalid(lengths.secret_key));
+    }
+    if bytes.len() != lengths.ciphertext {
+        assert_eq!(I::ciphertext_from_bytes(bytes).err(), invalid(lengths.ciphertext));
+    }
+}
+
+/// decodes well-formed input, checks the encoding is canonical, and runs
+/// encryption and decryption with the decoded objects
+fn check_well_formed<I: IbeScheme>(pk_bytes: &[u8], sk_bytes: &[u8], ct_bytes: &[u8]) {
+    let pk = I::public_key_from_bytes(pk_bytes).expect("in-range public key rejected");
+    let sk_id = I::secret_key_from_bytes(sk_bytes).expect("in-range secret key rejected");
+    let ct = I::ciphertext_from_bytes(ct_bytes).expect("in-range ciphertext rejected");
+    assert_eq!(I::public_key_to_bytes(pk.borrow()), pk_bytes);
+    assert_eq!(I::secret_key_to_bytes(&sk_id), sk_bytes);
+    assert_eq!(I::ciphertext_to_bytes(&ct), ct_bytes);
+
+    // the output is garbage, but must consist of bits
+    let dec = I::decrypt(&ct, &sk_id);
+    assert!(dec.iter().all(|&m| m == 0 || m == 1));
+    let fresh = I::encrypt(&[1i64; N0], pk.borrow(), &[0i64; N0]);
+    assert!(I::decrypt(&fresh, &sk_id).iter().all(|&m| m == 0 || m == 1));
+}
+
+#[cfg(feature = "mock-ibe")]
+mod mock {
+    use super::*;
+    use crate::mock::{MockIbe, CIPHERTEXT_BYTES, PUBLIC_KEY_BYTES, SECRET_KEY_BYTES};
+
+    const LENGTHS: Lengths = Lengths { public_key: PUBLIC_KEY_BYTES, secret_key: SECRET_KEY_BYTES, ciphertext: CIPHERTEXT_BYTES };
+
+    proptest! {
+        #[test]
+        fn wrong_lengths_are_rejected(bytes in wrong_length(CIPHERTEXT_BYTES)) {
+            check_wrong_lengths::<MockIbe>(&LENGTHS, &bytes);
+        }
+
+        // every byte string of the right length is a valid mock object
+        #[test]
+        fn any_bytes_decode(
+            pk in prop::collection::vec(any::<u8>(), PUBLIC_KEY_BYTES),
+            sk in prop::collection::vec(any::<u8>(), SECRET_KEY_BYTES),
+            ct in prop::collection::vec(any::<u8>(), CIPHERTEXT_BYTES),
+        ) {
+            check_well_formed::<MockIbe>(&pk, &sk, &ct);
+        }
+    }
+}
+
+#[cfg(feature = "lattice")]
+mod lattice {
+    use super::*;
+    use crate::{LatticeIbe, CIPHERTEXT_BYTES, PUBLIC_KEY_BYTES, Q0, SECRET_KEY_BYTES, SK_COEFF_BOUND};
+    use std::ops::RangeInclusive;
+
+    const LENGTHS: Lengths = Lengths { public_key: PUBLIC_KEY_BYTES, secret_key: SECRET_KEY_BYTES, ciphertext: CIPHERTEXT_BYTES };
+    const PK_RANGE: RangeInclusive<i32> = 0..=(Q0 as i32 - 1);
+    const SK_RANGE: RangeInclusive<i32> = -(SK_COEFF_BOUND as i32)..=SK_COEFF_BOUND as i32;
+    const CT_RANGE: RangeInclusive<i32> = PK_RANGE;
+
+    fn coeff_bytes(coeffs: &[i32]) -> Vec<u8> {
+        coeffs.iter().flat_map(|c| c.to_le_bytes()).collect()
+    }
+
+    /// `count` coefficients drawn from `range`
+    fn coeffs(range: RangeInclusive<i32>, count: usize) -> impl Strategy<Value = Vec<i32>> {
+        prop::collection::vec(range, count)
+    }
+
+    /// an `i32` outside of `range`
+    fn outside(range: RangeInclusive<i32>) -> impl Strategy<Value = i32> {
+        let (lo, hi) = (*range.start(), *range.end());
+        prop_oneof![i32::MIN..lo, (hi + 1)..=i32::MAX]
+    }
+
+    /// `count` in-range coefficients with the one at the returned index replaced by an out-of-range value
+    fn one_out_of_range(range: RangeInclusive<i32>, count: usize) -> impl Strategy<Value = (Vec<i32>, usize)> {
+        (coeffs(range.clone(), count), 0..count, outside(range)).prop_map(|(mut c, index, bad)| {
+            c[index] = bad;
+            (c, index)
+        })
+    }
+
+    proptest! {
+        // every case round-trips through NTL, keep the default run short
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn wrong_lengths_are_rejected(bytes in wrong_length(CIPHERTEXT_BYTES)) {
+            check_wrong_lengths::<LatticeIbe>(&LENGTHS, &bytes);
+        }
+
+        #[test]
+        fn out_of_range_public_key_is_rejected((c, index) in one_out_of_range(PK_RANGE, N0)) {
+            prop_assert_eq!(LatticeIbe::public_key_from_bytes(&coeff_bytes(&c)).err(), Some(IbeError::CoefficientOutOfRange { index }));
+        }
+
+        #[test]
+        fn out_of_range_secret_key_is_rejected((c, index) in one_out_of_range(SK_RANGE, N0)) {
+            prop_assert_eq!(LatticeIbe::secret_key_from_bytes(&coeff_bytes(&c)).err(), Some(IbeError::CoefficientOutOfRange { index }));
+        }
+
+        #[test]
+        fn out_of_range_ciphertext_is_rejected((c, index) in one_out_of_range(CT_RANGE, 2 * N0)) {
+            prop_assert_eq!(LatticeIbe::ciphertext_from_bytes(&coeff_bytes(&c)).err(), Some(IbeError::CoefficientOutOfRange { index }));
+        }
+
+        #[test]
+        fn in_range_input_decodes(
+            pk in coeffs(PK_RANGE, N0),
+            sk in coeffs(SK_RANGE, N0),
+            ct in coeffs(CT_RANGE, 2 * N0),
+        ) {
+            check_well_formed::<LatticeIbe>(&coeff_bytes(&pk), &coeff_bytes(&sk), &coeff_bytes(&ct));
+        }
+
+        // coefficients at the edges of the allowed ranges
+        #[test]
+        fn extreme_input_decodes(pk_hi in any::<bool>(), sk_hi in any::<bool>(), ct_hi in any::<bool>()) {
+            let edge = |r: RangeInclusive<i32>, hi: bool| if hi { *r.end() } else { *r.start() };
+            check_well_formed::<LatticeIbe>(
+                &coeff_bytes(&[edge(PK_RANGE, pk_hi); N0]),
+                &coeff_bytes(&[edge(SK_RANGE, sk_hi); N0]),
+                &coeff_bytes(&[edge(CT_RANGE, ct_hi); 2 * N0]),
+            );
+        }
+    }
+}
+
+#[cfg(all(feature = "pkg", feature = "mock-ibe"))]
+mod pkg {
+    use super::*;
+    use crate::mock::MockIbe;
+    use crate::pkg::{Pkg, PkgClient, Policy, STATUS_OK};
+
+    fn pkg() -> Pkg<MockIbe> {
+        let mut policy = Policy::new();
+        policy.allow("alice", [7u8; 32], vec![Vec::new()]);
+        Pkg::new(MockIbe::keygen(), policy, Box::new(std::io::sink()))
+    }
+
+    proptest! {
+        // arbitrary request frames never panic the PKG nor extract a key
+        #[test]
+        fn garbage_requests_are_rejected(request in prop::collection::vec(any::<u8>(), 0..256)) {
+            let response = pkg().handle_request(&request);
+            prop_assert_eq!(response.len(), 1);
+            prop_assert_ne!(response[0], STATUS_OK);
+        }
+
+        // flipping any byte of a valid request invalidates it
+        #[test]
+        fn tampered_requests_are_rejected(identity in prop::collection::vec(any::<u8>(), 0..64), flip in any::<prop::sample::Index>(), bit in 0..8u8) {
+            let mut request = PkgClient::new("alice", [7u8; 32]).request(&identity);
+            let index = flip.index(request.len());
+            request[index] ^= 1 << bit;
+            prop_assert_ne!(pkg().handle_request(&request)[0], STATUS_OK);
+        }
+    }
+}
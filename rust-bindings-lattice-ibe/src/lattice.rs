@@ -0,0 +1,497 @@
+use autocxx::prelude::*;
+use cxx::{UniquePtr};
+use autocxx::{c_long};
+
+use sha2::{Digest, Sha256};
+
+use crate::{IbeError, IbeScheme, N0, Q0};
+
+// -------------------- 1.  Bindings  ----------------------------------
+include_cpp! {
+    #include "lattice_ibe_ffi.h"
+    safety!(unsafe)
+
+    // expose constants & opaque types
+    generate!("lattice_ibe_ffi::N0")
+    generate!("lattice_ibe_ffi::Q0")
+    generate!("lattice_ibe_ffi::MasterPublicKey")
+    generate!("lattice_ibe_ffi::MasterSecretKey")
+    generate!("lattice_ibe_ffi::Ciphertext")
+    generate!("lattice_ibe_ffi::MasterKeypair")
+    generate!("lattice_ibe_ffi::SecretKeyID")
+    generate!("lattice_ibe_ffi::PublicKeyBox")
+
+    // functions
+    generate!("lattice_ibe_ffi::keygen")
+    generate!("lattice_ibe_ffi::keypair_pk")   
+    generate!("lattice_ibe_ffi::keypair_sk")   
+    generate!("lattice_ibe_ffi::publickey_pk")
+    generate!("lattice_ibe_ffi::ibe_encrypt")
+    generate!("lattice_ibe_ffi::ibe_decrypt")
+    generate!("lattice_ibe_ffi::ibe_decrypt_diagnostic")
+    generate!("lattice_ibe_ffi::ibe_extract")
+    generate!("lattice_ibe_ffi::ibe_verify_key")
+    generate!("lattice_ibe_ffi::mpk_to_raw")
+    generate!("lattice_ibe_ffi::mpk_from_raw")
+    generate!("lattice_ibe_ffi::ciphertext_to_raw")
+    generate!("lattice_ibe_ffi::ciphertext_from_raw")
+    generate!("lattice_ibe_ffi::sk_id_to_raw")
+    generate!("lattice_ibe_ffi::sk_id_from_raw")
+    generate!("lattice_ibe_ffi::sample0")
+    generate!("lattice_ibe_ffi::sample1")
+    generate!("lattice_ibe_ffi::sample2")
+    generate!("lattice_ibe_ffi::sample3")
+    generate!("lattice_ibe_ffi::sample4")
+}
+
+pub use ffi::lattice_ibe_ffi;
+
+// the scheme-independent constants must agree with Lattice-IBE/params.h
+const _: () = assert!(lattice_ibe_ffi::N0 as usize == N0);
+const _: () = assert!(lattice_ibe_ffi::Q0 as i64 == Q0);
+
+// -------------------- 2.  Safe wrappers  -----------------------------
+pub struct IbeMasterKeypair {
+    inner: UniquePtr<lattice_ibe_ffi::MasterKeypair>,
+}
+
+pub struct IbeCiphertext {
+    pub inner: UniquePtr<lattice_ibe_ffi::Ciphertext>,
+}
+
+pub struct IbeSecretKeyID {
+    pub inner: UniquePtr<lattice_ibe_ffi::SecretKeyID>,
+}
+
+/// A master public key decoded from bytes, i.e. not owned by a keypair.
+pub struct IbePublicKey {
+    inner: UniquePtr<lattice_ibe_ffi::PublicKeyBox>,
+}
+
+impl IbePublicKey {
+    pub fn master_pk(&self) -> &lattice_ibe_ffi::MasterPublicKey {
+        let box_ref = self.inner.as_ref().expect("null public key from C++");
+        unsafe { &*lattice_ibe_ffi::publickey_pk(box_ref) }
+    }
+}
+
+impl std::borrow::Borrow<lattice_ibe_ffi::MasterPublicKey> for IbePublicKey {
+    fn borrow(&self) -> &lattice_ibe_ffi::MasterPublicKey {
+        self.master_pk()
+    }
+}
+
+impl IbeMasterKeypair {
+    pub fn generate() -> Self {
+        Self {
+            inner: lattice_ibe_ffi::keygen(),
+        }
+    }
+    // getters for &MasterPublicKey / &MasterSecretKey
+    pub fn master_pk(&self) -> &lattice_ibe_ffi::MasterPublicKey {
+        let kp_ref = self.inner.as_ref().expect("null keypair from C++");
+        unsafe { &*lattice_ibe_ffi::keypair_pk(kp_ref) }
+    }
+    pub fn master_sk(&self) -> &lattice_ibe_ffi::MasterSecretKey {
+        let kp_ref = self.inner.as_ref().expect("null keypair from C++");
+        unsafe { &*lattice_ibe_ffi::keypair_sk(kp_ref) }
+    }
+    
+    pub fn extract_sk_id(&self, id:  &[i64; N0])
+        -> IbeSecretKeyID {
+        unsafe {
+            IbeSecretKeyID {
+                inner: lattice_ibe_ffi::ibe_extract(
+                    id.as_ptr()  as *const c_long,
+                    self.master_sk()
+                )
+            }
+        }
+    }
+}
+
+pub fn encrypt(
+    msg: &[i64; N0],
+    master_pk: &lattice_ibe_ffi::MasterPublicKey,
+    id:  &[i64; N0],
+) -> IbeCiphertext {
+    unsafe {
+        IbeCiphertext {
+            inner: lattice_ibe_ffi::ibe_encrypt(
+                master_pk,
+                msg.as_ptr() as *const c_long,
+                id.as_ptr()  as *const c_long,
+            )
+        }
+    }
+}
+
+pub fn decrypt(ct: &IbeCiphertext, sk_id: &IbeSecretKeyID,) -> [i64; N0] {
+    let mut out = [0i64; N0];
+    unsafe {
+        lattice_ibe_ffi::ibe_decrypt(
+            &ct.inner,
+            &sk_id.inner,
+            out.as_mut_ptr() as *mut c_long,
+        );
+    }
+    out
+}
+
+/// Checks that `sk_id` is a valid key for `id` under `master_pk`, without the
+/// master secret key. Anybody can thus check a key revealed by its owner.
+pub fn verify_key(master_pk: &lattice_ibe_ffi::MasterPublicKey, id: &[i64; N0], sk_id: &IbeSecretKeyID) -> bool {
+    unsafe {
+        lattice_ibe_ffi::ibe_verify_key(master_pk, id.as_ptr() as *const c_long, &sk_id.inner)
+    }
+}
+
+/// Result of a diagnostic decryption: the decoded message together with the
+/// signed noise left on every coefficient once the decoded codeword is removed.
+/// A coefficient decodes correctly as long as `|noise| < Q0 / 4`.
+pub struct DecryptionDiagnostics {
+    pub message: [i64; N0],
+    pub noise: [i64; N0],
+}
+
+impl DecryptionDiagnostics {
+    /// distance of every coefficient to the decision threshold `Q0 / 4`
+    pub fn distances(&self) -> [i64; N0] {
+        let mut out = [0i64; N0];
+        for i in 0..N0 {
+            out[i] = Q0 / 4 - self.noise[i].abs();
+        }
+        out
+    }
+
+    /// smallest distance to the decision threshold over all coefficients
+    pub fn margin(&self) -> i64 {
+        self.noise.iter().map(|e| Q0 / 4 - e.abs()).min().unwrap_or(Q0 / 4)
+    }
+}
+
+pub fn decrypt_with_diagnostics(ct: &IbeCiphertext, sk_id: &IbeSecretKeyID) -> DecryptionDiagnostics {
+    let mut message = [0i64; N0];
+    let mut noise = [0i64; N0];
+    unsafe {
+        lattice_ibe_ffi::ibe_decrypt_diagnostic(
+            &ct.inner,
+            &sk_id.inner,
+            message.as_mut_ptr() as *mut c_long,
+            noise.as_mut_ptr() as *mut c_long,
+        );
+    }
+    DecryptionDiagnostics { message, noise }
+}
+
+// -------------------- 3.  Encoding  ----------------------------------
+// Every coefficient is stored as a little-endian 32-bit integer. Decoding
+// range-checks all of them before anything reaches the C++ side.
+
+/// secret key coefficients are GPV samples of width ~2^14.2; anything beyond
+/// this bound is rejected, which also keeps `IBE_Decrypt` clear of overflows.
+pub const SK_COEFF_BOUND: i64 = 1 << 20;
+
+pub const PUBLIC_KEY_BYTES: usize = 4 * N0;
+pub const SECRET_KEY_BYTES: usize = 4 * N0;
+pub const CIPHERTEXT_BYTES: usize = 8 * N0;
+
+fn coeffs_to_bytes(coeffs: &[i64]) -> Vec<u8> {
+    coeffs.iter().flat_map(|&c| (c as i32).to_le_bytes()).collect()
+}
+
+fn coeffs_from_bytes(bytes: &[u8], expected: usize, range: std::ops::Range<i64>) -> Result<Vec<i64>, IbeError> {
+    if bytes.len() != expected {
+        return Err(IbeError::InvalidLength { expected, actual: bytes.len() });
+    }
+    bytes
+        .chunks_exact(4)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let c = i32::from_le_bytes(chunk.try_into().unwrap()) as i64;
+            if range.contains(&c) { Ok(c) } else { Err(IbeError::CoefficientOutOfRange { index }) }
+        })
+        .collect()
+}
+
+pub fn public_key_to_bytes(mpk: &lattice_ibe_ffi::MasterPublicKey) -> Vec<u8> {
+    let mut raw = [0i64; N0];
+    unsafe {
+        lattice_ibe_ffi::mpk_to_raw(mpk, raw.as_mut_ptr() as *mut c_long);
+    }
+    coeffs_to_bytes(&raw)
+}
+
+pub fn public_key_from_bytes(bytes: &[u8]) -> Result<IbePublicKey, IbeError> {
+    let raw = coeffs_from_bytes(bytes, PUBLIC_KEY_BYTES, 0..Q0)?;
+    unsafe {
+        Ok(IbePublicKey {
+            inner: lattice_ibe_ffi::mpk_from_raw(raw.as_ptr() as *const c_long),
+        })
+    }
+}
+
+pub fn secret_key_to_bytes(sk_id: &IbeSecretKeyID) -> Vec<u8> {
+    let mut raw = [0i64; N0];
+    unsafe {
+        lattice_ibe_ffi::sk_id_to_raw(&sk_id.inner, raw.as_mut_ptr() as *mut c_long);
+    }
+    coeffs_to_bytes(&raw)
+}
+
+pub fn secret_key_from_bytes(bytes: &[u8]) -> Result<IbeSecretKeyID, IbeError> {
+    let raw = coeffs_from_bytes(bytes, SECRET_KEY_BYTES, -SK_COEFF_BOUND..SK_COEFF_BOUND + 1)?;
+    unsafe {
+        Ok(IbeSecretKeyID {
+            inner: lattice_ibe_ffi::sk_id_from_raw(raw.as_ptr() as *const c_long),
+        })
+    }
+}
+
+/// Ciphertext coefficients are reduced into `[0, Q0)`, which does not change
+/// the decryption and makes the encoding canonical.
+pub fn ciphertext_to_bytes(ct: &IbeCiphertext) -> Vec<u8> {
+    let mut raw = [0i64; 2 * N0];
+    unsafe {
+        lattice_ibe_ffi::ciphertext_to_raw(&ct.inner, raw.as_mut_ptr() as *mut c_long);
+    }
+    let reduced: Vec<i64> = raw.iter().map(|c| c.rem_euclid(Q0)).collect();
+    coeffs_to_bytes(&reduced)
+}
+
+pub fn ciphertext_from_bytes(bytes: &[u8]) -> Result<IbeCiphertext, IbeError> {
+    let raw = coeffs_from_bytes(bytes, CIPHERTEXT_BYTES, 0..Q0)?;
+    unsafe {
+        Ok(IbeCiphertext {
+            inner: lattice_ibe_ffi::ciphertext_from_raw(raw.as_ptr() as *const c_long),
+        })
+    }
+}
+
+// -------------------- 4.  IbeScheme  ---------------------------------
+/// Maps an identity to `N0` uniform coefficients in `[0, Q0)`.
+///
+/// The scheme needs identities that look random mod `Q0`: for a short identity
+/// `t` (e.g. one byte per coefficient), `(t, 0)` is a valid short key anybody
+/// can write down, and `C1 = r*t + e2 + m*q0/2` decrypts without any key.
+pub fn hash_identity(id: &[i64; N0]) -> [i64; N0] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"lattice-ibe identity");
+    for c in id {
+        hasher.update(c.to_le_bytes());
+    }
+    let seed: [u8; 32] = hasher.finalize().into();
+
+    // Q0 is a power of two, so masking 32-bit words keeps them uniform
+    let mut out = [0i64; N0];
+    for (block, chunk) in out.chunks_mut(8).enumerate() {
+        let words: [u8; 32] = Sha256::new().chain_update(seed).chain_update((block as u32).to_le_bytes()).finalize().into();
+        for (c, w) in chunk.iter_mut().zip(words.chunks_exact(4)) {
+            *c = u32::from_le_bytes(w.try_into().unwrap()) as i64 & (Q0 - 1);
+        }
+    }
+    out
+}
+
+/// The NTRU lattice IBE of Ducas, Lyubashevsky and Prest. Identities are
+/// passed through [`hash_identity`].
+pub struct LatticeIbe;
+
+impl IbeScheme for LatticeIbe {
+    type MasterKeypair = IbeMasterKeypair;
+    type MasterPublicKey = lattice_ibe_ffi::MasterPublicKey;
+    type PublicKey = IbePublicKey;
+    type SecretKeyID = IbeSecretKeyID;
+    type Ciphertext = IbeCiphertext;
+
+    fn keygen() -> IbeMasterKeypair {
+        IbeMasterKeypair::generate()
+    }
+
+    fn master_pk(kp: &IbeMasterKeypair) -> &lattice_ibe_ffi::MasterPublicKey {
+        kp.master_pk()
+    }
+
+    fn extract(kp: &IbeMasterKeypair, id: &[i64; N0]) -> IbeSecretKeyID {
+        kp.extract_sk_id(&hash_identity(id))
+    }
+
+    fn encrypt(msg: &[i64; N0], master_pk: &lattice_ibe_ffi::MasterPublicKey, id: &[i64; N0]) -> IbeCiphertext {
+        encrypt(msg, master_pk, &hash_identity(id))
+    }
+
+    fn decrypt(ct: &IbeCiphertext, sk_id: &IbeSecretKeyID) -> [i64; N0] {
+        decrypt(ct, sk_id)
+    }
+
+    fn verify_key(master_pk: &lattice_ibe_ffi::MasterPublicKey, id: &[i64; N0], sk_id: &IbeSecretKeyID) -> bool {
+        verify_key(master_pk, &hash_identity(id), sk_id)
+    }
+
+    fn public_key_to_bytes(master_pk: &lattice_ibe_ffi::MasterPublicKey) -> Vec<u8> {
+        public_key_to_bytes(master_pk)
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Result<IbePublicKey, IbeError> {
+        public_key_from_bytes(bytes)
+    }
+
+    fn secret_key_to_bytes(sk_id: &IbeSecretKeyID) -> Vec<u8> {
+        secret_key_to_bytes(sk_id)
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Result<IbeSecretKeyID, IbeError> {
+        secret_key_from_bytes(bytes)
+    }
+
+    fn ciphertext_to_bytes(ct: &IbeCiphertext) -> Vec<u8> {
+        ciphertext_to_bytes(ct)
+    }
+
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<IbeCiphertext, IbeError> {
+        ciphertext_from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn roundtrip() {
+        let kp = IbeMasterKeypair::generate();
+
+        let mut msg = [0i64; N0];
+        msg[0] = 1;
+        msg[5] = 1;
+
+        let mut id  = [0i64; N0];
+        for i in 0..N0 { id[i] = (i % 3 == 0) as i64; }
+
+        let ct  = encrypt(&msg, kp.master_pk(), &id);
+        
+        let sk_id = kp.extract_sk_id(&id);
+        let dec = decrypt(&ct, &sk_id);
+
+        assert_eq!(msg[..], dec[..]);
+    }
+
+    #[test]
+    fn diagnostics_match_decrypt() {
+        let kp = IbeMasterKeypair::generate();
+
+        let mut msg = [0i64; N0];
+        for i in 0..N0 { msg[i] = (i % 5 == 1) as i64; }
+
+        let mut id  = [0i64; N0];
+        for i in 0..N0 { id[i] = (i % 7) as i64; }
+
+        let ct = encrypt(&msg, kp.master_pk(), &id);
+        let sk_id = kp.extract_sk_id(&id);
+
+        let diag = decrypt_with_diagnostics(&ct, &sk_id);
+        assert_eq!(diag.message[..], decrypt(&ct, &sk_id)[..]);
+        assert_eq!(diag.message[..], msg[..]);
+        assert!(diag.margin() > 0);
+        assert_eq!(diag.margin(), *diag.distances().iter().min().unwrap());
+    }
+
+    #[test]
+    fn verify_key_accepts_extracted_keys_only() {
+        let kp = LatticeIbe::keygen();
+        let other = LatticeIbe::keygen();
+        let mut id  = [0i64; N0];
+        for i in 0..N0 { id[i] = (i % 13) as i64; }
+        let mut other_id = id;
+        other_id[3] += 1;
+
+        let sk_id = LatticeIbe::extract(&kp, &id);
+        assert!(LatticeIbe::verify_key(kp.master_pk(), &id, &sk_id));
+        // also after a trip through the encodings
+        let pk = public_key_from_bytes(&public_key_to_bytes(kp.master_pk())).unwrap();
+        let decoded = secret_key_from_bytes(&secret_key_to_bytes(&sk_id)).unwrap();
+        assert!(LatticeIbe::verify_key(pk.master_pk(), &id, &decoded));
+
+        assert!(!LatticeIbe::verify_key(kp.master_pk(), &other_id, &sk_id));
+        assert!(!LatticeIbe::verify_key(other.master_pk(), &id, &sk_id));
+        let zero_key = secret_key_from_bytes(&[0u8; SECRET_KEY_BYTES]).unwrap();
+        assert!(!LatticeIbe::verify_key(kp.master_pk(), &id, &zero_key));
+    }
+
+    #[test]
+    fn short_identities_need_hashing() {
+        let kp = IbeMasterKeypair::generate();
+        let mut id  = [0i64; N0];
+        for i in 0..N0 { id[i] = (i % 251) as i64; }
+        let mut msg = [0i64; N0];
+        for i in 0..N0 { msg[i] = (i % 3 == 0) as i64; }
+
+        // without hashing, the all-zero key is valid and decrypts
+        let zero_key = secret_key_from_bytes(&[0u8; SECRET_KEY_BYTES]).unwrap();
+        assert!(verify_key(kp.master_pk(), &id, &zero_key));
+        assert_eq!(decrypt(&encrypt(&msg, kp.master_pk(), &id), &zero_key)[..], msg[..]);
+
+        // through the scheme, it does neither
+        assert!(!LatticeIbe::verify_key(kp.master_pk(), &id, &zero_key));
+        let ct = LatticeIbe::encrypt(&msg, kp.master_pk(), &id);
+        assert_ne!(LatticeIbe::decrypt(&ct, &zero_key)[..], msg[..]);
+        assert_eq!(LatticeIbe::decrypt(&ct, &LatticeIbe::extract(&kp, &id))[..], msg[..]);
+    }
+
+    #[test]
+    fn hash_identity_is_uniform_looking() {
+        let a = hash_identity(&[0i64; N0]);
+        let mut id = [0i64; N0];
+        id[N0 - 1] = 1;
+        let b = hash_identity(&id);
+        assert_ne!(a[..], b[..]);
+        assert_eq!(a[..], hash_identity(&[0i64; N0])[..]);
+        assert!(a.iter().chain(&b).all(|&c| (0..Q0).contains(&c)));
+        // a constant or short output would be caught here
+        assert!(a.iter().filter(|&&c| c >= Q0 / 2).count() > N0 / 4);
+    }
+
+    #[test]
+    fn encoding_roundtrip() {
+        let kp = IbeMasterKeypair::generate();
+
+        let mut msg = [0i64; N0];
+        for i in 0..N0 { msg[i] = (i % 3 == 2) as i64; }
+        let mut id  = [0i64; N0];
+        for i in 0..N0 { id[i] = (i % 11) as i64; }
+
+        // a decoded public key encrypts to the original keypair
+        let pk_bytes = public_key_to_bytes(kp.master_pk());
+        assert_eq!(pk_bytes.len(), PUBLIC_KEY_BYTES);
+        let pk = public_key_from_bytes(&pk_bytes).unwrap();
+        assert_eq!(public_key_to_bytes(pk.master_pk()), pk_bytes);
+        let ct = encrypt(&msg, pk.master_pk(), &id);
+
+        // a decoded ciphertext decrypts under a decoded secret key
+        let ct_bytes = ciphertext_to_bytes(&ct);
+        assert_eq!(ct_bytes.len(), CIPHERTEXT_BYTES);
+        let ct = ciphertext_from_bytes(&ct_bytes).unwrap();
+        assert_eq!(ciphertext_to_bytes(&ct), ct_bytes);
+
+        let sk_bytes = secret_key_to_bytes(&kp.extract_sk_id(&id));
+        let sk_id = secret_key_from_bytes(&sk_bytes).unwrap();
+        assert_eq!(secret_key_to_bytes(&sk_id), sk_bytes);
+
+        assert_eq!(decrypt(&ct, &sk_id)[..], msg[..]);
+    }
+
+    #[test]
+    fn encoding_rejects_malformed_input() {
+        assert_eq!(
+            ciphertext_from_bytes(&[0u8; 3]).err(),
+            Some(IbeError::InvalidLength { expected: CIPHERTEXT_BYTES, actual: 3 })
+        );
+
+        let mut bytes = vec![0u8; PUBLIC_KEY_BYTES];
+        bytes[4 * 7..4 * 8].copy_from_slice(&(Q0 as i32).to_le_bytes());
+        assert_eq!(public_key_from_bytes(&bytes).err(), Some(IbeError::CoefficientOutOfRange { index: 7 }));
+
+        let mut bytes = vec![0u8; SECRET_KEY_BYTES];
+        bytes[0..4].copy_from_slice(&(-(SK_COEFF_BOUND as i32) - 1).to_le_bytes());
+        assert_eq!(secret_key_from_bytes(&bytes).err(), Some(IbeError::CoefficientOutOfRange { index: 0 }));
+    }
+}
\ No newline at end of file
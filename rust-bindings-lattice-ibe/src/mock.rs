@@ -0,0 +1,176 @@
+//! A deterministic, **insecure** in-memory [`IbeScheme`] for protocol tests.
+//!
+//! Master keypairs are derived from a process-wide counter, and a ciphertext is
+//! the message bits XOR a pad derived from the master public key and identity.
+//! Anybody holding the public key can decrypt, but decrypting under the key of
+//! another identity or master key yields garbage, just like the lattice IBE.
+//! Everything runs in microseconds and needs neither NTL nor GMP.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use sha2::{Digest, Sha256};
+
+use crate::{IbeError, IbeScheme, N0};
+
+pub const PUBLIC_KEY_BYTES: usize = 32;
+pub const SECRET_KEY_BYTES: usize = 32;
+pub const CIPHERTEXT_BYTES: usize = N0 / 8;
+
+static KEYGEN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockPublicKey(pub [u8; 32]);
+
+#[derive(Clone, Debug)]
+pub struct MockMasterKeypair {
+    master_pk: MockPublicKey,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockSecretKeyID(pub [u8; 32]);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockCiphertext(pub [u8; CIPHERTEXT_BYTES]);
+
+/// The mock backend, see the module documentation.
+pub struct MockIbe;
+
+fn digest(data: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for d in data {
+        hasher.update(d);
+    }
+    hasher.finalize().into()
+}
+
+fn id_key(master_pk: &MockPublicKey, id: &[i64; N0]) -> MockSecretKeyID {
+    let id_bytes: Vec<u8> = id.iter().flat_map(|c| c.to_le_bytes()).collect();
+    MockSecretKeyID(digest(&[b"mock-ibe id key", &master_pk.0, &id_bytes]))
+}
+
+/// `N0` pseudo-random bits expanded from the identity key
+fn pad(sk_id: &MockSecretKeyID) -> [u8; CIPHERTEXT_BYTES] {
+    let mut out = [0u8; CIPHERTEXT_BYTES];
+    for (block, chunk) in out.chunks_mut(32).enumerate() {
+        let d = digest(&[b"mock-ibe pad", &sk_id.0, &(block as u64).to_le_bytes()]);
+        chunk.copy_from_slice(&d[..chunk.len()]);
+    }
+    out
+}
+
+fn array_from_bytes<const L: usize>(bytes: &[u8]) -> Result<[u8; L], IbeError> {
+    bytes.try_into().map_err(|_| IbeError::InvalidLength { expected: L, actual: bytes.len() })
+}
+
+impl IbeScheme for MockIbe {
+    type MasterKeypair = MockMasterKeypair;
+    type MasterPublicKey = MockPublicKey;
+    type PublicKey = MockPublicKey;
+    type SecretKeyID = MockSecretKeyID;
+    type Ciphertext = MockCiphertext;
+
+    fn keygen() -> MockMasterKeypair {
+        let counter = KEYGEN_COUNTER.fetch_add(1, Ordering::Relaxed);
+        MockMasterKeypair {
+            master_pk: MockPublicKey(digest(&[b"mock-ibe master key", &counter.to_le_bytes()])),
+        }
+    }
+
+    fn master_pk(kp: &MockMasterKeypair) -> &MockPublicKey {
+        &kp.master_pk
+    }
+
+    fn extract(kp: &MockMasterKeypair, id: &[i64; N0]) -> MockSecretKeyID {
+        id_key(&kp.master_pk, id)
+    }
+
+    fn encrypt(msg: &[i64; N0], master_pk: &MockPublicKey, id: &[i64; N0]) -> MockCiphertext {
+        let mut ct = pad(&id_key(master_pk, id));
+        for (i, &m) in msg.iter().enumerate() {
+            ct[i / 8] ^= ((m & 1) as u8) << (i % 8);
+        }
+        MockCiphertext(ct)
+    }
+
+    fn decrypt(ct: &MockCiphertext, sk_id: &MockSecretKeyID) -> [i64; N0] {
+        let pad = pad(sk_id);
+        let mut out = [0i64; N0];
+        for (i, m) in out.iter_mut().enumerate() {
+            *m = (((ct.0[i / 8] ^ pad[i / 8]) >> (i % 8)) & 1) as i64;
+        }
+        out
+    }
+
+    fn verify_key(master_pk: &MockPublicKey, id: &[i64; N0], sk_id: &MockSecretKeyID) -> bool {
+        id_key(master_pk, id) == *sk_id
+    }
+
+    fn public_key_to_bytes(master_pk: &MockPublicKey) -> Vec<u8> {
+        master_pk.0.to_vec()
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Result<MockPublicKey, IbeError> {
+        Ok(MockPublicKey(array_from_bytes(bytes)?))
+    }
+
+    fn secret_key_to_bytes(sk_id: &MockSecretKeyID) -> Vec<u8> {
+        sk_id.0.to_vec()
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Result<MockSecretKeyID, IbeError> {
+        Ok(MockSecretKeyID(array_from_bytes(bytes)?))
+    }
+
+    fn ciphertext_to_bytes(ct: &MockCiphertext) -> Vec<u8> {
+        ct.0.to_vec()
+    }
+
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<MockCiphertext, IbeError> {
+        Ok(MockCiphertext(array_from_bytes(bytes)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_and_wrong_key() {
+        let kp = MockIbe::keygen();
+        let other = MockIbe::keygen();
+        assert_ne!(MockIbe::master_pk(&kp), MockIbe::master_pk(&other));
+
+        let mut msg = [0i64; N0];
+        for (i, m) in msg.iter_mut().enumerate() { *m = (i % 3 == 0) as i64; }
+        let mut id = [0i64; N0];
+        id[0] = 7;
+
+        let ct = MockIbe::encrypt(&msg, MockIbe::master_pk(&kp), &id);
+        assert_eq!(MockIbe::decrypt(&ct, &MockIbe::extract(&kp, &id))[..], msg[..]);
+
+        let mut other_id = id;
+        other_id[1] = 1;
+        assert_ne!(MockIbe::decrypt(&ct, &MockIbe::extract(&kp, &other_id))[..], msg[..]);
+        assert_ne!(MockIbe::decrypt(&ct, &MockIbe::extract(&other, &id))[..], msg[..]);
+
+        assert!(MockIbe::verify_key(MockIbe::master_pk(&kp), &id, &MockIbe::extract(&kp, &id)));
+        assert!(!MockIbe::verify_key(MockIbe::master_pk(&kp), &id, &MockIbe::extract(&kp, &other_id)));
+        assert!(!MockIbe::verify_key(MockIbe::master_pk(&other), &id, &MockIbe::extract(&kp, &id)));
+    }
+
+    #[test]
+    fn encoding_roundtrip() {
+        let kp = MockIbe::keygen();
+        let id = [3i64; N0];
+        let pk = MockIbe::public_key_from_bytes(&MockIbe::public_key_to_bytes(MockIbe::master_pk(&kp))).unwrap();
+        let ct = MockIbe::encrypt(&[1i64; N0], &pk, &id);
+        let ct = MockIbe::ciphertext_from_bytes(&MockIbe::ciphertext_to_bytes(&ct)).unwrap();
+        let sk_id = MockIbe::secret_key_from_bytes(&MockIbe::secret_key_to_bytes(&MockIbe::extract(&kp, &id))).unwrap();
+        assert_eq!(MockIbe::decrypt(&ct, &sk_id), [1i64; N0]);
+
+        assert_eq!(
+            MockIbe::ciphertext_from_bytes(&[0u8; 5]),
+            Err(IbeError::InvalidLength { expected: CIPHERTEXT_BYTES, actual: 5 })
+        );
+    }
+}
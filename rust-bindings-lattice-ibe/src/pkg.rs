@@ -0,0 +1,684 @@
+//! A local Private Key Generator (PKG) for identity key extraction.
+//!
+//! The PKG holds a master keypair and answers extraction requests over a Unix
+//! socket or a localhost TCP connection. Every request is authenticated with an
+//! HMAC-SHA256 under a key shared between the PKG and the requesting client,
+//! carries a strictly increasing nonce against replays, and is checked against
+//! an allow-list of identity prefixes for that client. Every decision is written
+//! to an audit log.
+//!
+//! Secret keys travel in the clear, so the PKG is meant to be reached only over
+//! local transports.
+//!
+//! ## Wire format
+//!
+//! Every message is a frame: a little-endian `u32` length followed by that many
+//! bytes. A request frame holds
+//!
+//! `version (1) | client id length (u16) | client id | identity length (u16) | identity | nonce (u64) | mac (32)`
+//!
+//! where `mac = HMAC(key, MAC_LABEL | version | client id | identity | nonce)`
+//! with every field length-prefixed as above. A response frame holds a status
+//! byte, followed by the encoded secret key if the status is [`STATUS_OK`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{IbeError, IbeScheme, N0};
+
+pub const PROTOCOL_VERSION: u8 = 1;
+const MAC_LABEL: &[u8] = b"lattice-ibe-pkg extract";
+const MAX_FRAME_BYTES: usize = 1 << 16;
+/// default read and write timeout of a served connection
+pub const DEFAULT_IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const STATUS_OK: u8 = 0;
+pub const STATUS_MALFORMED: u8 = 1;
+pub const STATUS_UNAUTHORIZED: u8 = 2;
+pub const STATUS_REPLAY: u8 = 3;
+pub const STATUS_DENIED: u8 = 4;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Errors of the PKG server and client.
+#[derive(Debug)]
+pub enum PkgError {
+    Io(io::Error),
+    /// a request, response or policy file could not be parsed
+    Malformed(String),
+    /// unknown client, or the request MAC does not verify
+    Unauthorized,
+    /// the request nonce is not larger than the last accepted one
+    Replay,
+    /// the policy does not allow this client to extract this identity
+    Denied,
+    /// the returned secret key could not be decoded
+    Ibe(IbeError),
+}
+
+impl fmt::Display for PkgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PkgError::Io(err) => err.fmt(f),
+            PkgError::Malformed(what) => write!(f, "malformed {}", what),
+            PkgError::Unauthorized => write!(f, "request is not authenticated"),
+            PkgError::Replay => write!(f, "request nonce was already used"),
+            PkgError::Denied => write!(f, "identity is not allowed for this client"),
+            PkgError::Ibe(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for PkgError {}
+
+impl From<io::Error> for PkgError {
+    fn from(err: io::Error) -> PkgError {
+        PkgError::Io(err)
+    }
+}
+
+impl From<IbeError> for PkgError {
+    fn from(err: IbeError) -> PkgError {
+        PkgError::Ibe(err)
+    }
+}
+
+/// Maps an identity of at most `N0` bytes to IBE coefficients, one byte per
+/// coefficient, as done by the protocol crates.
+pub fn identity_to_ibe_format(identity: &[u8]) -> Result<[i64; N0], PkgError> {
+    if identity.len() > N0 {
+        return Err(PkgError::Malformed(format!("identity of {} bytes", identity.len())));
+    }
+    let mut id = [0i64; N0];
+    for (c, &b) in id.iter_mut().zip(identity) {
+        *c = b as i64;
+    }
+    Ok(id)
+}
+
+/*----------------------------------------------------------*
+ |  Policy                                                   |
+ *----------------------------------------------------------*/
+
+/// Authentication key and allowed identity prefixes of one client.
+#[derive(Clone, Debug)]
+pub struct ClientPolicy {
+    pub key: [u8; 32],
+    pub allowed_prefixes: Vec<Vec<u8>>,
+}
+
+/// Allow-list of the clients the PKG extracts keys for.
+#[derive(Clone, Debug, Default)]
+pub struct Policy {
+    clients: HashMap<String, ClientPolicy>,
+}
+
+impl Policy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// allows `client_id`, authenticated by `key`, to extract every identity
+    /// that starts with one of `allowed_prefixes` (an empty prefix allows all)
+    pub fn allow(&mut self, client_id: &str, key: [u8; 32], allowed_prefixes: Vec<Vec<u8>>) {
+        self.clients.insert(client_id.to_string(), ClientPolicy { key, allowed_prefixes });
+    }
+
+    /// Parses one client per line: `<client id> <hex key> <hex prefix>,<hex prefix>,...`,
+    /// where the prefix `*` allows every identity. Empty lines and lines
+    /// starting with `#` are skipped.
+    pub fn parse(text: &str) -> Result<Self, PkgError> {
+        let mut policy = Policy::new();
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let malformed = || PkgError::Malformed(format!("policy line {}", line_no + 1));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 3 {
+                return Err(malformed());
+            }
+            let key: [u8; 32] = from_hex(fields[1])
+                .and_then(|k| k.try_into().ok())
+                .ok_or_else(malformed)?;
+            let prefixes = fields[2]
+                .split(',')
+                .map(|p| if p == "*" { Some(Vec::new()) } else { from_hex(p) })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(malformed)?;
+            policy.allow(fields[0], key, prefixes);
+        }
+        Ok(policy)
+    }
+
+    fn client(&self, client_id: &str) -> Option<&ClientPolicy> {
+        self.clients.get(client_id)
+    }
+}
+
+impl ClientPolicy {
+    fn permits(&self, identity: &[u8]) -> bool {
+        self.allowed_prefixes.iter().any(|p| identity.starts_with(p))
+    }
+}
+
+/*----------------------------------------------------------*
+ |  Wire format                                              |
+ *----------------------------------------------------------*/
+
+struct ExtractRequest {
+    client_id: String,
+    identity: Vec<u8>,
+    nonce: u64,
+    mac: [u8; 32],
+}
+
+fn mac_input(client_id: &str, identity: &[u8], nonce: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAC_LABEL);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&(client_id.len() as u16).to_le_bytes());
+    out.extend_from_slice(client_id.as_bytes());
+    out.extend_from_slice(&(identity.len() as u16).to_le_bytes());
+    out.extend_from_slice(identity);
+    out.extend_from_slice(&nonce.to_le_bytes());
+    out
+}
+
+fn compute_mac(key: &[u8; 32], client_id: &str, identity: &[u8], nonce: u64) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&mac_input(client_id, identity, nonce));
+    mac.finalize().into_bytes().into()
+}
+
+impl ExtractRequest {
+    fn encode(&self) -> Vec<u8> {
+        // the MAC input already is the request minus its label and MAC
+        let mut out = mac_input(&self.client_id, &self.identity, self.nonce).split_off(MAC_LABEL.len());
+        out.extend_from_slice(&self.mac);
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut rest = bytes;
+        let mut take = |len: usize| -> Option<&[u8]> {
+            if rest.len() < len {
+                return None;
+            }
+            let (head, tail) = rest.split_at(len);
+            rest = tail;
+            Some(head)
+        };
+        if take(1)?[0] != PROTOCOL_VERSION {
+            return None;
+        }
+        let len = u16::from_le_bytes(take(2)?.try_into().ok()?) as usize;
+        let client_id = String::from_utf8(take(len)?.to_vec()).ok()?;
+        let len = u16::from_le_bytes(take(2)?.try_into().ok()?) as usize;
+        let identity = take(len)?.to_vec();
+        let nonce = u64::from_le_bytes(take(8)?.try_into().ok()?);
+        let mac = take(32)?.try_into().ok()?;
+        if !rest.is_empty() {
+            return None;
+        }
+        Some(ExtractRequest { client_id, identity, nonce, mac })
+    }
+}
+
+fn write_frame<W: Write>(w: &mut W, payload: &[u8]) -> io::Result<()> {
+    w.write_all(&(payload.len() as u32).to_le_bytes())?;
+    w.write_all(payload)?;
+    w.flush()
+}
+
+/// reads one frame, or `None` on a clean end of stream
+fn read_frame<R: Read>(r: &mut R) -> Result<Option<Vec<u8>>, PkgError> {
+    let mut len = [0u8; 4];
+    match r.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_BYTES {
+        return Err(PkgError::Malformed(format!("frame of {} bytes", len)));
+    }
+    let mut payload = vec![0u8; len];
+    r.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/*----------------------------------------------------------*
+ |  Server                                                   |
+ *----------------------------------------------------------*/
+
+/// The key authority: holds the master keypair and serves extraction requests.
+///
+/// Connections are served one at a time, as the lattice keypair may not be
+/// shared across threads. Every read and write on a connection is bounded by
+/// the I/O timeout, so an idle or stalled client holds up the others for at
+/// most that long.
+pub struct Pkg<I: IbeScheme> {
+    keypair: I::MasterKeypair,
+    policy: Policy,
+    audit: Box<dyn Write + Send>,
+    last_nonce: HashMap<String, u64>,
+    io_timeout: Duration,
+}
+
+impl<I: IbeScheme> Pkg<I> {
+    pub fn new(keypair: I::MasterKeypair, policy: Policy, audit: Box<dyn Write + Send>) -> Self {
+        Self { keypair, policy, audit, last_nonce: HashMap::new(), io_timeout: DEFAULT_IO_TIMEOUT }
+    }
+
+    /// sets the read and write timeout of served connections
+    ///
+    /// # Panics
+    /// If `timeout` is zero.
+    pub fn with_io_timeout(mut self, timeout: Duration) -> Self {
+        assert!(!timeout.is_zero(), "the I/O timeout must be positive");
+        self.io_timeout = timeout;
+        self
+    }
+
+    /// the master public key that senders encrypt under
+    pub fn master_pk(&self) -> &I::MasterPublicKey {
+        I::master_pk(&self.keypair)
+    }
+
+    /// Answers one request frame with a response frame.
+    pub fn handle_request(&mut self, request: &[u8]) -> Vec<u8> {
+        let (status, payload) = match self.process(request) {
+            Ok(sk_bytes) => (STATUS_OK, sk_bytes),
+            Err(PkgError::Unauthorized) => (STATUS_UNAUTHORIZED, Vec::new()),
+            Err(PkgError::Replay) => (STATUS_REPLAY, Vec::new()),
+            Err(PkgError::Denied) => (STATUS_DENIED, Vec::new()),
+            Err(_) => (STATUS_MALFORMED, Vec::new()),
+        };
+        let mut response = vec![status];
+        response.extend(payload);
+        response
+    }
+
+    fn process(&mut self, request: &[u8]) -> Result<Vec<u8>, PkgError> {
+        let request = match ExtractRequest::decode(request) {
+            Some(request) => request,
+            None => {
+                self.log("-", &[], "rejected: malformed request");
+                return Err(PkgError::Malformed("request".to_string()));
+            }
+        };
+        let (client_id, identity) = (request.client_id.as_str(), request.identity.as_slice());
+
+        let client = match self.policy.client(client_id) {
+            Some(client) => client.clone(),
+            None => {
+                self.log(client_id, identity, "rejected: unknown client");
+                return Err(PkgError::Unauthorized);
+            }
+        };
+        let mut mac = HmacSha256::new_from_slice(&client.key).expect("HMAC accepts keys of any length");
+        mac.update(&mac_input(client_id, identity, request.nonce));
+        if mac.verify_slice(&request.mac).is_err() {
+            self.log(client_id, identity, "rejected: bad mac");
+            return Err(PkgError::Unauthorized);
+        }
+        if self.last_nonce.get(client_id).is_some_and(|&last| request.nonce <= last) {
+            self.log(client_id, identity, "rejected: replayed nonce");
+            return Err(PkgError::Replay);
+        }
+        self.last_nonce.insert(client_id.to_string(), request.nonce);
+
+        if !client.permits(identity) {
+            self.log(client_id, identity, "denied: identity not allowed");
+            return Err(PkgError::Denied);
+        }
+        let id = match identity_to_ibe_format(identity) {
+            Ok(id) => id,
+            Err(err) => {
+                self.log(client_id, identity, "rejected: identity too long");
+                return Err(err);
+            }
+        };
+        let sk_id = I::extract(&self.keypair, &id);
+        self.log(client_id, identity, "granted");
+        Ok(I::secret_key_to_bytes(&sk_id))
+    }
+
+    fn log(&mut self, client_id: &str, identity: &[u8], decision: &str) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        // a failing audit sink must not take the PKG down with it
+        let _ = writeln!(self.audit, "{} client={} identity={} {}", now, client_id, to_hex(identity), decision);
+        let _ = self.audit.flush();
+    }
+
+    /// Serves request frames on `stream` until the peer closes it.
+    pub fn handle_connection<S: Read + Write>(&mut self, stream: &mut S) -> Result<(), PkgError> {
+        while let Some(request) = read_frame(stream)? {
+            let response = self.handle_request(&request);
+            write_frame(stream, &response)?;
+        }
+        Ok(())
+    }
+
+    /// Serves connections from `listener` forever. A failing connection, or a
+    /// failing `accept`, is logged and dropped without stopping the PKG.
+    pub fn serve_tcp(&mut self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream.and_then(|s| {
+                s.set_read_timeout(Some(self.io_timeout))?;
+                s.set_write_timeout(Some(self.io_timeout))?;
+                Ok(s)
+            });
+            self.serve_stream(stream);
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub fn serve_unix(&mut self, listener: UnixListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream.and_then(|s| {
+                s.set_read_timeout(Some(self.io_timeout))?;
+                s.set_write_timeout(Some(self.io_timeout))?;
+                Ok(s)
+            });
+            self.serve_stream(stream);
+        }
+        Ok(())
+    }
+
+    fn serve_stream<S: Read + Write>(&mut self, stream: io::Result<S>) {
+        let result = match stream {
+            Ok(mut stream) => self.handle_connection(&mut stream),
+            Err(err) => {
+                self.log("-", &[], &format!("accept failed: {}", err));
+                return;
+            }
+        };
+        if let Err(err) = result {
+            self.log("-", &[], &format!("connection dropped: {}", err));
+        }
+    }
+}
+
+/*----------------------------------------------------------*
+ |  Client                                                   |
+ *----------------------------------------------------------*/
+
+/// Requests identity secret keys from a [`Pkg`].
+pub struct PkgClient {
+    client_id: String,
+    key: [u8; 32],
+    last_nonce: u64,
+}
+
+impl PkgClient {
+    pub fn new(client_id: &str, key: [u8; 32]) -> Self {
+        Self { client_id: client_id.to_string(), key, last_nonce: 0 }
+    }
+
+    /// nonces follow the wall clock, so they keep increasing across restarts
+    fn next_nonce(&mut self) -> u64 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        self.last_nonce = now.max(self.last_nonce + 1);
+        self.last_nonce
+    }
+
+    /// the request frame payload for `identity`
+    pub fn request(&mut self, identity: &[u8]) -> Vec<u8> {
+        let nonce = self.next_nonce();
+        ExtractRequest {
+            client_id: self.client_id.clone(),
+            identity: identity.to_vec(),
+            nonce,
+            mac: compute_mac(&self.key, &self.client_id, identity, nonce),
+        }
+        .encode()
+    }
+
+    /// Extracts the secret key of `identity` over an established connection.
+    pub fn extract<I: IbeScheme, S: Read + Write>(&mut self, stream: &mut S, identity: &[u8]) -> Result<I::SecretKeyID, PkgError> {
+        identity_to_ibe_format(identity)?;
+        write_frame(stream, &self.request(identity))?;
+        let response = read_frame(stream)?
+            .ok_or_else(|| PkgError::Malformed("empty response".to_string()))?;
+        match response.split_first() {
+            Some((&STATUS_OK, sk_bytes)) => Ok(I::secret_key_from_bytes(sk_bytes)?),
+            Some((&STATUS_UNAUTHORIZED, _)) => Err(PkgError::Unauthorized),
+            Some((&STATUS_REPLAY, _)) => Err(PkgError::Replay),
+            Some((&STATUS_DENIED, _)) => Err(PkgError::Denied),
+            _ => Err(PkgError::Malformed("response".to_string())),
+        }
+    }
+
+    pub fn extract_tcp<I: IbeScheme>(&mut self, addr: impl ToSocketAddrs, identity: &[u8]) -> Result<I::SecretKeyID, PkgError> {
+        let mut stream = TcpStream::connect(addr)?;
+        self.extract::<I, _>(&mut stream, identity)
+    }
+
+    #[cfg(unix)]
+    pub fn extract_unix<I: IbeScheme>(&mut self, path: impl AsRef<Path>, identity: &[u8]) -> Result<I::SecretKeyID, PkgError> {
+        let mut stream = UnixStream::connect(path)?;
+        self.extract::<I, _>(&mut stream, identity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[cfg(feature = "mock-ibe")]
+    type TestIbe = crate::mock::MockIbe;
+    #[cfg(not(feature = "mock-ibe"))]
+    type TestIbe = crate::LatticeIbe;
+
+    const KEY_A: [u8; 32] = [1u8; 32];
+    const KEY_B: [u8; 32] = [2u8; 32];
+
+    /// audit sink whose contents the test can read back
+    #[derive(Clone, Default)]
+    struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedLog {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedLog {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap().lines().map(String::from).collect()
+        }
+    }
+
+    fn test_policy() -> Policy {
+        Policy::parse(&format!(
+            "# test policy\nalice {} 706f6c6c2f,766f7465\nbob {} *\n",
+            to_hex(&KEY_A),
+            to_hex(&KEY_B)
+        ))
+        .unwrap()
+    }
+
+    /// encrypts a message to `identity` and checks that `sk_id` decrypts it
+    fn assert_key_decrypts(master_pk: &[u8], identity: &[u8], sk_id: &<TestIbe as IbeScheme>::SecretKeyID) {
+        let master_pk = TestIbe::public_key_from_bytes(master_pk).unwrap();
+        let mut msg = [0i64; N0];
+        for (i, m) in msg.iter_mut().enumerate() { *m = (i % 5 == 0) as i64; }
+        let id = identity_to_ibe_format(identity).unwrap();
+        let ct = TestIbe::encrypt(&msg, std::borrow::Borrow::borrow(&master_pk), &id);
+        assert_eq!(TestIbe::decrypt(&ct, sk_id)[..], msg[..]);
+    }
+
+    #[test]
+    fn tcp_extraction_and_policy() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let log = SharedLog::default();
+        let (pk_tx, pk_rx) = std::sync::mpsc::channel();
+
+        let audit = log.clone();
+        thread::spawn(move || {
+            // the keypair is created on the serving thread, it need not be Send
+            let mut pkg = Pkg::<TestIbe>::new(TestIbe::keygen(), test_policy(), Box::new(audit));
+            pk_tx.send(TestIbe::public_key_to_bytes(pkg.master_pk())).unwrap();
+            pkg.serve_tcp(listener).unwrap();
+        });
+        let master_pk = pk_rx.recv().unwrap();
+
+        // allowed prefixes
+        let mut alice = PkgClient::new("alice", KEY_A);
+        let sk_id = alice.extract_tcp::<TestIbe>(addr, b"poll/42/server/3").unwrap();
+        assert_key_decrypts(&master_pk, b"poll/42/server/3", &sk_id);
+        let sk_id = alice.extract_tcp::<TestIbe>(addr, b"vote").unwrap();
+        assert_key_decrypts(&master_pk, b"vote", &sk_id);
+
+        // identity outside alice's prefixes, but within bob's wildcard
+        assert!(matches!(alice.extract_tcp::<TestIbe>(addr, b"admin"), Err(PkgError::Denied)));
+        let mut bob = PkgClient::new("bob", KEY_B);
+        let sk_id = bob.extract_tcp::<TestIbe>(addr, b"admin").unwrap();
+        assert_key_decrypts(&master_pk, b"admin", &sk_id);
+
+        // wrong key and unknown client
+        let mut mallory = PkgClient::new("alice", KEY_B);
+        assert!(matches!(mallory.extract_tcp::<TestIbe>(addr, b"vote"), Err(PkgError::Unauthorized)));
+        let mut eve = PkgClient::new("eve", KEY_A);
+        assert!(matches!(eve.extract_tcp::<TestIbe>(addr, b"vote"), Err(PkgError::Unauthorized)));
+
+        // a recorded request cannot be replayed
+        let request = alice.request(b"vote");
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write_frame(&mut stream, &request).unwrap();
+        assert_eq!(read_frame(&mut stream).unwrap().unwrap()[0], STATUS_OK);
+        write_frame(&mut stream, &request).unwrap();
+        assert_eq!(read_frame(&mut stream).unwrap().unwrap(), vec![STATUS_REPLAY]);
+        drop(stream);
+
+        // garbage is rejected, and the connection keeps working
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write_frame(&mut stream, b"not a request").unwrap();
+        assert_eq!(read_frame(&mut stream).unwrap().unwrap(), vec![STATUS_MALFORMED]);
+        let sk_id = bob.extract::<TestIbe, _>(&mut stream, b"x").unwrap();
+        assert_key_decrypts(&master_pk, b"x", &sk_id);
+        drop(stream);
+
+        let lines = log.lines();
+        let decisions: Vec<&str> = lines.iter().map(|l| l.splitn(4, ' ').nth(3).unwrap()).collect();
+        assert_eq!(decisions, vec![
+            "granted",
+            "granted",
+            "denied: identity not allowed",
+            "granted",
+            "rejected: bad mac",
+            "rejected: unknown client",
+            "granted",
+            "rejected: replayed nonce",
+            "rejected: malformed request",
+            "granted",
+        ]);
+        assert!(lines[0].contains("client=alice identity=706f6c6c2f34322f7365727665722f33"));
+    }
+
+    #[test]
+    fn idle_client_does_not_block_others() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let log = SharedLog::default();
+        let (pk_tx, pk_rx) = std::sync::mpsc::channel();
+
+        let audit = log.clone();
+        thread::spawn(move || {
+            let mut pkg = Pkg::<TestIbe>::new(TestIbe::keygen(), test_policy(), Box::new(audit))
+                .with_io_timeout(Duration::from_millis(200));
+            pk_tx.send(TestIbe::public_key_to_bytes(pkg.master_pk())).unwrap();
+            pkg.serve_tcp(listener).unwrap();
+        });
+        let master_pk = pk_rx.recv().unwrap();
+
+        // one client connects and sends nothing, another stops halfway through a frame
+        let idle = TcpStream::connect(addr).unwrap();
+        let mut stalled = TcpStream::connect(addr).unwrap();
+        stalled.write_all(&100u32.to_le_bytes()).unwrap();
+        stalled.write_all(&[PROTOCOL_VERSION]).unwrap();
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let mut alice = PkgClient::new("alice", KEY_A);
+        let sk_id = alice.extract::<TestIbe, _>(&mut stream, b"vote").unwrap();
+        assert_key_decrypts(&master_pk, b"vote", &sk_id);
+        drop((idle, stalled));
+
+        let lines = log.lines();
+        assert_eq!(lines.iter().filter(|l| l.contains("connection dropped")).count(), 2);
+        assert!(lines.last().unwrap().ends_with("granted"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_extraction() {
+        let path = std::env::temp_dir().join(format!("lattice-ibe-pkg-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let (pk_tx, pk_rx) = std::sync::mpsc::channel();
+
+        thread::spawn(move || {
+            let mut pkg = Pkg::<TestIbe>::new(TestIbe::keygen(), test_policy(), Box::new(io::sink()));
+            pk_tx.send(TestIbe::public_key_to_bytes(pkg.master_pk())).unwrap();
+            pkg.serve_unix(listener).unwrap();
+        });
+        let master_pk = pk_rx.recv().unwrap();
+
+        let mut alice = PkgClient::new("alice", KEY_A);
+        let sk_id = alice.extract_unix::<TestIbe>(&path, b"vote/7").unwrap();
+        assert_key_decrypts(&master_pk, b"vote/7", &sk_id);
+        assert!(matches!(alice.extract_unix::<TestIbe>(&path, b"other"), Err(PkgError::Denied)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn policy_parsing() {
+        assert!(Policy::parse("alice 00 *").is_err());
+        assert!(Policy::parse(&format!("alice {} zz", to_hex(&KEY_A))).is_err());
+        assert!(Policy::parse(&format!("alice {}", to_hex(&KEY_A))).is_err());
+
+        let policy = test_policy();
+        let alice = policy.client("alice").unwrap();
+        assert_eq!(alice.key, KEY_A);
+        assert!(alice.permits(b"poll/1") && alice.permits(b"vote") && !alice.permits(b"pol"));
+        assert!(policy.client("bob").unwrap().permits(b""));
+        assert!(policy.client("carol").is_none());
+    }
+}
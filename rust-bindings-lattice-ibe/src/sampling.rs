@@ -0,0 +1,210 @@
+//! Safe wrappers around the discrete Gaussian samplers of `Sampling.cc`.
+//!
+//! They produce all of the key-generation, extraction and encryption noise of
+//! the scheme, so they are exposed (behind the `sampler-tests` feature) only to
+//! check their output distributions from Rust.
+//!
+//! - [`sample0`] - `D_{sigma_2}^+`, i.e. `x >= 0` with probability ~ `2^{-x^2}`.
+//! - [`sample1`] - `D_{k sigma_2}^+`, i.e. `x >= 0` with probability ~ `2^{-(x/k)^2}`.
+//! - [`sample2`] - `D_{k sigma_2}`, i.e. `x` in Z with probability ~ `2^{-(x/k)^2}`.
+//! - [`sample3`] - `D_{sigma}`, i.e. `x` in Z with probability ~ `e^{-x^2 / 2 sigma^2}`.
+//! - [`sample4`] - `D_{c, sigma}`, i.e. `x` in Z with probability ~ `e^{-(x-c)^2 / 2 sigma^2}`.
+
+use autocxx::{c_uint, c_ulong};
+
+use crate::lattice_ibe_ffi;
+
+/// `sigma_2 = sqrt(1 / (2 ln 2))` (`sigma_1` in `params.h`), the width of [`sample0`].
+pub const SIGMA_1: f64 = 0.849_321_800_288_019_f64;
+
+/// Samples from `D_{sigma_2}^+`, consuming the random bits of `alea`.
+pub fn sample0(alea: u64) -> u32 {
+    lattice_ibe_ffi::sample0(c_ulong(alea as _)).0
+}
+
+/// Samples from `D_{k sigma_2}^+`.
+///
+/// # Panics
+/// If `k` is zero.
+pub fn sample1(k: u32) -> u32 {
+    assert!(k > 0, "sample1: k must be positive");
+    lattice_ibe_ffi::sample1(c_uint(k)).0
+}
+
+/// Samples from `D_{k sigma_2}`.
+///
+/// # Panics
+/// If `k` is zero.
+pub fn sample2(k: u32) -> i32 {
+    assert!(k > 0, "sample2: k must be positive");
+    lattice_ibe_ffi::sample2(c_uint(k)).0
+}
+
+/// Samples from `D_{sigma}`.
+///
+/// # Panics
+/// If `sigma` is not a positive finite number.
+pub fn sample3(sigma: f64) -> i32 {
+    assert!(sigma.is_finite() && sigma > 0.0, "sample3: sigma must be positive and finite");
+    lattice_ibe_ffi::sample3(sigma).0
+}
+
+/// Samples from `D_{c, sigma}`.
+///
+/// # Panics
+/// If `sigma` is not a positive finite number or `c` is not finite.
+pub fn sample4(c: f64, sigma: f64) -> i32 {
+    assert!(c.is_finite(), "sample4: c must be finite");
+    assert!(sigma.is_finite() && sigma > 0.0, "sample4: sigma must be positive and finite");
+    lattice_ibe_ffi::sample4(c, sigma).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const SAMPLES: usize = 200_000;
+    // one-sided standard normal quantile for p = 1e-6; every check below
+    // fails spuriously with probability of that order.
+    const Z_CRIT: f64 = 4.753;
+
+    /// Compares `samples` against the distribution proportional to `weight`
+    /// over `support`: mean, variance, two-sided tail mass beyond two standard
+    /// deviations, and a chi-squared goodness-of-fit test.
+    fn check_distribution(name: &str, samples: &[i64], support: std::ops::RangeInclusive<i64>, weight: impl Fn(i64) -> f64) {
+        let xs: Vec<i64> = support.collect();
+        let total: f64 = xs.iter().map(|&x| weight(x)).sum();
+        let pmf: Vec<f64> = xs.iter().map(|&x| weight(x) / total).collect();
+
+        // target moments
+        let mu: f64 = xs.iter().zip(&pmf).map(|(&x, p)| x as f64 * p).sum();
+        let var: f64 = xs.iter().zip(&pmf).map(|(&x, p)| (x as f64 - mu).powi(2) * p).sum();
+        let mu4: f64 = xs.iter().zip(&pmf).map(|(&x, p)| (x as f64 - mu).powi(4) * p).sum();
+        let sd = var.sqrt();
+        let tail: f64 = xs.iter().zip(&pmf)
+            .filter(|&(&x, _)| (x as f64 - mu).abs() > 2.0 * sd)
+            .map(|(_, p)| p)
+            .sum();
+
+        // empirical moments
+        let n = samples.len() as f64;
+        let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / n;
+        let s2 = samples.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        let tail_obs = samples.iter().filter(|&&x| (x as f64 - mu).abs() > 2.0 * sd).count() as f64 / n;
+
+        assert!((mean - mu).abs() < Z_CRIT * sd / n.sqrt(),
+            "{}: mean {} too far from {}", name, mean, mu);
+        assert!((s2 - var).abs() < Z_CRIT * ((mu4 - var * var) / n).sqrt(),
+            "{}: variance {} too far from {}", name, s2, var);
+        assert!((tail_obs - tail).abs() < Z_CRIT * (tail * (1.0 - tail) / n).sqrt(),
+            "{}: tail mass {} too far from {}", name, tail_obs, tail);
+
+        // chi-squared over the support, merging neighbouring values until
+        // every bin expects at least 5 samples
+        let lo = xs[0];
+        let hi = xs[xs.len() - 1];
+        let mut counts = vec![0f64; xs.len()];
+        for &x in samples {
+            counts[(x.clamp(lo, hi) - lo) as usize] += 1.0;
+        }
+        let mut bins: Vec<(f64, f64)> = Vec::new();
+        let (mut exp_acc, mut obs_acc) = (0f64, 0f64);
+        for (p, c) in pmf.iter().zip(&counts) {
+            exp_acc += p * n;
+            obs_acc += c;
+            if exp_acc >= 5.0 {
+                bins.push((exp_acc, obs_acc));
+                exp_acc = 0.0;
+                obs_acc = 0.0;
+            }
+        }
+        if let Some(last) = bins.last_mut() {
+            last.0 += exp_acc;
+            last.1 += obs_acc;
+        }
+        let chi2: f64 = bins.iter().map(|(e, o)| (o - e).powi(2) / e).sum();
+        let df = (bins.len() - 1) as f64;
+        // Wilson-Hilferty approximation of the chi-squared quantile
+        let h = 2.0 / (9.0 * df);
+        let crit = df * (1.0 - h + Z_CRIT * h.sqrt()).powi(3);
+        assert!(chi2 < crit,
+            "{}: chi-squared {} exceeds critical value {} ({} degrees of freedom)", name, chi2, crit, df);
+    }
+
+    #[test]
+    fn sample0_distribution() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let samples: Vec<i64> = (0..SAMPLES).map(|_| sample0(rng.gen()) as i64).collect();
+        check_distribution("sample0", &samples, 0..=10, |x| (-((x * x) as f64) * std::f64::consts::LN_2).exp());
+    }
+
+    #[test]
+    fn sample1_distribution() {
+        for k in [1u32, 3, 8] {
+            let samples: Vec<i64> = (0..SAMPLES).map(|_| sample1(k) as i64).collect();
+            let kf = k as f64;
+            check_distribution(&format!("sample1(k = {})", k), &samples, 0..=(10 * k as i64),
+                |x| (-(x as f64 / kf).powi(2) * std::f64::consts::LN_2).exp());
+        }
+    }
+
+    #[test]
+    fn sample2_distribution() {
+        for k in [1u32, 3, 8] {
+            let samples: Vec<i64> = (0..SAMPLES).map(|_| sample2(k) as i64).collect();
+            let kf = k as f64;
+            let bound = 10 * k as i64;
+            check_distribution(&format!("sample2(k = {})", k), &samples, -bound..=bound,
+                |x| (-(x as f64 / kf).powi(2) * std::f64::consts::LN_2).exp());
+        }
+    }
+
+    #[test]
+    fn sample3_distribution() {
+        for sigma in [1.0f64, 2.5, 6.0] {
+            let samples: Vec<i64> = (0..SAMPLES).map(|_| sample3(sigma) as i64).collect();
+            let bound = (12.0 * sigma).ceil() as i64;
+            check_distribution(&format!("sample3(sigma = {})", sigma), &samples, -bound..=bound,
+                |x| (-(x as f64).powi(2) / (2.0 * sigma * sigma)).exp());
+        }
+    }
+
+    #[test]
+    fn sample4_distribution() {
+        // sigma is kept small: Sample4 is only ever called with narrow widths
+        // from GPV and prints every candidate beyond |8|.
+        for (c, sigma) in [(0.0f64, 1.2f64), (0.5, 1.5), (-17.3, 1.0), (1234.75, 1.7)] {
+            let samples: Vec<i64> = (0..SAMPLES).map(|_| sample4(c, sigma) as i64).collect();
+            let lo = (c - 12.0 * sigma).floor() as i64;
+            let hi = (c + 12.0 * sigma).ceil() as i64;
+            check_distribution(&format!("sample4(c = {}, sigma = {})", c, sigma), &samples, lo..=hi,
+                |x| (-(x as f64 - c).powi(2) / (2.0 * sigma * sigma)).exp());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "k must be positive")]
+    fn sample1_rejects_zero_k() {
+        sample1(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "k must be positive")]
+    fn sample2_rejects_zero_k() {
+        sample2(0);
+    }
+
+    #[test]
+    fn sample3_and_sample4_reject_bad_sigma() {
+        for sigma in [0.0f64, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(std::panic::catch_unwind(|| sample3(sigma)).is_err());
+            assert!(std::panic::catch_unwind(|| sample4(0.0, sigma)).is_err());
+        }
+    }
+
+    #[test]
+    fn sigma_1_matches_definition() {
+        assert!((SIGMA_1 - (1.0 / (2.0 * std::f64::consts::LN_2)).sqrt()).abs() < 1e-12);
+    }
+}
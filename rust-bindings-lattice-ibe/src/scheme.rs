@@ -0,0 +1,58 @@
+use std::borrow::Borrow;
+use std::fmt;
+
+use crate::N0;
+
+/// Error returned when decoding IBE keys or ciphertexts from bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IbeError {
+    /// the encoding does not have the expected number of bytes
+    InvalidLength { expected: usize, actual: usize },
+    /// the coefficient at `index` lies outside the range allowed for the object
+    CoefficientOutOfRange { index: usize },
+}
+
+impl fmt::Display for IbeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IbeError::InvalidLength { expected, actual } =>
+                write!(f, "invalid encoding length: expected {} bytes, got {}", expected, actual),
+            IbeError::CoefficientOutOfRange { index } =>
+                write!(f, "coefficient {} is out of range", index),
+        }
+    }
+}
+
+impl std::error::Error for IbeError {}
+
+/// An identity-based encryption scheme over `N0`-coefficient messages and
+/// identities, as used by the PVSS and polling protocols.
+///
+/// Messages are vectors of bits (one per coefficient); identities are
+/// arbitrary coefficient vectors. All functions are associated functions,
+/// so implementations are plain marker types such as `LatticeIbe`.
+pub trait IbeScheme {
+    type MasterKeypair;
+    /// master public key, as borrowed from a keypair
+    type MasterPublicKey;
+    /// owned master public key, as decoded from bytes
+    type PublicKey: Borrow<Self::MasterPublicKey>;
+    type SecretKeyID;
+    type Ciphertext;
+
+    fn keygen() -> Self::MasterKeypair;
+    fn master_pk(kp: &Self::MasterKeypair) -> &Self::MasterPublicKey;
+    fn extract(kp: &Self::MasterKeypair, id: &[i64; N0]) -> Self::SecretKeyID;
+    fn encrypt(msg: &[i64; N0], master_pk: &Self::MasterPublicKey, id: &[i64; N0]) -> Self::Ciphertext;
+    fn decrypt(ct: &Self::Ciphertext, sk_id: &Self::SecretKeyID) -> [i64; N0];
+    /// whether `sk_id` is a valid key for `id` under `master_pk`, so that a
+    /// revealed key can be checked by anybody
+    fn verify_key(master_pk: &Self::MasterPublicKey, id: &[i64; N0], sk_id: &Self::SecretKeyID) -> bool;
+
+    fn public_key_to_bytes(master_pk: &Self::MasterPublicKey) -> Vec<u8>;
+    fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, IbeError>;
+    fn secret_key_to_bytes(sk_id: &Self::SecretKeyID) -> Vec<u8>;
+    fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKeyID, IbeError>;
+    fn ciphertext_to_bytes(ct: &Self::Ciphertext) -> Vec<u8>;
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Self::Ciphertext, IbeError>;
+}